@@ -1,11 +1,14 @@
 //! `anchor_client` provides an RPC client to send transactions and fetch
 //! deserialized accounts from Solana programs written in `anchor_lang`.
 
+use anchor_lang::accounts::orphan_account::{Codec, OrphanAccount};
+use anchor_lang::error::ErrorCode;
+use anchor_lang::solana_program::account_info::AccountInfo;
 use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
 use anchor_lang::solana_program::program_error::ProgramError;
 use anchor_lang::solana_program::pubkey::Pubkey;
 use anchor_lang::solana_program::system_program;
-use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anchor_lang::{AccountDeserialize, Accounts, Discriminator, InstructionData, ToAccountMetas};
 use regex::Regex;
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::client_error::ClientError as SolanaClientError;
@@ -17,11 +20,12 @@ use solana_client::rpc_config::{
 };
 use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
 use solana_client::rpc_response::{Response as RpcResponse, RpcLogsResponse};
-use solana_sdk::account::Account;
+use solana_sdk::account::{Account, AccountSharedData, ReadableAccount};
 use solana_sdk::bs58;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::signature::{Signature, Signer};
 use solana_sdk::transaction::Transaction;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::Into;
 use std::iter::Map;
 use std::rc::Rc;
@@ -588,9 +592,171 @@ impl<'a> RequestBuilder<'a> {
     }
 }
 
+/// Reads orphan accounts from [`AccountSharedData`], as used by
+/// `solana-program-test` and the SVM, instead of an [`AccountInfo`].
+pub trait OrphanAccountExt<T> {
+    /// Deserializes `account`, stored at `key`, running the same checks as
+    /// `OrphanAccount::try_from`.
+    fn try_from_shared(key: &Pubkey, account: &AccountSharedData) -> anchor_lang::Result<T>;
+}
+
+impl<'info, T: Clone, C: Codec<T>> OrphanAccountExt<T> for OrphanAccount<'info, T, C> {
+    fn try_from_shared(key: &Pubkey, account: &AccountSharedData) -> anchor_lang::Result<T> {
+        let owner = *account.owner();
+        let mut lamports = account.lamports();
+        let mut data = account.data().to_vec();
+        let info = AccountInfo::new(
+            key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            account.executable(),
+            account.rent_epoch(),
+        );
+        OrphanAccount::<T, C>::try_from(&info).map(OrphanAccount::into_inner)
+    }
+}
+
+/// Runs the account validation of `T` over the accounts of `instruction`, as
+/// `try_accounts` would on chain, without sending it. `account_datas` holds
+/// the state of each account, in the order of `instruction.accounts`.
+///
+/// This surfaces orphan accounts that fail to deserialize or fail their owner
+/// checks, as well as accounts passed in the wrong order, before the
+/// transaction is sent. Checks needing the runtime, such as CPIs made by
+/// `init`, can't run off chain.
+pub fn validate_orphan_accounts<'info, T: Accounts<'info>>(
+    instruction: &'info Instruction,
+    account_datas: &'info mut [Account],
+) -> anchor_lang::Result<()> {
+    if account_datas.len() != instruction.accounts.len() {
+        return Err(
+            anchor_lang::error::Error::from(ErrorCode::AccountNotEnoughKeys)
+                .with_values((account_datas.len(), instruction.accounts.len())),
+        );
+    }
+    let infos: Vec<AccountInfo<'info>> = instruction
+        .accounts
+        .iter()
+        .zip(account_datas.iter_mut())
+        .map(|(meta, account)| {
+            let Account {
+                lamports,
+                data,
+                owner,
+                executable,
+                rent_epoch,
+            } = account;
+            AccountInfo::new(
+                &meta.pubkey,
+                meta.is_signer,
+                meta.is_writable,
+                lamports,
+                data,
+                owner,
+                *executable,
+                *rent_epoch,
+            )
+        })
+        .collect();
+    T::try_accounts(
+        &instruction.program_id,
+        &mut &infos[..],
+        instruction.data.get(8..).unwrap_or(&[]),
+        &mut BTreeMap::new(),
+        &mut BTreeSet::new(),
+    )
+    .map(|_| ())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use anchor_lang::accounts::unchecked_account::UncheckedAccount;
+    use anchor_lang::Key;
+
+    // Stores a `u64` as its little endian bytes.
+    struct LeCodec;
+
+    impl Codec<u64> for LeCodec {
+        fn deserialize(buf: &mut &[u8]) -> anchor_lang::Result<u64> {
+            let bytes = buf
+                .get(..8)
+                .ok_or(anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+            Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+        }
+
+        fn serialize<W: std::io::Write>(value: &u64, writer: &mut W) -> anchor_lang::Result<()> {
+            writer
+                .write_all(&value.to_le_bytes())
+                .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize.into())
+        }
+    }
+
+    #[derive(Accounts)]
+    pub struct ReadOwned<'info> {
+        #[account(owner = registry.key())]
+        pub shared: OrphanAccount<'info, u64, LeCodec>,
+        pub registry: UncheckedAccount<'info>,
+    }
+
+    #[test]
+    fn validate_wrong_owner() {
+        let (shared, registry) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![
+                AccountMeta::new_readonly(shared, false),
+                AccountMeta::new_readonly(registry, false),
+            ],
+            data: vec![0; 8],
+        };
+        let account = |owner: Pubkey, data: Vec<u8>| Account {
+            lamports: 1,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        let mut datas = vec![
+            account(registry, 42u64.to_le_bytes().to_vec()),
+            account(system_program::ID, vec![]),
+        ];
+        validate_orphan_accounts::<ReadOwned>(&instruction, &mut datas).unwrap();
+
+        let mut datas = vec![
+            account(Pubkey::new_unique(), 42u64.to_le_bytes().to_vec()),
+            account(system_program::ID, vec![]),
+        ];
+        assert_eq!(
+            validate_orphan_accounts::<ReadOwned>(&instruction, &mut datas).unwrap_err(),
+            ErrorCode::ConstraintOwner.into()
+        );
+    }
+
+    #[test]
+    fn orphan_account_from_shared() {
+        let account = AccountSharedData::from(Account {
+            lamports: 1,
+            data: 42u64.to_le_bytes().to_vec(),
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        });
+        let value = OrphanAccount::<u64, LeCodec>::try_from_shared(&Pubkey::new_unique(), &account)
+            .unwrap();
+        assert_eq!(value, 42);
+
+        let drained = AccountSharedData::new(0, 8, &system_program::ID);
+        assert!(
+            OrphanAccount::<u64, LeCodec>::try_from_shared(&Pubkey::new_unique(), &drained)
+                .is_err()
+        );
+    }
+
     #[test]
     fn new_execution() {
         let mut logs: &[String] =
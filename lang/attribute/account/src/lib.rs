@@ -16,6 +16,8 @@ mod id;
 /// - [`Clone`](https://doc.rust-lang.org/std/clone/trait.Clone.html)
 /// - [`Discriminator`](./trait.Discriminator.html)
 /// - [`Owner`](./trait.Owner.html)
+/// - [`Owners`](./trait.Owners.html), if `orphan_owners` is given
+/// - [`Labeled`](./trait.Labeled.html), if `label` or `category` is given
 ///
 /// When implementing account serialization traits the first 8 bytes are
 /// reserved for a unique account discriminator, self described by the first 8
@@ -25,6 +27,59 @@ mod id;
 /// check this discriminator. If it doesn't match, an invalid account was given,
 /// and the account deserialization will exit with an error.
 ///
+/// # Trusted Owners
+///
+/// Accounts read through
+/// [`OrphanAccount`](./accounts/orphan_account/struct.OrphanAccount.html) may
+/// be owned by other programs. The set of programs trusted to own an account
+/// type can be declared on the type itself:
+///
+/// ```ignore
+/// #[account(orphan_owners = [prog_a::ID, prog_b::ID])]
+/// ```
+///
+/// This implements [`Owners`](./trait.Owners.html), which
+/// `OrphanAccount::try_from_trusted` uses to reject accounts owned by any
+/// other program.
+///
+/// # Metadata
+///
+/// A human readable label and category can be attached to an account type for
+/// client tooling:
+///
+/// ```ignore
+/// #[account(label = "Vault", category = "Treasury")]
+/// ```
+///
+/// This implements [`Labeled`](./trait.Labeled.html), returned by
+/// `OrphanAccount::metadata`, and adds the values to the account's entry in
+/// the IDL.
+///
+/// # Discriminator Namespace
+///
+/// Programs that derive discriminators from a namespace other than `account`
+/// can be read by giving the namespace explicitly:
+///
+/// ```ignore
+/// #[account(discriminator_namespace = "custom")]
+/// ```
+///
+/// The discriminator is then the first 8 bytes of the SHA256 of
+/// `custom:<Name>`, which `OrphanAccount::try_from` checks as usual. Only the
+/// discriminator changes, the other generated implementations are the same.
+///
+/// # Discriminator Hash
+///
+/// The discriminator is hashed with SHA256 unless another hash is requested:
+///
+/// ```ignore
+/// #[account(discriminator_hash = "blake3")]
+/// ```
+///
+/// The discriminator is then the first 8 bytes of the Blake3 hash of the same
+/// preimage. `"sha256"` and `"blake3"` are the accepted values, and the
+/// argument can be combined with `discriminator_namespace`.
+///
 /// # Zero Copy Deserialization
 ///
 /// **WARNING**: Zero copy deserialization is an experimental feature. It's
@@ -66,20 +121,37 @@ pub fn account(
 ) -> proc_macro::TokenStream {
     let mut namespace = "".to_string();
     let mut is_zero_copy = false;
+    let mut discriminator_namespace: Option<String> = None;
+    let mut discriminator_hash = "sha256".to_string();
+    let (args, orphan_owners) = split_orphan_owners(args.into());
+    let (args, metadata) = anchor_syn::parser::account_metadata::split(args);
     let args_str = args.to_string();
-    let args: Vec<&str> = args_str.split(',').collect();
-    if args.len() > 2 {
-        panic!("Only two args are allowed to the account attribute.")
-    }
+    let args: Vec<&str> = args_str
+        .split(',')
+        .filter(|a| !a.trim().is_empty())
+        .collect();
+    let mut seen = std::collections::HashSet::new();
     for arg in args {
-        let ns = arg
+        let ns: String = arg
             .to_string()
             .replace('\"', "")
             .chars()
             .filter(|c| !c.is_whitespace())
             .collect();
+        let kind = match ns.split_once('=') {
+            Some((key, _)) => key.to_string(),
+            None if ns == "zero_copy" => ns.clone(),
+            None => "namespace".to_string(),
+        };
+        if !seen.insert(kind) {
+            panic!("Each arg may only be given once to the account attribute.")
+        }
         if ns == "zero_copy" {
             is_zero_copy = true;
+        } else if let Some(ns) = ns.strip_prefix("discriminator_namespace=") {
+            discriminator_namespace = Some(ns.to_string());
+        } else if let Some(hash) = ns.strip_prefix("discriminator_hash=") {
+            discriminator_hash = hash.to_string();
         } else {
             namespace = ns;
         }
@@ -93,18 +165,24 @@ pub fn account(
     let discriminator: proc_macro2::TokenStream = {
         // Namespace the discriminator to prevent collisions.
         let discriminator_preimage = {
-            // For now, zero copy accounts can't be namespaced.
-            if namespace.is_empty() {
-                format!("account:{}", account_name)
-            } else {
-                format!("{}:{}", namespace, account_name)
+            // An explicit discriminator namespace takes precedence over the
+            // namespace of the owner.
+            match &discriminator_namespace {
+                Some(ns) => format!("{}:{}", ns, account_name),
+                None if namespace.is_empty() => format!("account:{}", account_name),
+                None => format!("{}:{}", namespace, account_name),
             }
         };
 
         let mut discriminator = [0u8; 8];
-        discriminator.copy_from_slice(
-            &anchor_syn::hash::hash(discriminator_preimage.as_bytes()).to_bytes()[..8],
-        );
+        match discriminator_hash.as_str() {
+            "sha256" => discriminator.copy_from_slice(
+                &anchor_syn::hash::hash(discriminator_preimage.as_bytes()).to_bytes()[..8],
+            ),
+            "blake3" => discriminator
+                .copy_from_slice(&blake3::hash(discriminator_preimage.as_bytes()).as_bytes()[..8]),
+            other => panic!("Unsupported discriminator hash: {}", other),
+        }
         format!("{:?}", discriminator).parse().unwrap()
     };
 
@@ -123,6 +201,36 @@ pub fn account(
         }
     };
 
+    let owners_impl = match orphan_owners {
+        Some(owners) => quote! {
+            #[automatically_derived]
+            impl #impl_gen anchor_lang::Owners for #account_name #type_gen #where_clause {
+                fn owners() -> Vec<Pubkey> {
+                    vec![#(#owners),*]
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    let labeled_impl = if metadata.is_empty() {
+        quote! {}
+    } else {
+        let label = option_tokens(&metadata.label);
+        let category = option_tokens(&metadata.category);
+        quote! {
+            #[automatically_derived]
+            impl #impl_gen anchor_lang::Labeled for #account_name #type_gen #where_clause {
+                fn metadata() -> anchor_lang::AccountMetadata {
+                    anchor_lang::AccountMetadata {
+                        label: #label,
+                        category: #category,
+                    }
+                }
+            }
+        }
+    };
+
     proc_macro::TokenStream::from({
         if is_zero_copy {
             quote! {
@@ -169,6 +277,10 @@ pub fn account(
                 }
 
                 #owner_impl
+
+                #owners_impl
+
+                #labeled_impl
             }
         } else {
             quote! {
@@ -217,6 +329,117 @@ pub fn account(
                 }
 
                 #owner_impl
+
+                #owners_impl
+
+                #labeled_impl
+            }
+        }
+    })
+}
+
+fn option_tokens(value: &Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+// Removes the `orphan_owners = [..]` argument, if any, from the attribute
+// args, returning the remaining args and the listed owner expressions.
+fn split_orphan_owners(
+    args: proc_macro2::TokenStream,
+) -> (proc_macro2::TokenStream, Option<Vec<syn::Expr>>) {
+    let mut rest = Vec::new();
+    let mut owners = None;
+    let mut tokens = args.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match &token {
+            proc_macro2::TokenTree::Ident(ident) if ident == "orphan_owners" => {
+                let value: proc_macro2::TokenStream = tokens
+                    .by_ref()
+                    .skip_while(
+                        |t| matches!(t, proc_macro2::TokenTree::Punct(p) if p.as_char() == '='),
+                    )
+                    .take_while(
+                        |t| !matches!(t, proc_macro2::TokenTree::Punct(p) if p.as_char() == ','),
+                    )
+                    .collect();
+                let array: syn::ExprArray = syn::parse2(value)
+                    .expect("orphan_owners must be an array, e.g. `orphan_owners = [A, B]`");
+                owners = Some(array.elems.into_iter().collect());
+            }
+            _ => rest.push(token),
+        }
+    }
+    (rest.into_iter().collect(), owners)
+}
+
+/// Implements
+/// [`Diffable`](./accounts/orphan_account/trait.Diffable.html), reporting
+/// every named field that is not equal, by `PartialEq`, to the same field of
+/// the previous value.
+#[proc_macro_derive(Diffable)]
+pub fn derive_diffable(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let strct = parse_macro_input!(item as syn::ItemStruct);
+    let name = &strct.ident;
+    let (impl_gen, ty_gen, where_clause) = strct.generics.split_for_impl();
+
+    let fields = match &strct.fields {
+        syn::Fields::Named(n) => n,
+        _ => panic!("Fields must be named"),
+    };
+    let checks = fields.named.iter().map(|field| {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_name_str = field_name.to_string();
+        quote! {
+            if self.#field_name != previous.#field_name {
+                changes.push(anchor_lang::accounts::orphan_account::FieldChange {
+                    field: #field_name_str,
+                });
+            }
+        }
+    });
+
+    proc_macro::TokenStream::from(quote! {
+        #[automatically_derived]
+        impl #impl_gen anchor_lang::accounts::orphan_account::Diffable for #name #ty_gen #where_clause {
+            #[allow(unused_mut)]
+            fn diff_fields(&self, previous: &Self) -> Vec<anchor_lang::accounts::orphan_account::FieldChange> {
+                let mut changes = Vec::new();
+                #(#checks)*
+                changes
+            }
+        }
+    })
+}
+
+/// Implements
+/// [`DebugFields`](./accounts/orphan_account/trait.DebugFields.html),
+/// listing every named field with its `Debug` representation.
+#[proc_macro_derive(DebugFields)]
+pub fn derive_debug_fields(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let strct = parse_macro_input!(item as syn::ItemStruct);
+    let name = &strct.ident;
+    let (impl_gen, ty_gen, where_clause) = strct.generics.split_for_impl();
+
+    let fields = match &strct.fields {
+        syn::Fields::Named(n) => n,
+        _ => panic!("Fields must be named"),
+    };
+    let entries = fields.named.iter().map(|field| {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_name_str = field_name.to_string();
+        quote! {
+            (#field_name_str, format!("{:?}", self.#field_name))
+        }
+    });
+
+    proc_macro::TokenStream::from(quote! {
+        #[automatically_derived]
+        impl #impl_gen anchor_lang::accounts::orphan_account::DebugFields for #name #ty_gen #where_clause {
+            fn debug_fields(&self) -> Vec<(&'static str, String)> {
+                vec![#(#entries),*]
             }
         }
     })
@@ -9,6 +9,10 @@ use syn::parse_macro_input;
 ///
 /// # Table of Contents
 /// - [Instruction Attribute](#instruction-attribute)
+/// - [Strict Accounts Attribute](#strict-accounts-attribute)
+/// - [Memoize Orphans Attribute](#memoize-orphans-attribute)
+/// - [Snapshot Orphans Attribute](#snapshot-orphans-attribute)
+/// - [Orphan Stack Budget Attribute](#orphan-stack-budget-attribute)
 /// - [Constraints](#constraints)
 ///
 /// # Instruction Attribute
@@ -34,6 +38,85 @@ use syn::parse_macro_input;
 /// }
 /// ```
 ///
+/// # Strict Accounts Attribute
+///
+/// By default, any accounts left over after deserializing the struct are
+/// passed on as `remaining_accounts`. With `#[strict_accounts]`, leftover
+/// accounts are rejected instead, catching clients that pass more accounts
+/// than the instruction expects. A strict struct nested in another accounts
+/// struct leaves its leftovers to the fields following it, so only the
+/// top-level struct of an instruction is checked.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Accounts)]
+/// #[strict_accounts]
+/// pub struct ReadShared<'info> {
+///     pub shared: OrphanAccount<'info, SharedConfig>,
+/// }
+/// ```
+///
+/// # Memoize Orphans Attribute
+///
+/// With `#[memoize_orphans]`, an `OrphanAccount` field given the same account
+/// as an earlier `OrphanAccount` field of the same type reuses the earlier
+/// value instead of deserializing the account again.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Accounts)]
+/// #[memoize_orphans]
+/// pub struct CompareViews<'info> {
+///     pub left: OrphanAccount<'info, SharedConfig>,
+///     pub right: OrphanAccount<'info, SharedConfig>,
+/// }
+/// ```
+///
+/// # Snapshot Orphans Attribute
+///
+/// With `#[snapshot_orphans]`, a `snapshot_orphans()` method is generated
+/// that returns a `<Struct>OrphanSnapshot` holding a clone of the value of
+/// every `OrphanAccount` field, e.g. to take a consistent view of the shared
+/// accounts at the start of an instruction and detect changes made by a CPI.
+/// Fields of nested accounts structs are not included.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Accounts)]
+/// #[snapshot_orphans]
+/// pub struct Settle<'info> {
+///     pub shared: OrphanAccount<'info, SharedConfig>,
+/// }
+///
+/// let before = ctx.accounts.snapshot_orphans();
+/// // CPI...
+/// ctx.accounts.shared.reload()?;
+/// require!(before.shared.fee == ctx.accounts.shared.fee, MyError::FeeChanged);
+/// ```
+///
+/// # Orphan Stack Budget Attribute
+///
+/// An `OrphanAccount` holds its deserialized value inline, so a large account
+/// type can overflow the BPF stack at runtime. With
+/// `#[orphan_stack_budget(<bytes>)]`, compilation instead fails if the
+/// account type of any `OrphanAccount` field is larger than `<bytes>`, with
+/// an overflowing constant reported at the field. Its large fields should
+/// then be moved to the heap, e.g. with `Box` or `Vec`. Account types with generic parameters of the struct are not
+/// supported.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Accounts)]
+/// #[orphan_stack_budget(1024)]
+/// pub struct ReadShared<'info> {
+///     pub shared: OrphanAccount<'info, SharedConfig>,
+/// }
+/// ```
+///
 /// # Constraints
 ///
 /// There are different types of constraints that can be applied with the `#[account(..)]` attribute.
@@ -122,6 +205,11 @@ use syn::parse_macro_input;
 ///                         (which anchor uses under the hood for serialization) specification to learn how much
 ///                         space different data structures require.
 ///                     </li>
+///                     <li>
+///                         Requires the account type of an <code>OrphanAccount</code> to implement
+///                         <code>Default</code>, as the given space is checked to fit the serialized
+///                         default value.
+///                     </li>
 ///                 <br>
 ///                 Example:
 ///                 <pre>
@@ -329,15 +417,57 @@ use syn::parse_macro_input;
 ///         </tr>
 ///         <tr>
 ///             <td>
-///                 <code>#[account(executable)]</code>
+///                 <code>#[account(executable)]</code><br><br>
+///                 <code>#[account(executable = false)]</code>
 ///             </td>
 ///             <td>
 ///                 Checks the account is executable (i.e. the account is a program).<br>
-///                 You may want to use the <code>Program</code> type instead.<br><br>
+///                 You may want to use the <code>Program</code> type instead.<br>
+///                 With <code>= false</code>, checks the account is <i>not</i> executable,
+///                 e.g. for an <code>OrphanAccount</code> whose owner is not checked.<br><br>
 ///                 Example:
 ///                 <pre><code>
 /// #[account(executable)]
-/// pub my_program: AccountInfo<'info>
+/// pub my_program: AccountInfo<'info>,
+/// #[account(executable = false)]
+/// pub shared: OrphanAccount<'info, MyData>
+///                 </code></pre>
+///             </td>
+///         </tr>
+///         <tr>
+///             <td>
+///                 <code>#[account(deny_self_owner)]</code>
+///             </td>
+///             <td>
+///                 Checks the <code>OrphanAccount</code> is <i>not</i> owned by the executing program.<br>
+///                 Accounts owned by the program should use the <code>Account</code> type instead.<br><br>
+///                 Example:
+///                 <pre><code>
+/// #[account(deny_self_owner)]
+/// pub shared: OrphanAccount<'info, MyData>
+///                 </code></pre>
+///             </td>
+///         </tr>
+///         <tr>
+///             <td>
+///                 <code>#[account(required_if = &lt;expr&gt;)]</code>
+///             </td>
+///             <td>
+///                 Checks an optional <code>OrphanAccount</code> is given when <code>expr</code> is true.
+///                 A field of type <code>Option&lt;OrphanAccount&gt;</code> is <code>None</code> when the
+///                 program id is passed in its place. The expression can use the arguments declared with
+///                 <code>#[instruction(..)]</code>.<br>
+///                 Other constraints on the field are only checked when the account is present, and the
+///                 <code>init</code>, <code>zero</code>, <code>realloc</code> and <code>close</code>
+///                 constraints aren't supported on optional accounts.<br><br>
+///                 Example:
+///                 <pre><code>
+/// #[derive(Accounts)]
+/// #[instruction(with_config: bool)]
+/// pub struct Configure<'info> {
+///     #[account(required_if = with_config)]
+///     pub config: Option<OrphanAccount<'info, Config>>,
+/// }
 ///                 </code></pre>
 ///             </td>
 ///         </tr>
@@ -583,7 +713,17 @@ use syn::parse_macro_input;
 ///         </tr>
 ///     <tbody>
 /// </table>
-#[proc_macro_derive(Accounts, attributes(account, instruction))]
+#[proc_macro_derive(
+    Accounts,
+    attributes(
+        account,
+        instruction,
+        strict_accounts,
+        memoize_orphans,
+        orphan_stack_budget,
+        snapshot_orphans
+    )
+)]
 pub fn derive_anchor_deserialize(item: TokenStream) -> TokenStream {
     parse_macro_input!(item as anchor_syn::AccountsStruct)
         .to_token_stream()
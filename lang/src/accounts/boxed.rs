@@ -30,6 +30,16 @@ impl<'info, T: Accounts<'info>> Accounts<'info> for Box<T> {
     ) -> Result<Self> {
         T::try_accounts(program_id, accounts, ix_data, bumps, reallocs).map(Box::new)
     }
+
+    fn try_accounts_nested(
+        program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        ix_data: &[u8],
+        bumps: &mut BTreeMap<String, u8>,
+        reallocs: &mut BTreeSet<Pubkey>,
+    ) -> Result<Self> {
+        T::try_accounts_nested(program_id, accounts, ix_data, bumps, reallocs).map(Box::new)
+    }
 }
 
 impl<'info, T: AccountsExit<'info>> AccountsExit<'info> for Box<T> {
@@ -0,0 +1,81 @@
+//! Orphan account sub-objects deserialized once and cached.
+
+use crate::{Key, Result};
+use solana_program::account_info::AccountInfo;
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+/// Reads a sub-object from the full account data, discriminator included.
+pub type SubObjectExtractor<S> = fn(&[u8]) -> Result<S>;
+
+/// A sub-object of an account, read by its [`SubObjectExtractor`] on first
+/// access and served from the cache afterwards, e.g. for a nested object that
+/// is expensive to deserialize and read in a loop.
+///
+/// Changes to the account data aren't tracked, call
+/// [`invalidate`](Self::invalidate) once the data is modified, e.g. by a CPI.
+/// As with [`OrphanAccount`](crate::accounts::orphan_account::OrphanAccount),
+/// the owner is not checked, and nothing is written back on exit.
+///
+/// # Example
+/// ```ignore
+/// let mut curve = CachedOrphan::new(&ctx.accounts.pool, |data| {
+///     Curve::deserialize(&mut &data[CURVE_OFFSET..]).map_err(Into::into)
+/// });
+/// for amount in amounts {
+///     total += curve.get()?.quote(amount);
+/// }
+/// ```
+pub struct CachedOrphan<'info, S> {
+    info: AccountInfo<'info>,
+    extract: SubObjectExtractor<S>,
+    value: Option<S>,
+}
+
+impl<'info, S> CachedOrphan<'info, S> {
+    /// Wraps `info` without reading it, the extractor only runs on the first
+    /// [`get`](Self::get).
+    pub fn new(info: &AccountInfo<'info>, extract: SubObjectExtractor<S>) -> Self {
+        Self {
+            info: info.clone(),
+            extract,
+            value: None,
+        }
+    }
+
+    /// Returns the sub-object, extracting it from the account data first if
+    /// it isn't cached. A failed extraction isn't cached.
+    pub fn get(&mut self) -> Result<&S> {
+        if self.value.is_none() {
+            let value = (self.extract)(&self.info.try_borrow_data()?)?;
+            self.value = Some(value);
+        }
+        Ok(self.value.as_ref().unwrap())
+    }
+
+    /// Returns true if the sub-object has been extracted.
+    pub fn is_cached(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Drops the cached sub-object, so the next [`get`](Self::get) extracts it
+    /// again from the current account data.
+    pub fn invalidate(&mut self) {
+        self.value = None;
+    }
+}
+
+impl<'info, S: fmt::Debug> fmt::Debug for CachedOrphan<'info, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedOrphan")
+            .field("key", self.info.key)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<'info, S> Key for CachedOrphan<'info, S> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
@@ -0,0 +1,70 @@
+//! Copy on write access to orphan accounts whose fields can borrow from the
+//! account data.
+
+use crate::Result;
+
+/// A view of an account that borrows its fields, such as strings and slices,
+/// from the account data instead of allocating them.
+pub trait BorrowedView<'a>: Sized {
+    /// The owned counterpart of the view, e.g. with `String` fields in place
+    /// of `&str` ones.
+    type Owned;
+
+    /// Reads the view from `data`, the full account data including the
+    /// discriminator, which implementations are expected to check.
+    fn view(data: &'a [u8]) -> Result<Self>;
+
+    /// Copies the view into an owned value.
+    fn to_owned_value(&self) -> Self::Owned;
+}
+
+/// An account read as a [`BorrowedView`] into its data, only copied into an
+/// owned value when first mutated through [`to_mut`](Self::to_mut).
+///
+/// Unlike [`OrphanAccount`](crate::accounts::orphan_account::OrphanAccount),
+/// the data must stay borrowed while the account is in use, and nothing is
+/// written back on exit. As with `OrphanAccount`, the owner isn't checked.
+///
+/// # Example
+/// ```ignore
+/// let data = ctx.accounts.profile.try_borrow_data()?;
+/// let profile = CowOrphan::<ProfileView>::try_from(&data)?;
+/// if let CowOrphan::Borrowed(view) = &profile {
+///     msg!("{}", view.name);
+/// }
+/// ```
+pub enum CowOrphan<'a, V: BorrowedView<'a>> {
+    Borrowed(V),
+    Owned(V::Owned),
+}
+
+impl<'a, V: BorrowedView<'a>> CowOrphan<'a, V> {
+    /// Reads a borrowed view from `data`, without allocating.
+    pub fn try_from(data: &'a [u8]) -> Result<CowOrphan<'a, V>> {
+        V::view(data).map(CowOrphan::Borrowed)
+    }
+
+    /// Returns true if the account still borrows from the account data.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, CowOrphan::Borrowed(_))
+    }
+
+    /// Returns the owned value, copying the view into it first if needed.
+    pub fn to_mut(&mut self) -> &mut V::Owned {
+        if let CowOrphan::Borrowed(view) = self {
+            *self = CowOrphan::Owned(view.to_owned_value());
+        }
+        match self {
+            CowOrphan::Owned(owned) => owned,
+            CowOrphan::Borrowed(_) => unreachable!(),
+        }
+    }
+
+    /// Consumes the account, returning the owned value.
+    pub fn into_owned(self) -> V::Owned {
+        match self {
+            CowOrphan::Borrowed(view) => view.to_owned_value(),
+            CowOrphan::Owned(owned) => owned,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Orphan accounts whose type is selected at runtime by their discriminator.
+
+use crate::accounts::orphan_account::check_initialized;
+use crate::error::{Error, ErrorCode};
+use crate::{Key, Result, ToAccountInfos};
+use solana_program::account_info::AccountInfo;
+use solana_program::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Deref;
+
+/// Deserializes the full data of an account, discriminator included, into a
+/// boxed `D`, typically a trait object.
+pub type DynamicDeserializer<D> = fn(&[u8]) -> Result<Box<D>>;
+
+/// An account whose concrete type isn't known at compile time, deserialized
+/// by the [`DynamicDeserializer`] registered for its discriminator.
+///
+/// Dereferences to `D`, typically a trait object implemented by every
+/// registered type. As with
+/// [`OrphanAccount`](crate::accounts::orphan_account::OrphanAccount), the
+/// owner is not checked, and nothing is written back on exit.
+///
+/// # Example
+/// ```ignore
+/// let mut deserializers: BTreeMap<[u8; 8], DynamicDeserializer<dyn Plugin>> = BTreeMap::new();
+/// deserializers.insert(Swap::discriminator(), |data| {
+///     Ok(Box::new(Swap::try_deserialize(&mut &data[..])?))
+/// });
+/// let plugin = DynamicOrphan::try_from(&ctx.accounts.plugin, &deserializers)?;
+/// plugin.run()?;
+/// ```
+pub struct DynamicOrphan<'info, D: ?Sized> {
+    account: Box<D>,
+    info: AccountInfo<'info>,
+    discriminator: [u8; 8],
+}
+
+impl<'info, D: ?Sized> DynamicOrphan<'info, D> {
+    /// Deserializes the given `info` with the deserializer registered for its
+    /// discriminator, failing with [`ErrorCode::AccountDiscriminatorMismatch`]
+    /// if there is none.
+    pub fn try_from(
+        info: &AccountInfo<'info>,
+        deserializers: &BTreeMap<[u8; 8], DynamicDeserializer<D>>,
+    ) -> Result<DynamicOrphan<'info, D>> {
+        check_initialized(info)?;
+        let data = info.try_borrow_data()?;
+        let discriminator: [u8; 8] = match data.get(..8) {
+            Some(discriminator) => discriminator.try_into().unwrap(),
+            None => return Err(ErrorCode::AccountDiscriminatorNotFound.into()),
+        };
+        let deserialize = deserializers.get(&discriminator).ok_or_else(|| {
+            Error::from(ErrorCode::AccountDiscriminatorMismatch).with_account_name(info.key)
+        })?;
+        let account = deserialize(&data)?;
+        drop(data);
+        Ok(Self {
+            account,
+            info: info.clone(),
+            discriminator,
+        })
+    }
+
+    /// Returns the discriminator the account was dispatched on.
+    pub fn discriminator(&self) -> [u8; 8] {
+        self.discriminator
+    }
+
+    pub fn into_inner(self) -> Box<D> {
+        self.account
+    }
+}
+
+impl<'info, D: ?Sized> Deref for DynamicOrphan<'info, D> {
+    type Target = D;
+
+    fn deref(&self) -> &Self::Target {
+        &self.account
+    }
+}
+
+impl<'info, D: ?Sized> fmt::Debug for DynamicOrphan<'info, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicOrphan")
+            .field("info", &self.info)
+            .field("discriminator", &self.discriminator)
+            .finish()
+    }
+}
+
+impl<'info, D: ?Sized> ToAccountInfos<'info> for DynamicOrphan<'info, D> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.info.clone()]
+    }
+}
+
+impl<'info, D: ?Sized> AsRef<AccountInfo<'info>> for DynamicOrphan<'info, D> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        &self.info
+    }
+}
+
+impl<'info, D: ?Sized> Key for DynamicOrphan<'info, D> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
@@ -4,15 +4,23 @@ pub mod account;
 pub mod account_info;
 pub mod account_loader;
 pub mod boxed;
+pub mod cached_orphan;
+pub mod cow_orphan;
 #[doc(hidden)]
 #[allow(deprecated)]
 pub mod cpi_account;
 #[doc(hidden)]
 #[allow(deprecated)]
 pub mod cpi_state;
+pub mod dynamic_orphan;
 #[doc(hidden)]
 #[allow(deprecated)]
 pub mod loader;
+pub mod option;
+pub mod orphan_account;
+pub mod orphan_array;
+pub mod orphan_loader;
+pub mod orphan_log;
 pub mod program;
 #[doc(hidden)]
 #[allow(deprecated)]
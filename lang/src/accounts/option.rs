@@ -0,0 +1,67 @@
+//! Option<T> type for optional accounts.
+//!
+//! An optional account is absent when the program id is given in its place,
+//! or when no accounts are left.
+//!
+//! # Example
+//! ```ignore
+//! #[derive(Accounts)]
+//! #[instruction(with_config: bool)]
+//! pub struct Example<'info> {
+//!     #[account(required_if = with_config)]
+//!     pub config: Option<OrphanAccount<'info, Config>>,
+//! }
+//! ```
+
+use crate::{Accounts, AccountsExit, Result, ToAccountInfos, ToAccountMetas};
+use solana_program::account_info::AccountInfo;
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet};
+
+impl<'info, T: Accounts<'info>> Accounts<'info> for Option<T> {
+    fn try_accounts(
+        program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        ix_data: &[u8],
+        bumps: &mut BTreeMap<String, u8>,
+        reallocs: &mut BTreeSet<Pubkey>,
+    ) -> Result<Self> {
+        if accounts.is_empty() {
+            return Ok(None);
+        }
+        if accounts[0].key == program_id {
+            *accounts = &accounts[1..];
+            return Ok(None);
+        }
+        T::try_accounts(program_id, accounts, ix_data, bumps, reallocs).map(Some)
+    }
+}
+
+impl<'info, T: AccountsExit<'info>> AccountsExit<'info> for Option<T> {
+    fn exit(&self, program_id: &Pubkey) -> Result<()> {
+        match self {
+            Some(account) => account.exit(program_id),
+            None => Ok(()),
+        }
+    }
+}
+
+// An absent account has no infos or metas of its own. The program id it is
+// replaced by isn't known here, so `#[derive(Accounts)]` pushes the
+// placeholder meta itself, keeping the position of the accounts following it.
+// No placeholder info is needed, as infos are matched by key rather than by
+// position.
+impl<'info, T: ToAccountInfos<'info>> ToAccountInfos<'info> for Option<T> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        self.as_ref()
+            .map_or_else(Vec::new, |account| account.to_account_infos())
+    }
+}
+
+impl<T: ToAccountMetas> ToAccountMetas for Option<T> {
+    fn to_account_metas(&self, is_signer: Option<bool>) -> Vec<AccountMeta> {
+        self.as_ref()
+            .map_or_else(Vec::new, |account| account.to_account_metas(is_signer))
+    }
+}
@@ -0,0 +1,3891 @@
+//! Account container that deserializes data without checking ownership.
+
+use crate::bpf_writer::BpfWriter;
+use crate::error::{Error, ErrorCode};
+use crate::{
+    AccountDeserialize, AccountMetadata, AccountSerialize, Accounts, AccountsClose, AccountsExit,
+    AnchorDeserialize, AnchorSerialize, Bump, Discriminator, Key, Labeled, Owners, Result,
+    ToAccountInfo, ToAccountInfos, ToAccountMetas,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::bpf_loader_upgradeable;
+use solana_program::clock::Clock;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_instruction;
+use solana_program::system_program;
+use solana_program::sysvar::Sysvar;
+use std::cell::{Cell, Ref};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::hash::Hasher;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Index, IndexMut, Range, RangeInclusive};
+
+pub use anchor_attribute_account::{DebugFields, Diffable};
+
+/// Strategy used by [`OrphanAccount`] to read and write the account data.
+///
+/// The default, [`AnchorCodec`], delegates to the type's
+/// [`AccountSerialize`] and [`AccountDeserialize`] implementations. Implement
+/// this trait to read accounts stored in a format other than the one
+/// generated by `#[account]`, e.g. bincode or a hand rolled binary layout.
+pub trait Codec<T> {
+    /// Deserializes previously initialized account data, checking the
+    /// discriminator if the format has one.
+    fn deserialize(buf: &mut &[u8]) -> Result<T>;
+
+    /// Deserializes account data without checking the discriminator.
+    /// Defaults to [`deserialize`](Codec::deserialize) for formats without one.
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        Self::deserialize(buf)
+    }
+
+    /// Serializes `value` into `writer`.
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()>;
+
+    /// Returns the serialized length of `value` if it is known without
+    /// serializing it. Defaults to `None`.
+    fn serialized_len(_value: &T) -> Option<usize> {
+        None
+    }
+
+    /// Returns true if `data` is marked as not initialized by the format.
+    /// Checked in addition to the system owned, zero lamports convention.
+    /// Defaults to `false`.
+    fn is_uninitialized(_data: &[u8]) -> bool {
+        false
+    }
+
+    /// Returns the range of the serialized bytes of `value` that changed since
+    /// it was deserialized, if known. When `Some`, `exit` only writes the
+    /// bytes in the range. Defaults to `None`, writing the whole account.
+    fn changed_range(_value: &T) -> Option<Range<usize>> {
+        None
+    }
+}
+
+/// The default [`Codec`], which uses the type's [`AccountSerialize`] and
+/// [`AccountDeserialize`] implementations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AnchorCodec;
+
+impl<T: AccountSerialize + AccountDeserialize> Codec<T> for AnchorCodec {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        T::try_deserialize(buf)
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        T::try_deserialize_unchecked(buf)
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        value.try_serialize(writer)
+    }
+}
+
+/// Implemented by fixed-size types whose serialized length never changes. For
+/// account types, the length includes the discriminator.
+pub trait ConstSerializedLen {
+    const SERIALIZED_LEN: usize;
+}
+
+/// A [`Codec`] for [`ConstSerializedLen`] types, reporting
+/// [`SERIALIZED_LEN`](ConstSerializedLen::SERIALIZED_LEN) as the serialized
+/// length and delegating everything else to `C`.
+///
+/// With this codec, [`OrphanAccount::serialized_len`] doesn't serialize the
+/// account and `exit` checks that the account fits before writing it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstLen<C = AnchorCodec>(PhantomData<C>);
+
+impl<T: ConstSerializedLen, C: Codec<T>> Codec<T> for ConstLen<C> {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize(buf)
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(buf)
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        C::serialize(value, writer)
+    }
+
+    fn serialized_len(_value: &T) -> Option<usize> {
+        Some(T::SERIALIZED_LEN)
+    }
+
+    fn is_uninitialized(data: &[u8]) -> bool {
+        C::is_uninitialized(data)
+    }
+
+    fn changed_range(value: &T) -> Option<Range<usize>> {
+        C::changed_range(value)
+    }
+}
+
+/// Marks account types whose format has no discriminator, for use with
+/// [`Bare`].
+pub trait NoDiscriminator {}
+
+/// A [`Codec`] for [`NoDiscriminator`] types, deserializing with
+/// [`deserialize_unchecked`](Codec::deserialize_unchecked) so that
+/// [`OrphanAccount::try_from`] skips the discriminator check, and delegating
+/// everything else to `C`.
+///
+/// Unlike [`OrphanAccount::try_from_unchecked`], the intent is part of the
+/// type, and `try_from` still rejects uninitialized accounts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bare<C = AnchorCodec>(PhantomData<C>);
+
+impl<T: NoDiscriminator, C: Codec<T>> Codec<T> for Bare<C> {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(buf)
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(buf)
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        C::serialize(value, writer)
+    }
+
+    fn serialized_len(value: &T) -> Option<usize> {
+        C::serialized_len(value)
+    }
+
+    fn is_uninitialized(data: &[u8]) -> bool {
+        C::is_uninitialized(data)
+    }
+
+    fn changed_range(value: &T) -> Option<Range<usize>> {
+        C::changed_range(value)
+    }
+}
+
+/// Implemented by types from foreign formats that mark uninitialized
+/// accounts with a sentinel, e.g. data filled with `0xFF`.
+pub trait UninitSentinel {
+    fn is_uninitialized(data: &[u8]) -> bool;
+}
+
+/// A [`Codec`] for [`UninitSentinel`] types, rejecting accounts matching the
+/// sentinel as [`ErrorCode::AccountNotInitialized`] and delegating everything
+/// else to `C`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sentinel<C = AnchorCodec>(PhantomData<C>);
+
+impl<T: UninitSentinel, C: Codec<T>> Codec<T> for Sentinel<C> {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize(buf)
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(buf)
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        C::serialize(value, writer)
+    }
+
+    fn serialized_len(value: &T) -> Option<usize> {
+        C::serialized_len(value)
+    }
+
+    fn is_uninitialized(data: &[u8]) -> bool {
+        T::is_uninitialized(data) || C::is_uninitialized(data)
+    }
+
+    fn changed_range(value: &T) -> Option<Range<usize>> {
+        C::changed_range(value)
+    }
+}
+
+/// Implemented by fixed-layout types that track which of their serialized
+/// bytes changed, so that only those are written back on exit.
+///
+/// The offsets of the fields must not depend on their values, which is why
+/// the serialized length must be constant.
+pub trait ChangedRange: ConstSerializedLen {
+    /// Returns the range of the serialized account, including the
+    /// discriminator, modified since deserialization. `None` writes the
+    /// whole account.
+    fn changed_range(&self) -> Option<Range<usize>>;
+}
+
+/// A [`Codec`] for [`ChangedRange`] types, making `exit` write only the
+/// changed bytes and delegating everything else to `C`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Partial<C = AnchorCodec>(PhantomData<C>);
+
+impl<T: ChangedRange, C: Codec<T>> Codec<T> for Partial<C> {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize(buf)
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(buf)
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        C::serialize(value, writer)
+    }
+
+    fn serialized_len(_value: &T) -> Option<usize> {
+        Some(T::SERIALIZED_LEN)
+    }
+
+    fn is_uninitialized(data: &[u8]) -> bool {
+        C::is_uninitialized(data)
+    }
+
+    fn changed_range(value: &T) -> Option<Range<usize>> {
+        value.changed_range()
+    }
+}
+
+/// A [`Codec`] for plain old data account types, serializing the account as
+/// its discriminator followed by a single copy of its bytes instead of field
+/// by field, and delegating everything else to `C`.
+///
+/// The bytes of `T` must match its borsh serialization, which holds for
+/// `#[repr(C)]` structs of little endian integers and arrays thereof. As
+/// [`Pod`](bytemuck::Pod) types have no padding, such structs lay their
+/// fields out in declaration order.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PodCopy<C = AnchorCodec>(PhantomData<C>);
+
+impl<T: bytemuck::Pod + Discriminator, C: Codec<T>> Codec<T> for PodCopy<C> {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize(buf)
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(buf)
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        writer
+            .write_all(&T::discriminator())
+            .and_then(|_| writer.write_all(bytemuck::bytes_of(value)))
+            .map_err(|_| ErrorCode::AccountDidNotSerialize.into())
+    }
+
+    fn serialized_len(_value: &T) -> Option<usize> {
+        Some(8 + std::mem::size_of::<T>())
+    }
+
+    fn is_uninitialized(data: &[u8]) -> bool {
+        C::is_uninitialized(data)
+    }
+
+    fn changed_range(value: &T) -> Option<Range<usize>> {
+        C::changed_range(value)
+    }
+}
+
+/// Serializes an account piece by piece, for use with [`Streamed`].
+pub trait StreamSerialize {
+    /// Writes the account, discriminator included, into `writer` in the
+    /// layout its codec reads, e.g. one element of a large vector at a time,
+    /// without building the serialized account in memory first.
+    fn stream_serialize<W: Write>(&self, writer: &mut W) -> Result<()>;
+}
+
+/// A [`Codec`] for [`StreamSerialize`] types, serializing with
+/// [`stream_serialize`](StreamSerialize::stream_serialize) and delegating
+/// everything else to `C`.
+///
+/// `exit` hands the codec a writer over the account data itself, so each
+/// piece lands in the account as it is written and peak memory stays bounded
+/// by the largest piece rather than the whole account.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Streamed<C = AnchorCodec>(PhantomData<C>);
+
+impl<T: StreamSerialize, C: Codec<T>> Codec<T> for Streamed<C> {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize(buf)
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(buf)
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        value.stream_serialize(writer)
+    }
+
+    fn serialized_len(value: &T) -> Option<usize> {
+        C::serialized_len(value)
+    }
+
+    fn is_uninitialized(data: &[u8]) -> bool {
+        C::is_uninitialized(data)
+    }
+
+    fn changed_range(value: &T) -> Option<Range<usize>> {
+        C::changed_range(value)
+    }
+}
+
+/// Compresses the body of an account, everything following its
+/// discriminator, for use with [`Compression`].
+pub trait Compressed {
+    fn compress(body: &[u8]) -> Vec<u8>;
+
+    /// Reverses [`compress`](Compressed::compress), failing on malformed
+    /// input.
+    fn decompress(compressed: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// A [`Codec`] for [`Compressed`] types, storing the account as the
+/// discriminator written by `C`, the little endian `u32` length of the
+/// compressed body and the compressed body itself. Everything else is
+/// delegated to `C`, which serializes the uncompressed account.
+///
+/// The whole account is decompressed on every read, e.g. by
+/// [`OrphanAccount::try_from`], and recompressed on every write, trading
+/// compute for rent.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Compression<C = AnchorCodec>(PhantomData<C>);
+
+impl<C> Compression<C> {
+    // Decompresses `buf` into the account data `C` reads, advancing `buf`
+    // past the compressed body.
+    fn decompress<T: Compressed>(buf: &mut &[u8]) -> Result<Vec<u8>> {
+        if buf.len() < 12 {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+        let len = u32::from_le_bytes(buf[8..12].try_into().unwrap()) as usize;
+        let compressed = buf.get(12..12 + len).ok_or_else(|| {
+            Error::from(ErrorCode::AccountDidNotDeserialize).with_values((buf.len(), 12 + len))
+        })?;
+        let mut data = buf[..8].to_vec();
+        data.extend_from_slice(&T::decompress(compressed)?);
+        *buf = &buf[12 + len..];
+        Ok(data)
+    }
+}
+
+impl<T: Compressed, C: Codec<T>> Codec<T> for Compression<C> {
+    fn deserialize(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize(&mut &Self::decompress::<T>(buf)?[..])
+    }
+
+    fn deserialize_unchecked(buf: &mut &[u8]) -> Result<T> {
+        C::deserialize_unchecked(&mut &Self::decompress::<T>(buf)?[..])
+    }
+
+    fn serialize<W: Write>(value: &T, writer: &mut W) -> Result<()> {
+        let mut data = Vec::new();
+        C::serialize(value, &mut data)?;
+        let split = data.len().min(8);
+        let compressed = T::compress(&data[split..]);
+        let len = u32::try_from(compressed.len()).map_err(|_| ErrorCode::AccountDidNotSerialize)?;
+        writer
+            .write_all(&data[..split])
+            .and_then(|_| writer.write_all(&len.to_le_bytes()))
+            .and_then(|_| writer.write_all(&compressed))
+            .map_err(|_| ErrorCode::AccountDidNotSerialize.into())
+    }
+
+    fn is_uninitialized(data: &[u8]) -> bool {
+        C::is_uninitialized(data)
+    }
+}
+
+/// Writer that only copies the bytes falling in `range` into `dst`, at the
+/// same offsets, discarding the rest.
+struct RangeWriter<'a> {
+    dst: &'a mut [u8],
+    range: Range<usize>,
+    pos: usize,
+}
+
+impl<'a> Write for RangeWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let start = self.pos.max(self.range.start);
+        let end = (self.pos + buf.len()).min(self.range.end);
+        if start < end {
+            self.dst[start..end].copy_from_slice(&buf[start - self.pos..end - self.pos]);
+        }
+        self.pos += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writer that discards its input, counting the bytes written.
+#[derive(Default)]
+struct CountingWriter {
+    len: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.len += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writer that forwards its input into a `Hasher`.
+struct HashWriter<'h, H: Hasher> {
+    hasher: &'h mut H,
+}
+
+impl<'h, H: Hasher> Write for HashWriter<'h, H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.hasher.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The depth [`Nested`] values may be nested to when deserializing, unless
+/// overridden with [`OrphanAccount::try_from_max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+thread_local! {
+    // The current and maximum depth of the `Nested` values being
+    // deserialized, and whether the maximum was exceeded.
+    static NESTING: Cell<(usize, usize, bool)> = Cell::new((0, DEFAULT_MAX_DEPTH, false));
+}
+
+/// A boxed value counting towards the nesting depth of the data it is
+/// deserialized from, for the recursive fields of account types, e.g. the
+/// `Option<Nested<Node>>` child of a `Node`.
+///
+/// Deserializing data nested deeper than [`DEFAULT_MAX_DEPTH`], or the depth
+/// given to [`OrphanAccount::try_from_max_depth`], fails instead of
+/// overflowing the stack on untrusted data.
+///
+/// The borsh derives bound the type of every field, which rustc can't
+/// resolve for a type containing itself, so a recursive type implements
+/// [`AnchorSerialize`] and [`AnchorDeserialize`] by hand and may then be
+/// used in a derived account type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Nested<T>(pub Box<T>);
+
+impl<T> Deref for Nested<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Nested<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: AnchorSerialize> AnchorSerialize for Nested<T> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+impl<T: AnchorDeserialize> AnchorDeserialize for Nested<T> {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let (depth, max, exceeded) = NESTING.with(Cell::get);
+        if depth >= max {
+            NESTING.with(|nesting| nesting.set((depth, max, true)));
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Nesting too deep",
+            ));
+        }
+        NESTING.with(|nesting| nesting.set((depth + 1, max, exceeded)));
+        let value = T::deserialize(buf);
+        NESTING.with(|nesting| {
+            let (depth, max, exceeded) = nesting.get();
+            nesting.set((depth - 1, max, exceeded));
+        });
+        Ok(Nested(Box::new(value?)))
+    }
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static DISCRIMINATORS: std::cell::RefCell<BTreeMap<[u8; 8], &'static str>> =
+        std::cell::RefCell::new(BTreeMap::new());
+}
+
+/// Records the discriminator of `T` in a debug-mode registry, failing with
+/// [`ErrorCode::AccountDiscriminatorCollision`] if another type was already
+/// registered with the same discriminator.
+///
+/// Once registered, [`OrphanAccount::try_from`] logs a warning when
+/// deserializing an account whose discriminator belongs to a different type.
+/// The registry only exists when `debug_assertions` are enabled; otherwise
+/// this function does nothing.
+pub fn register_discriminator<T: Discriminator>() -> Result<()> {
+    #[cfg(debug_assertions)]
+    {
+        let name = std::any::type_name::<T>();
+        let existing = DISCRIMINATORS.with(|registry| {
+            *registry
+                .borrow_mut()
+                .entry(T::discriminator())
+                .or_insert(name)
+        });
+        if existing != name {
+            solana_program::msg!("Discriminator of {} collides with {}", name, existing);
+            return Err(ErrorCode::AccountDiscriminatorCollision.into());
+        }
+    }
+    Ok(())
+}
+
+/// Receives the compute cost of every deserialization by
+/// [`OrphanAccount::try_from`], once installed with [`set_profiler_sink`],
+/// e.g. to aggregate the costs of an orphan heavy instruction and log a
+/// summary.
+#[cfg(feature = "orphan-profiler")]
+pub trait ProfilerSink {
+    fn record(&self, key: &Pubkey, compute_units: u64);
+}
+
+// The installed sink and the meter returning the remaining compute units.
+#[cfg(feature = "orphan-profiler")]
+type Profiler = (Box<dyn ProfilerSink>, fn() -> u64);
+
+#[cfg(feature = "orphan-profiler")]
+thread_local! {
+    static PROFILER: std::cell::RefCell<Option<Profiler>> = std::cell::RefCell::new(None);
+}
+
+/// Installs `sink` to receive the compute units used by each orphan
+/// deserialization, measured as the difference of `remaining_units` before
+/// and after it. Replaces and returns any previously installed sink.
+///
+/// Programs built against this version of Solana can't read their remaining
+/// compute units, so `remaining_units` must come from the runtime the
+/// program is profiled in, e.g. a mock meter in host tests.
+#[cfg(feature = "orphan-profiler")]
+pub fn set_profiler_sink(
+    sink: Box<dyn ProfilerSink>,
+    remaining_units: fn() -> u64,
+) -> Option<Box<dyn ProfilerSink>> {
+    PROFILER.with(|profiler| {
+        profiler
+            .replace(Some((sink, remaining_units)))
+            .map(|(sink, _)| sink)
+    })
+}
+
+/// Uninstalls and returns the sink installed with [`set_profiler_sink`].
+#[cfg(feature = "orphan-profiler")]
+pub fn take_profiler_sink() -> Option<Box<dyn ProfilerSink>> {
+    PROFILER.with(|profiler| profiler.borrow_mut().take().map(|(sink, _)| sink))
+}
+
+// Reports the units used between its creation and drop to the installed
+// sink, if any.
+#[cfg(feature = "orphan-profiler")]
+struct ProfileGuard {
+    key: Pubkey,
+    start: Option<u64>,
+}
+
+#[cfg(feature = "orphan-profiler")]
+impl ProfileGuard {
+    fn start(key: &Pubkey) -> Self {
+        let start = PROFILER.with(|profiler| {
+            profiler
+                .borrow()
+                .as_ref()
+                .map(|(_, remaining_units)| remaining_units())
+        });
+        Self { key: *key, start }
+    }
+}
+
+#[cfg(feature = "orphan-profiler")]
+impl Drop for ProfileGuard {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            PROFILER.with(|profiler| {
+                if let Some((sink, remaining_units)) = profiler.borrow().as_ref() {
+                    sink.record(&self.key, start.saturating_sub(remaining_units()));
+                }
+            });
+        }
+    }
+}
+
+/// Returns the type registered with [`register_discriminator`] for the
+/// discriminator at the start of `data`, if any.
+pub fn registered_type(data: &[u8]) -> Option<&'static str> {
+    #[cfg(debug_assertions)]
+    if let Some(discriminator) = data.get(..8) {
+        return DISCRIMINATORS.with(|registry| registry.borrow().get(discriminator).copied());
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = data;
+    None
+}
+
+/// Migrates accounts stored in an older layout to the current one, for use
+/// with [`OrphanAccount::try_from_migrating`].
+pub trait Migrate: Sized {
+    /// Converts `data`, the raw data of an account that failed to deserialize
+    /// as the current layout, including the discriminator.
+    fn migrate(data: &[u8]) -> Result<Self>;
+}
+
+/// Deserializes accounts whose layout depends on runtime context, e.g. a
+/// decryption key or a registry of layouts, for use with
+/// [`OrphanAccount::try_from_with_ctx`]. Other constructors, such as
+/// [`OrphanAccount::try_from`], ignore it.
+pub trait ContextualDeserialize<X: ?Sized>: Sized {
+    /// Deserializes `data`, the full account data including the
+    /// discriminator, which implementations are expected to check.
+    fn deserialize_with_ctx(data: &[u8], ctx: &X) -> Result<Self>;
+}
+
+/// Declares the versions of an account type that can be read, for accounts
+/// storing a version byte right after the discriminator. Used by
+/// [`OrphanAccount::try_from_versioned`].
+pub trait SupportedVersions {
+    const RANGE: RangeInclusive<u8>;
+}
+
+/// Declares a type tag stored at a fixed offset of the account data, for
+/// account types that share a discriminator and are told apart by a later
+/// byte. Used by [`OrphanAccount::try_from_tagged`].
+pub trait Tagged {
+    const TAG: u8;
+
+    /// Returns the offset of the tag in the account data, discriminator
+    /// included.
+    fn tag_offset() -> usize;
+}
+
+/// Exposes the discriminator of the only instruction allowed to write an
+/// account, if any, for use with [`OrphanAccount::try_from_selector_gated`].
+pub trait WriteSelector {
+    fn write_selector(&self) -> Option<[u8; 8]>;
+}
+
+/// Reports how many bytes of an account, discriminator included, hold
+/// meaningful content, for use with [`OrphanAccount::logical_len`].
+pub trait LogicalLen {
+    fn logical_len(&self) -> usize;
+}
+
+/// Computes the checksum stored in a trailer following an account, for use
+/// with [`OrphanAccount::try_from_checksummed`].
+pub trait Checksummed {
+    /// Returns the checksum of `data`, the serialized account preceding the
+    /// trailer, discriminator included.
+    fn checksum(data: &[u8]) -> Vec<u8>;
+}
+
+type ChecksumFn = fn(&[u8]) -> Vec<u8>;
+
+/// Shrinks an account to a smaller logical length, for use with
+/// [`OrphanAccount::truncate`].
+pub trait Truncate: LogicalLen {
+    /// Drops content so that [`logical_len`](LogicalLen::logical_len) is at
+    /// most `len`.
+    fn truncate_to(&mut self, len: usize);
+}
+
+/// Exposes the keys of the accounts an account refers to, e.g. the vault of a
+/// pool, for use with [`OrphanAccount::validate_refs`].
+pub trait HasReferences {
+    fn references(&self) -> Vec<Pubkey>;
+}
+
+/// Exposes a version or nonce of an account that advances with every write,
+/// for use with [`OrphanAccount::with_version_bump`].
+pub trait Versioned {
+    fn version_mut(&mut self) -> &mut u64;
+}
+
+/// Exposes the unix timestamp at which an account was last written, for use
+/// with [`OrphanAccount::with_timestamp`].
+pub trait Timestamped {
+    fn last_modified_mut(&mut self) -> &mut i64;
+}
+
+/// Exposes the authority of an account, for use with
+/// [`OrphanAccount::require_authority`].
+pub trait HasAuthority {
+    fn authority(&self) -> &Pubkey;
+}
+
+/// Lists the named fields that differ between two values, for use with
+/// [`OrphanAccount::diff`]. Derive it with `#[derive(Diffable)]`, which
+/// compares every field with `PartialEq`.
+pub trait Diffable {
+    fn diff_fields(&self, previous: &Self) -> Vec<FieldChange>;
+}
+
+/// A field reported as changed by [`Diffable::diff_fields`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+}
+
+/// Lists the named fields of a value with their `Debug` representation, for
+/// use with [`OrphanAccount::debug_dump`]. Derive it with
+/// `#[derive(DebugFields)]`.
+pub trait DebugFields {
+    fn debug_fields(&self) -> Vec<(&'static str, String)>;
+}
+
+/// Maximum length, in bytes, of a field value logged by
+/// [`OrphanAccount::debug_dump`]. Longer values are truncated.
+#[cfg(feature = "anchor-debug")]
+pub const DEBUG_DUMP_MAX_VALUE_LEN: usize = 64;
+
+/// Exposes a bitflags field of an account, for use with
+/// [`OrphanAccount::require_flags`].
+pub trait HasFlags {
+    fn flags(&self) -> u64;
+}
+
+/// An account update received from a Geyser plugin or gRPC stream, for use
+/// with [`OrphanAccount::try_from_geyser_update`]. Implement it for the
+/// update type of the stream being consumed.
+#[cfg(feature = "client")]
+pub trait AccountUpdate {
+    fn pubkey(&self) -> Pubkey;
+    fn owner(&self) -> Pubkey;
+    fn lamports(&self) -> u64;
+    fn data(&self) -> &[u8];
+}
+
+/// Describes the reserved padding of an account, for use with
+/// [`OrphanAccount::try_from_padded`].
+pub trait Padded {
+    /// Returns the ranges of the account data, discriminator included,
+    /// reserved for future fields.
+    fn padding_ranges() -> Vec<Range<usize>>;
+}
+
+/// Event emitted by `exit` when it writes changed bytes to an account
+/// constructed with [`OrphanAccount::with_mutation_events`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrphanMutated {
+    pub key: Pubkey,
+    /// The first 8 bytes of the account data after the write.
+    pub discriminator: [u8; 8],
+}
+
+impl Discriminator for OrphanMutated {
+    // sha256("event:OrphanMutated")[..8]
+    fn discriminator() -> [u8; 8] {
+        [220, 91, 211, 143, 81, 209, 24, 247]
+    }
+}
+
+impl crate::Event for OrphanMutated {
+    fn data(&self) -> Vec<u8> {
+        let mut data = Self::discriminator().to_vec();
+        data.append(&mut self.try_to_vec().unwrap());
+        data
+    }
+}
+
+/// Event emitted by [`OrphanAccount::emit_for`], wrapping another event with
+/// the key of the account it concerns so indexers can tell its source.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrphanEvent {
+    pub key: Pubkey,
+    /// The wrapped event, discriminator included, as logged by `emit!`.
+    pub event: Vec<u8>,
+}
+
+impl Discriminator for OrphanEvent {
+    // sha256("event:OrphanEvent")[..8]
+    fn discriminator() -> [u8; 8] {
+        [226, 209, 39, 250, 232, 207, 104, 106]
+    }
+}
+
+impl crate::Event for OrphanEvent {
+    fn data(&self) -> Vec<u8> {
+        let mut data = Self::discriminator().to_vec();
+        data.append(&mut self.try_to_vec().unwrap());
+        data
+    }
+}
+
+// The framework errors `OrphanAccount::try_from` can fail with.
+const MAPPED_ERRORS: [ErrorCode; 5] = [
+    ErrorCode::AccountNotInitialized,
+    ErrorCode::AccountClosed,
+    ErrorCode::AccountDiscriminatorNotFound,
+    ErrorCode::AccountDiscriminatorMismatch,
+    ErrorCode::AccountDidNotDeserialize,
+];
+
+/// Outcome of [`OrphanAccount::reload_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReloadStatus {
+    /// The account data changed and was deserialized again.
+    Reloaded,
+    /// The account was closed, e.g. by the callee of a CPI. The in-memory
+    /// value is left untouched.
+    Closed,
+    /// The account data still matches the in-memory value.
+    Unchanged,
+}
+
+/// Balance of an account recorded when an [`OrphanAccount`] is constructed,
+/// returned by [`OrphanAccount::lamport_snapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LamportSnapshot {
+    pub key: Pubkey,
+    pub lamports: u64,
+}
+
+impl LamportSnapshot {
+    /// Records the current balance of `info`.
+    pub fn capture(info: &AccountInfo) -> Self {
+        Self {
+            key: *info.key,
+            lamports: info.lamports(),
+        }
+    }
+}
+
+/// A bloom filter over account keys, for use with
+/// [`OrphanAccount::assert_in_filter`] when the set of accepted accounts is
+/// too large to scan, e.g. stored in a config account.
+///
+/// Membership is probabilistic in one direction only: a key that was
+/// inserted is always found, but a key that wasn't may be found too, with a
+/// probability that grows with the number of keys inserted and shrinks with
+/// the number of bits. A filter therefore only proves a key is *not* in the
+/// set, and must not be the sole check guarding an account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    num_hashes: u8,
+}
+
+impl BloomFilter {
+    /// Creates an empty filter of `num_bits` bits, rounded up to a whole
+    /// byte, setting `num_hashes` bits per key.
+    pub fn new(num_bits: usize, num_hashes: u8) -> Self {
+        Self {
+            bits: vec![0; (num_bits.max(1) + 7) / 8],
+            num_hashes: num_hashes.max(1),
+        }
+    }
+
+    /// Adds `key` to the filter.
+    pub fn insert(&mut self, key: &Pubkey) {
+        for bit in self.bit_indices(key) {
+            self.bits[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    /// Returns false if `key` is definitely not in the filter, and true if it
+    /// may be.
+    pub fn contains(&self, key: &Pubkey) -> bool {
+        self.bit_indices(key)
+            .all(|bit| self.bits[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    // Derives the bits of `key` from two halves of its hash, with double
+    // hashing.
+    fn bit_indices(&self, key: &Pubkey) -> impl Iterator<Item = usize> {
+        let hash = solana_program::hash::hashv(&[b"bloom", key.as_ref()]).to_bytes();
+        let h1 = u64::from_le_bytes(hash[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(hash[8..16].try_into().unwrap());
+        let num_bits = self.bits.len() as u64 * 8;
+        (0..self.num_hashes as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+}
+
+/// Wrapper around [`AccountInfo`](crate::solana_program::account_info::AccountInfo)
+/// that deserializes the underlying data into a Rust type without verifying
+/// which program owns the account.
+///
+/// `OrphanAccount` is meant for accounts that are shared between programs,
+/// where the data layout is known but the owner is not fixed at compile
+/// time. Because *any* program can create an account with the right bytes,
+/// the owner must be validated some other way, e.g. with the `owner`
+/// constraint, before the data is trusted.
+///
+/// Checks:
+///
+/// - `!(OrphanAccount.info.owner == SystemProgram && OrphanAccount.info.lamports() == 0)`
+///
+/// On exit, the data is only persisted if the account is owned by the
+/// currently executing program, since the runtime rejects writes to any
+/// other account.
+///
+/// The data is read and written through a [`Codec`], which defaults to
+/// [`AnchorCodec`].
+///
+/// # Example
+/// ```ignore
+/// #[derive(Accounts)]
+/// pub struct ReadShared<'info> {
+///     #[account(owner = registry.key())]
+///     pub shared: OrphanAccount<'info, SharedConfig>,
+///     pub registry: UncheckedAccount<'info>,
+/// }
+/// ```
+///
+/// # Access Control
+///
+/// The fields of the account can be read through `Context` in an
+/// `#[access_control]` function like any other account, thanks to deref.
+/// As `OrphanAccount` implements `AsRef` for its [`AccountInfo`], which
+/// `as_ref` calls may not be able to tell apart from other impls, use
+/// [`info`](OrphanAccount::info) to read the owner, key or lamports:
+///
+/// ```ignore
+/// fn check_shared(ctx: &Context<ReadShared>) -> Result<()> {
+///     require!(ctx.accounts.shared.enabled, MyError::Disabled);
+///     require_keys_eq!(*ctx.accounts.shared.info().owner, registry::ID);
+///     Ok(())
+/// }
+///
+/// #[access_control(check_shared(&ctx))]
+/// pub fn read_shared(ctx: Context<ReadShared>) -> Result<()> {
+///     // ...
+/// }
+/// ```
+///
+/// # CPI Return Buffers
+///
+/// An `OrphanAccount` can be handed to a program that returns its result by
+/// writing into a caller provided account, rather than with
+/// `set_return_data`. The in-memory value is not updated by the callee, so
+/// [`reload`](OrphanAccount::reload) the account after the invocation, both
+/// to read the result and so that `exit` doesn't overwrite it with the stale
+/// value:
+///
+/// ```ignore
+/// let ix = Instruction {
+///     program_id: quoter::ID,
+///     accounts: ctx.accounts.quote.to_account_metas(None),
+///     data: request.try_to_vec()?,
+/// };
+/// invoke(&ix, &ctx.accounts.quote.to_account_infos())?;
+/// ctx.accounts.quote.reload()?;
+/// let price = ctx.accounts.quote.price;
+/// ```
+///
+/// The deserialized value is held inline, so a large `T` can overflow the
+/// stack. `#[orphan_stack_budget(<bytes>)]` on the accounts struct turns this
+/// into a compile error:
+///
+/// ```compile_fail
+/// # use anchor_lang::prelude::*;
+/// # declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+/// #[account]
+/// pub struct Large {
+///     pub values: [u64; 1024],
+/// }
+///
+/// #[derive(Accounts)]
+/// #[orphan_stack_budget(4096)]
+/// pub struct ReadLarge<'info> {
+///     pub large: OrphanAccount<'info, Large>,
+/// }
+/// ```
+pub struct OrphanAccount<'info, T: Clone, C: Codec<T> = AnchorCodec> {
+    account: T,
+    info: AccountInfo<'info>,
+    initial_len: usize,
+    initial_lamports: u64,
+    // Only allocated once a behaviour is opted into, keeping plain accounts
+    // small and their `exit` a single check away from a plain write back.
+    policy: Option<Box<ExitPolicy<T>>>,
+    codec: PhantomData<C>,
+}
+
+impl<'info, T: Clone, C: Codec<T>> Clone for OrphanAccount<'info, T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            account: self.account.clone(),
+            info: self.info.clone(),
+            initial_len: self.initial_len,
+            initial_lamports: self.initial_lamports,
+            policy: self.policy.clone(),
+            codec: PhantomData,
+        }
+    }
+}
+
+impl<'info, T: Clone + fmt::Debug, C: Codec<T>> fmt::Debug for OrphanAccount<'info, T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrphanAccount")
+            .field("account", &self.account)
+            .field("info", &self.info)
+            .field("initial_len", &self.initial_len)
+            .field("initial_lamports", &self.initial_lamports)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+// The opt-in behaviours of an `OrphanAccount`, mostly applied by `exit`.
+struct ExitPolicy<T> {
+    migrated: bool,
+    legacy: bool,
+    lazy_owner: bool,
+    writer: Option<Pubkey>,
+    emit_mutations: bool,
+    // The leading bytes of the current instruction's data and the
+    // discriminator of the instruction allowed to write the account.
+    write_selector: Option<(Vec<u8>, [u8; 8])>,
+    // The version accessor of `T`, if `exit` bumps the version.
+    version_bump: Option<fn(&mut T) -> &mut u64>,
+    // The last modified accessor of `T`, if `exit` stamps the write time.
+    timestamp: Option<fn(&mut T) -> &mut i64>,
+    // The logical length of `T`, if `exit` zeroes the data following it.
+    zero_tail: Option<fn(&T) -> usize>,
+    // The checksum of `T`, if the account has a checksum trailer.
+    checksum: Option<ChecksumFn>,
+    // Whether the data was written directly, so `exit` must not overwrite it.
+    raw_written: bool,
+    // The current version, if the in-memory value was upgraded to it, which
+    // `exit` also stamps on data written directly.
+    version_stamp: Option<u8>,
+}
+
+impl<T> Default for ExitPolicy<T> {
+    fn default() -> Self {
+        Self {
+            migrated: false,
+            legacy: false,
+            lazy_owner: false,
+            writer: None,
+            emit_mutations: false,
+            write_selector: None,
+            version_bump: None,
+            timestamp: None,
+            zero_tail: None,
+            checksum: None,
+            raw_written: false,
+            version_stamp: None,
+        }
+    }
+}
+
+impl<T> Clone for ExitPolicy<T> {
+    fn clone(&self) -> Self {
+        Self {
+            migrated: self.migrated,
+            legacy: self.legacy,
+            lazy_owner: self.lazy_owner,
+            writer: self.writer,
+            emit_mutations: self.emit_mutations,
+            write_selector: self.write_selector.clone(),
+            version_bump: self.version_bump,
+            timestamp: self.timestamp,
+            zero_tail: self.zero_tail,
+            checksum: self.checksum,
+            raw_written: self.raw_written,
+            version_stamp: self.version_stamp,
+        }
+    }
+}
+
+impl<T> fmt::Debug for ExitPolicy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExitPolicy")
+            .field("migrated", &self.migrated)
+            .field("legacy", &self.legacy)
+            .field("lazy_owner", &self.lazy_owner)
+            .field("writer", &self.writer)
+            .field("emit_mutations", &self.emit_mutations)
+            .field("write_selector", &self.write_selector)
+            .field("version_bump", &self.version_bump.is_some())
+            .field("timestamp", &self.timestamp.is_some())
+            .field("zero_tail", &self.zero_tail.is_some())
+            .field("checksum", &self.checksum.is_some())
+            .field("raw_written", &self.raw_written)
+            .field("version_stamp", &self.version_stamp)
+            .finish()
+    }
+}
+
+impl<'a, T: Clone, C: Codec<T>> OrphanAccount<'a, T, C> {
+    fn new(info: AccountInfo<'a>, account: T) -> OrphanAccount<'a, T, C> {
+        Self {
+            initial_len: info.data_len(),
+            initial_lamports: info.lamports(),
+            info,
+            account,
+            policy: None,
+            codec: PhantomData,
+        }
+    }
+
+    // Returns the policy of the account, allocating it on first use.
+    fn policy_mut(&mut self) -> &mut ExitPolicy<T> {
+        self.policy.get_or_insert_with(Default::default)
+    }
+
+    fn clear_raw_written(&mut self) {
+        if let Some(policy) = &mut self.policy {
+            policy.raw_written = false;
+        }
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount`.
+    ///
+    /// With the `legacy-fallback` feature, an account whose discriminator
+    /// doesn't match, e.g. one created before the program adopted
+    /// discriminators, is read with the codec's
+    /// [`deserialize_unchecked`](Codec::deserialize_unchecked) instead and
+    /// marked with [`is_legacy`](Self::is_legacy). `exit` then writes the
+    /// account back in full, normalizing it to the current layout.
+    #[inline(never)]
+    pub fn try_from(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>> {
+        check_initialized(info)?;
+        Self::try_from_data(info)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), but without rejecting accounts that hold
+    /// zero lamports, e.g. a program owned account midway through a
+    /// migration that moves its lamports out and back in again.
+    ///
+    /// Closed, uninitialized and mismatched data is still rejected, but the
+    /// lamports are the only sign that an account owned by the system
+    /// program was never created or was drained by the runtime. Its data may
+    /// be left over from a garbage collected account and must not be trusted,
+    /// so only use this for accounts whose owner is checked separately.
+    pub fn try_from_allow_zero_lamports(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>> {
+        if info
+            .try_borrow_data()?
+            .starts_with(&crate::__private::CLOSED_ACCOUNT_DISCRIMINATOR)
+        {
+            return Err(ErrorCode::AccountClosed.into());
+        }
+        Self::try_from_data(info)
+    }
+
+    // The data checks of `try_from`, following the lamports check.
+    fn try_from_data(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>> {
+        #[cfg(feature = "orphan-profiler")]
+        let _profile = ProfileGuard::start(info.key);
+        let mut data: &[u8] = &info.try_borrow_data()?;
+        if C::is_uninitialized(data) {
+            return Err(ErrorCode::AccountNotInitialized.into());
+        }
+        if let Some(registered) = registered_type(data) {
+            if registered != std::any::type_name::<T>() {
+                solana_program::msg!(
+                    "Warning: deserializing {} from an account of type {}",
+                    std::any::type_name::<T>(),
+                    registered
+                );
+            }
+        }
+        #[cfg(feature = "legacy-fallback")]
+        let unchecked = data;
+        let account = match C::deserialize(&mut data) {
+            #[cfg(feature = "legacy-fallback")]
+            Err(e) if e == ErrorCode::AccountDiscriminatorMismatch.into() => {
+                let mut account = OrphanAccount::new(
+                    info.clone(),
+                    C::deserialize_unchecked(&mut &unchecked[..])?,
+                );
+                account.policy_mut().legacy = true;
+                return Ok(account);
+            }
+            result => result?,
+        };
+        Ok(OrphanAccount::new(info.clone(), account))
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), failing with
+    /// [`ErrorCode::AccountNestingTooDeep`] if its [`Nested`] values are
+    /// nested deeper than `max_depth`, rather than [`DEFAULT_MAX_DEPTH`].
+    pub fn try_from_max_depth(
+        info: &AccountInfo<'a>,
+        max_depth: usize,
+    ) -> Result<OrphanAccount<'a, T, C>> {
+        let previous = NESTING.with(|nesting| nesting.replace((0, max_depth, false)));
+        let result = Self::try_from(info);
+        let (_, _, exceeded) = NESTING.with(|nesting| nesting.replace(previous));
+        if exceeded {
+            return Err(Error::from(ErrorCode::AccountNestingTooDeep).with_account_name(info.key));
+        }
+        result
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from). If `reject_nonzero` is set, fails with
+    /// [`ErrorCode::AccountPaddingNotZero`] if any byte of the
+    /// [`Padded`] ranges isn't zero, e.g. when a foreign program has started
+    /// using its reserved space. Otherwise padding is ignored.
+    pub fn try_from_padded(
+        info: &AccountInfo<'a>,
+        reject_nonzero: bool,
+    ) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: Padded,
+    {
+        let account = Self::try_from(info)?;
+        if reject_nonzero {
+            let data = info.try_borrow_data()?;
+            for range in T::padding_ranges() {
+                let padding = data.get(range.clone()).ok_or_else(|| {
+                    Error::from(ErrorCode::AccountDidNotDeserialize)
+                        .with_values((range.end, data.len()))
+                })?;
+                if let Some(offset) = padding.iter().position(|byte| *byte != 0) {
+                    return Err(Error::from(ErrorCode::AccountPaddingNotZero)
+                        .with_account_name(info.key)
+                        .with_values((range.start + offset, format!("{:?}", range))));
+                }
+            }
+        }
+        Ok(account)
+    }
+
+    /// Loads the account like [`try_from`](Self::try_from) if it exists, or
+    /// else creates it with `space` bytes, owned by `program_id` and funded
+    /// by `payer` with the rent exempt minimum, and initializes it with
+    /// `default`, e.g. for idempotent instructions sharing an account.
+    ///
+    /// An account is created while it is owned by the system program. As
+    /// with the `init` constraint, lamports already sent to the address are
+    /// topped up to the rent exempt minimum rather than blocking creation.
+    /// The system program CPIs require `info` to sign, so it must be a
+    /// keypair account signing the transaction.
+    pub fn init_or_load(
+        info: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        space: usize,
+        program_id: &Pubkey,
+        default: impl FnOnce() -> T,
+    ) -> Result<OrphanAccount<'a, T, C>> {
+        if info.owner != &system_program::ID {
+            return Self::try_from(info);
+        }
+        let rent = Rent::get()?.minimum_balance(space);
+        let current_lamports = info.lamports();
+        if current_lamports == 0 {
+            let ix = system_instruction::create_account(
+                payer.key,
+                info.key,
+                rent,
+                space as u64,
+                program_id,
+            );
+            solana_program::program::invoke(&ix, &[payer.clone(), info.clone()])?;
+        } else {
+            let required_lamports = rent.saturating_sub(current_lamports);
+            if required_lamports > 0 {
+                let ix = system_instruction::transfer(payer.key, info.key, required_lamports);
+                solana_program::program::invoke(&ix, &[payer.clone(), info.clone()])?;
+            }
+            let ix = system_instruction::allocate(info.key, space as u64);
+            solana_program::program::invoke(&ix, std::slice::from_ref(info))?;
+            let ix = system_instruction::assign(info.key, program_id);
+            solana_program::program::invoke(&ix, std::slice::from_ref(info))?;
+        }
+        let account = OrphanAccount::new(info.clone(), default());
+        account.write_value(&account.account)?;
+        Ok(account)
+    }
+
+    /// Deserializes the [`AccountInfo`] returned by `resolver` like
+    /// [`try_from`](Self::try_from), for accounts looked up dynamically
+    /// rather than passed in directly. Errors from `resolver` are returned
+    /// unchanged.
+    pub fn try_from_resolver(
+        resolver: impl FnOnce() -> Result<AccountInfo<'a>>,
+    ) -> Result<OrphanAccount<'a, T, C>> {
+        Self::try_from(&resolver()?)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), passing the framework error codes it can
+    /// fail with through `map`, e.g. to surface them as the program's own
+    /// errors. Other errors, such as borrow failures, are returned unchanged.
+    pub fn try_from_mapped(
+        info: &AccountInfo<'a>,
+        map: impl Fn(ErrorCode) -> Error,
+    ) -> Result<OrphanAccount<'a, T, C>> {
+        Self::try_from(info).map_err(|error| {
+            let code = match &error {
+                Error::AnchorError(anchor) => MAPPED_ERRORS
+                    .iter()
+                    .find(|code| u32::from(**code) == anchor.error_code_number),
+                Error::ProgramError(_) => None,
+            };
+            match code {
+                Some(code) => map(*code),
+                None => error,
+            }
+        })
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), falling back to [`Migrate::migrate`] if
+    /// the data can't be deserialized as the current layout of `T`.
+    ///
+    /// A migrated account is marked with [`is_migrated`](Self::is_migrated)
+    /// and always written back in full on exit, in the current layout.
+    pub fn try_from_migrating(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: Migrate,
+    {
+        check_initialized(info)?;
+        let data: &[u8] = &info.try_borrow_data()?;
+        if C::is_uninitialized(data) {
+            return Err(ErrorCode::AccountNotInitialized.into());
+        }
+        if let Ok(account) = C::deserialize(&mut &data[..]) {
+            return Ok(OrphanAccount::new(info.clone(), account));
+        }
+        let mut account = OrphanAccount::new(info.clone(), T::migrate(data)?);
+        account.policy_mut().migrated = true;
+        Ok(account)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` with
+    /// [`ContextualDeserialize::deserialize_with_ctx`], passing it `ctx`,
+    /// rather than with the codec.
+    ///
+    /// `exit` still writes the account with the codec, so an account whose
+    /// stored layout differs from the codec's, e.g. an encrypted one, must not
+    /// be mutated.
+    pub fn try_from_with_ctx<X: ?Sized>(
+        info: &AccountInfo<'a>,
+        ctx: &X,
+    ) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: ContextualDeserialize<X>,
+    {
+        check_initialized(info)?;
+        let data: &[u8] = &info.try_borrow_data()?;
+        if C::is_uninitialized(data) {
+            return Err(ErrorCode::AccountNotInitialized.into());
+        }
+        let account = T::deserialize_with_ctx(data, ctx)?;
+        Ok(OrphanAccount::new(info.clone(), account))
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), deferring the owner check to `exit`.
+    ///
+    /// Normally `exit` silently skips accounts not owned by the program. An
+    /// account constructed this way instead makes `exit` fail with
+    /// [`ErrorCode::AccountOwnedByWrongProgram`] if it was modified, so that
+    /// a write to a foreign account is reported rather than lost, while
+    /// unmodified accounts are never checked.
+    pub fn try_from_lazy_owner(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>> {
+        let mut account = Self::try_from(info)?;
+        account.policy_mut().lazy_owner = true;
+        Ok(account)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), sealed so that only `writer_program`
+    /// may write it back.
+    ///
+    /// When running in any other program, `exit` fails with
+    /// [`ErrorCode::AccountWriteSealed`] if the account was modified, even if
+    /// that program owns it. Unmodified accounts can be read by any program.
+    pub fn try_from_write_sealed(
+        info: &AccountInfo<'a>,
+        writer_program: &Pubkey,
+    ) -> Result<OrphanAccount<'a, T, C>> {
+        let mut account = Self::try_from(info)?;
+        account.policy_mut().writer = Some(*writer_program);
+        Ok(account)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), restricting writes to the instruction
+    /// stored in the account, as returned by [`WriteSelector::write_selector`].
+    ///
+    /// If a selector is stored, `exit` fails with
+    /// [`ErrorCode::AccountWrongInstruction`] when the account was modified
+    /// and the data of the current instruction, read from the `instructions`
+    /// sysvar, doesn't start with it. The sysvar only holds top level
+    /// instructions, so for a CPI the selector is compared with the
+    /// instruction that started the call chain.
+    pub fn try_from_selector_gated(
+        info: &AccountInfo<'a>,
+        instructions: &AccountInfo<'a>,
+    ) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: WriteSelector,
+    {
+        let mut account = Self::try_from(info)?;
+        if let Some(selector) = account.account.write_selector() {
+            let current =
+                solana_program::sysvar::instructions::get_instruction_relative(0, instructions)?;
+            let len = current.data.len().min(8);
+            account.policy_mut().write_selector = Some((current.data[..len].to_vec(), selector));
+        }
+        Ok(account)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), then checks the trailer following the
+    /// serialized account holds its [`Checksummed::checksum`].
+    ///
+    /// Fails with [`ErrorCode::AccountChecksumMismatch`] if it doesn't, or
+    /// with [`ErrorCode::AccountDidNotDeserialize`] if the data is too short
+    /// to hold the trailer. `exit` writes the trailer anew after the account.
+    pub fn try_from_checksummed(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: Checksummed,
+    {
+        let mut account = Self::try_from(info)?;
+        let len = Self::len_of(&account.account)?;
+        let data = info.try_borrow_data()?;
+        let expected = T::checksum(&data[..len.min(data.len())]);
+        let stored = data.get(len..len + expected.len()).ok_or_else(|| {
+            Error::from(ErrorCode::AccountDidNotDeserialize)
+                .with_values((data.len(), len + expected.len()))
+        })?;
+        if stored != &expected[..] {
+            return Err(ErrorCode::AccountChecksumMismatch.into());
+        }
+        drop(data);
+        account.policy_mut().checksum = Some(T::checksum);
+        Ok(account)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), after checking the version byte following
+    /// the discriminator is within [`SupportedVersions::RANGE`]. Fails with
+    /// [`ErrorCode::AccountVersionUnsupported`] otherwise, without attempting
+    /// to deserialize an incompatible layout.
+    pub fn try_from_versioned(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: SupportedVersions,
+    {
+        check_initialized(info)?;
+        let version = match info.try_borrow_data()?.get(8) {
+            Some(version) => *version,
+            None => return Err(ErrorCode::AccountDidNotDeserialize.into()),
+        };
+        if !T::RANGE.contains(&version) {
+            return Err(Error::from(ErrorCode::AccountVersionUnsupported)
+                .with_values((version, format!("{:?}", T::RANGE))));
+        }
+        Self::try_from(info)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from_versioned`](Self::try_from_versioned), but with a floor of
+    /// `min` rather than the start of [`SupportedVersions::RANGE`]: older
+    /// accounts fail with [`ErrorCode::AccountVersionUnsupported`] instead of
+    /// being read or migrated.
+    ///
+    /// The account is read as if stored at the current version, the end of
+    /// [`SupportedVersions::RANGE`], so accounts read at an older supported
+    /// version are upgraded as `exit` writes them back. Until then, such an
+    /// account differs from its data, e.g. [`is_dirty`](Self::is_dirty) is
+    /// true and a [write sealed](Self::try_from_write_sealed) account may not
+    /// be exited by other programs.
+    pub fn try_from_min_version(info: &AccountInfo<'a>, min: u8) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: SupportedVersions,
+    {
+        check_initialized(info)?;
+        let version = match info.try_borrow_data()?.get(8) {
+            Some(version) => *version,
+            None => return Err(ErrorCode::AccountDidNotDeserialize.into()),
+        };
+        if version < min {
+            return Err(Error::from(ErrorCode::AccountVersionUnsupported)
+                .with_values((version, format!("{}..", min))));
+        }
+        let mut account = Self::try_from_versioned(info)?;
+        let current = *T::RANGE.end();
+        if version != current {
+            let mut data = info.try_borrow_data()?.to_vec();
+            data[8] = current;
+            account.account = C::deserialize(&mut &data[..])?;
+        }
+        account.policy_mut().version_stamp = Some(current);
+        Ok(account)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), after checking the byte at
+    /// [`Tagged::tag_offset`] is [`Tagged::TAG`]. Fails with
+    /// [`ErrorCode::AccountDiscriminatorMismatch`] otherwise, as the account
+    /// holds another type sharing the discriminator.
+    pub fn try_from_tagged(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: Tagged,
+    {
+        check_initialized(info)?;
+        let tag = match info.try_borrow_data()?.get(T::tag_offset()) {
+            Some(tag) => *tag,
+            None => return Err(ErrorCode::AccountDidNotDeserialize.into()),
+        };
+        if tag != T::TAG {
+            return Err(
+                Error::from(ErrorCode::AccountDiscriminatorMismatch).with_values((tag, T::TAG))
+            );
+        }
+        Self::try_from(info)
+    }
+
+    /// Makes `exit` emit an [`OrphanMutated`] event whenever it writes changed
+    /// bytes back to the account. Exits that leave the data untouched emit
+    /// nothing, while data written with
+    /// [`with_raw_data_mut`](Self::with_raw_data_mut) always counts as
+    /// changed.
+    pub fn with_mutation_events(mut self) -> Self {
+        self.policy_mut().emit_mutations = true;
+        self
+    }
+
+    /// Makes `exit` advance the version of the account, as returned by
+    /// [`Versioned::version_mut`], whenever it changes the stored bytes.
+    ///
+    /// The version written is always one more than the stored version, so
+    /// exiting several times bumps it once per persisted change, and any
+    /// version set on the in-memory value is ignored. The in-memory value
+    /// keeps its version, use [`reload`](Self::reload) to observe the new one.
+    pub fn with_version_bump(mut self) -> Self
+    where
+        T: Versioned,
+    {
+        self.policy_mut().version_bump = Some(T::version_mut);
+        self
+    }
+
+    /// Makes `exit` set the last modified time of the account, as returned by
+    /// [`Timestamped::last_modified_mut`], to the unix timestamp of the
+    /// [`Clock`] sysvar whenever it changes the stored bytes.
+    ///
+    /// Like [`with_version_bump`](Self::with_version_bump), a timestamp set
+    /// on the in-memory value is ignored and the in-memory value keeps its
+    /// timestamp. The sysvar is only read by exits that persist a change.
+    pub fn with_timestamp(mut self) -> Self
+    where
+        T: Timestamped,
+    {
+        self.policy_mut().timestamp = Some(T::last_modified_mut);
+        self
+    }
+
+    /// Returns true if the account was read without a matching discriminator,
+    /// which only happens with the `legacy-fallback` feature. See
+    /// [`try_from`](Self::try_from).
+    pub fn is_legacy(&self) -> bool {
+        self.policy.as_ref().map_or(false, |policy| policy.legacy)
+    }
+
+    /// Returns true if the account was migrated from an older layout by
+    /// [`try_from_migrating`](Self::try_from_migrating).
+    pub fn is_migrated(&self) -> bool {
+        self.policy.as_ref().map_or(false, |policy| policy.migrated)
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), after checking the account is owned by
+    /// one of the programs listed in [`Owners::owners`].
+    pub fn try_from_trusted(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>>
+    where
+        T: Owners,
+    {
+        Self::try_from_dynamic_owners(info, &T::owners())
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), after checking the account is owned by
+    /// one of the `allowed` programs, failing with
+    /// [`ErrorCode::AccountOwnedByWrongProgram`] otherwise.
+    ///
+    /// Unlike [`try_from_trusted`](Self::try_from_trusted), the allowed
+    /// owners are given at runtime, e.g. read from a config account.
+    pub fn try_from_dynamic_owners(
+        info: &AccountInfo<'a>,
+        allowed: &[Pubkey],
+    ) -> Result<OrphanAccount<'a, T, C>> {
+        if !allowed.contains(info.owner) {
+            return Err(ErrorCode::AccountOwnedByWrongProgram.into());
+        }
+        Self::try_from(info)
+    }
+
+    /// Returns a builder to deserialize the given `info` after applying a
+    /// combination of checks.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let shared = OrphanAccount::<SharedConfig>::checked(&info)
+    ///     .owner(&registry::ID)
+    ///     .rent_exempt(&Rent::get()?)
+    ///     .build()?;
+    /// ```
+    pub fn checked<'b>(info: &'b AccountInfo<'a>) -> OrphanAccountBuilder<'b, 'a, T, C> {
+        OrphanAccountBuilder {
+            info,
+            checks: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` like
+    /// [`try_from`](Self::try_from), unless its data is shorter than
+    /// `disc_threshold` bytes, in which case the discriminator is not checked,
+    /// as with [`try_from_unchecked`](Self::try_from_unchecked).
+    ///
+    /// This is meant for foreign accounts of a known small size, too short to
+    /// hold a discriminator along with their body.
+    pub fn try_from_auto(
+        info: &AccountInfo<'a>,
+        disc_threshold: usize,
+    ) -> Result<OrphanAccount<'a, T, C>> {
+        if info.data_len() < disc_threshold {
+            return Self::try_from_unchecked(info);
+        }
+        Self::try_from(info)
+    }
+
+    /// Checks that the discriminator of the account is that of the account
+    /// type `type_name` of `idl`, e.g. the IDL of the program owning a
+    /// foreign account, rather than of any type that happens to deserialize.
+    ///
+    /// Fails with [`ErrorCode::AccountDiscriminatorMismatch`] if the
+    /// discriminator differs or `idl` declares no such account type. Types
+    /// declared with a custom `discriminator_namespace` or
+    /// `discriminator_hash` always fail, as the IDL doesn't record either,
+    /// see [`account_discriminator`](crate::idl::account_discriminator).
+    #[cfg(feature = "client")]
+    pub fn assert_idl_account(&self, idl: &anchor_syn::idl::Idl, type_name: &str) -> Result<()> {
+        let data = self.info.try_borrow_data()?;
+        let declared = idl.accounts.iter().any(|account| account.name == type_name);
+        if !declared || !data.starts_with(&crate::idl::account_discriminator(type_name)) {
+            return Err(Error::from(ErrorCode::AccountDiscriminatorMismatch)
+                .with_account_name(self.info.key));
+        }
+        Ok(())
+    }
+
+    /// Validates and deserializes the data of the account `key`, owned by
+    /// `owner`, straight from a borrowed slice, e.g. a record of a memory
+    /// mapped snapshot, without copying it into an [`AccountInfo`].
+    ///
+    /// The checks are those of [`try_from`](Self::try_from), except that
+    /// lamports are not known, so accounts owned by the system program are
+    /// always rejected. Errors are named after `key`.
+    #[cfg(feature = "client")]
+    pub fn try_from_bytes_borrowed(key: &Pubkey, owner: &Pubkey, data: &[u8]) -> Result<T> {
+        let check = || {
+            if owner == &system_program::ID {
+                return Err(match data.is_empty() {
+                    true => ErrorCode::AccountNotInitialized.into(),
+                    false => ErrorCode::AccountClosed.into(),
+                });
+            }
+            if data.starts_with(&crate::__private::CLOSED_ACCOUNT_DISCRIMINATOR) {
+                return Err(ErrorCode::AccountClosed.into());
+            }
+            if C::is_uninitialized(data) {
+                return Err(ErrorCode::AccountNotInitialized.into());
+            }
+            C::deserialize(&mut &data[..])
+        };
+        check().map_err(|e| e.with_account_name(key))
+    }
+
+    /// Validates and deserializes the account carried by a Geyser `update`,
+    /// e.g. in an indexer, with the same checks as
+    /// [`try_from`](Self::try_from). Errors are named after the updated key.
+    #[cfg(feature = "client")]
+    pub fn try_from_geyser_update(update: &impl AccountUpdate) -> Result<T> {
+        let key = update.pubkey();
+        let owner = update.owner();
+        let mut lamports = update.lamports();
+        let mut data = update.data().to_vec();
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        OrphanAccount::<T, C>::try_from(&info)
+            .map(|account| account.account)
+            .map_err(|e| e.with_account_name(key))
+    }
+
+    /// Deserializes the given `info` into an `OrphanAccount` without checking
+    /// the account discriminator. Be careful when using this and avoid it if
+    /// possible.
+    #[inline(never)]
+    pub fn try_from_unchecked(info: &AccountInfo<'a>) -> Result<OrphanAccount<'a, T, C>> {
+        check_initialized(info)?;
+        let mut data: &[u8] = &info.try_borrow_data()?;
+        if C::is_uninitialized(data) {
+            return Err(ErrorCode::AccountNotInitialized.into());
+        }
+        Ok(OrphanAccount::new(
+            info.clone(),
+            C::deserialize_unchecked(&mut data)?,
+        ))
+    }
+
+    /// Reloads the account from storage. This is useful, for example, when
+    /// observing side effects after CPI.
+    ///
+    /// The data is borrowed anew from the [`AccountInfo`] on every reload.
+    /// The runtime updates the data slice shared by all clones of an
+    /// `AccountInfo` in place when an account is reallocated, including by a
+    /// CPI callee, so a reload after a realloc reads the data with its new
+    /// length rather than a stale region.
+    ///
+    /// If the data is already mutably borrowed, e.g. by a caller further up
+    /// a re-entrant CPI, this fails with
+    /// [`ProgramError::AccountBorrowFailed`](solana_program::program_error::ProgramError::AccountBorrowFailed)
+    /// instead of panicking, leaving the in-memory value untouched.
+    pub fn reload(&mut self) -> Result<()> {
+        self.account = C::deserialize(&mut &self.info.try_borrow_data()?[..])?;
+        self.clear_raw_written();
+        Ok(())
+    }
+
+    /// Reloads the account from storage like [`reload`](Self::reload),
+    /// retrying up to `attempts` times, e.g. while a borrow conflict from an
+    /// unwinding CPI frame resolves. At least one attempt is always made and
+    /// the error of the last one is returned.
+    ///
+    /// On-chain execution is single threaded, so this is mostly useful in
+    /// test harnesses and off-chain simulators.
+    pub fn reload_retry(&mut self, attempts: usize) -> Result<()> {
+        let mut result = self.reload();
+        for _ in 1..attempts {
+            if result.is_ok() {
+                break;
+            }
+            std::hint::spin_loop();
+            result = self.reload();
+        }
+        result
+    }
+
+    /// Reloads the account from storage like [`reload`](Self::reload), but
+    /// reports a closed account as [`ReloadStatus::Closed`] instead of
+    /// failing to deserialize it.
+    ///
+    /// An account is considered closed if it has no lamports, no data, or
+    /// starts with the closed account discriminator.
+    pub fn reload_status(&mut self) -> Result<ReloadStatus> {
+        if self.info.lamports() == 0 {
+            return Ok(ReloadStatus::Closed);
+        }
+        let data = self.info.try_borrow_data()?;
+        if data.is_empty() || data.starts_with(&crate::__private::CLOSED_ACCOUNT_DISCRIMINATOR) {
+            return Ok(ReloadStatus::Closed);
+        }
+        let mut current = Vec::new();
+        C::serialize(&self.account, &mut current)?;
+        if data.starts_with(&current) {
+            return Ok(ReloadStatus::Unchanged);
+        }
+        self.account = C::deserialize(&mut &data[..])?;
+        drop(data);
+        self.clear_raw_written();
+        Ok(ReloadStatus::Reloaded)
+    }
+
+    /// Resets the account to `T::default()` and writes it to storage right
+    /// away, for recycling an account rather than closing it. The lamports
+    /// are left untouched, the discriminator is rewritten by the codec and any
+    /// data past the serialized default is zeroed.
+    ///
+    /// Errors if the account is not owned by `program_id`.
+    pub fn reset_to_default(&mut self, program_id: &Pubkey) -> Result<()>
+    where
+        T: Default,
+    {
+        if self.info.owner != program_id {
+            return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+                .with_pubkeys((*self.info.owner, *program_id)));
+        }
+        let account = T::default();
+        let mut serialized = Vec::new();
+        C::serialize(&account, &mut serialized)?;
+        let mut data = self.info.try_borrow_mut_data()?;
+        if serialized.len() > data.len() {
+            return Err(ErrorCode::AccountDidNotSerialize.into());
+        }
+        let (head, tail) = data.split_at_mut(serialized.len());
+        head.copy_from_slice(&serialized);
+        tail.fill(0);
+        self.account = account;
+        Ok(())
+    }
+
+    /// Converts the account with `convert` and stores the result in `new`, an
+    /// account of another type, e.g. to migrate between account schemas. The
+    /// converted value is persisted when `new` exits.
+    ///
+    /// Fails with [`ErrorCode::AccountDidNotSerialize`], leaving `new`
+    /// untouched, if the converted value doesn't fit in `new`'s account data.
+    pub fn migrate_into<U: Clone, D: Codec<U>>(
+        &self,
+        new: &mut OrphanAccount<'a, U, D>,
+        convert: impl Fn(&T) -> U,
+    ) -> Result<()> {
+        let account = convert(&self.account);
+        let len = OrphanAccount::<U, D>::len_of(&account)?;
+        if len > new.info.data_len() {
+            return Err(Error::from(ErrorCode::AccountDidNotSerialize)
+                .with_values((len, new.info.data_len())));
+        }
+        new.account = account;
+        Ok(())
+    }
+
+    /// Returns the length of `T::default()` once serialized by the codec,
+    /// i.e. the minimum space for a newly initialized account.
+    pub fn default_len() -> Result<usize>
+    where
+        T: Default,
+    {
+        let mut serialized = Vec::new();
+        C::serialize(&T::default(), &mut serialized)?;
+        Ok(serialized.len())
+    }
+
+    /// Returns the length of the account once serialized. Uses the codec's
+    /// [`serialized_len`](Codec::serialized_len) when available, otherwise
+    /// serializes into a counting writer.
+    pub fn serialized_len(&self) -> Result<usize> {
+        Self::len_of(&self.account)
+    }
+
+    /// Returns how many bytes the account data must grow by to hold
+    /// `new_value` once serialized, or zero if it already fits. Pass
+    /// `data_len() + growth` to `AccountInfo::realloc` before assigning.
+    pub fn growth_needed_for(&self, new_value: &T) -> Result<usize> {
+        let len = Self::len_of(new_value)?;
+        Ok(len.saturating_sub(self.info.data_len()))
+    }
+
+    fn len_of(account: &T) -> Result<usize> {
+        if let Some(len) = C::serialized_len(account) {
+            return Ok(len);
+        }
+        let mut writer = CountingWriter::default();
+        C::serialize(account, &mut writer)?;
+        Ok(writer.len)
+    }
+
+    /// Serializes the account, discriminator included, into a fresh buffer
+    /// holding the same bytes `exit` would write, e.g. to cache the account
+    /// off-chain.
+    pub fn to_borsh_bytes(&self) -> Result<Vec<u8>> {
+        let mut serialized = Vec::new();
+        C::serialize(&self.account, &mut serialized)?;
+        Ok(serialized)
+    }
+
+    /// Returns true if `self` and `other` serialize to the same bytes.
+    ///
+    /// Unlike comparing the deserialized values, this also tells apart values
+    /// that compare equal but have different encodings.
+    pub fn serialized_eq(&self, other: &OrphanAccount<'_, T, C>) -> Result<bool> {
+        Ok(self.to_borsh_bytes()? == other.to_borsh_bytes()?)
+    }
+
+    /// Deserializes a single field of type `F` from the account data at
+    /// `offset`, discriminator included, without deserializing the rest.
+    ///
+    /// Reads the bytes currently stored in the account. Fails with
+    /// [`ErrorCode::AccountDidNotDeserialize`] if `offset` is past the end of
+    /// the data or the bytes there don't deserialize into an `F`.
+    pub fn read_field<F: AnchorDeserialize>(&self, offset: usize) -> Result<F> {
+        let data = self.info.try_borrow_data()?;
+        if offset > data.len() {
+            return Err(
+                Error::from(ErrorCode::AccountDidNotDeserialize).with_values((offset, data.len()))
+            );
+        }
+        F::deserialize(&mut &data[offset..]).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    /// Reads a big endian `u16` from the account data at `offset`, see
+    /// [`read_field`](Self::read_field).
+    pub fn read_u16_be(&self, offset: usize) -> Result<u16> {
+        self.read_bytes(offset).map(u16::from_be_bytes)
+    }
+
+    /// Reads a little endian `u16` from the account data at `offset`, see
+    /// [`read_field`](Self::read_field).
+    pub fn read_u16_le(&self, offset: usize) -> Result<u16> {
+        self.read_bytes(offset).map(u16::from_le_bytes)
+    }
+
+    /// Reads a big endian `u32` from the account data at `offset`, see
+    /// [`read_field`](Self::read_field).
+    pub fn read_u32_be(&self, offset: usize) -> Result<u32> {
+        self.read_bytes(offset).map(u32::from_be_bytes)
+    }
+
+    /// Reads a little endian `u32` from the account data at `offset`, see
+    /// [`read_field`](Self::read_field).
+    pub fn read_u32_le(&self, offset: usize) -> Result<u32> {
+        self.read_bytes(offset).map(u32::from_le_bytes)
+    }
+
+    /// Reads a big endian `u64` from the account data at `offset`, see
+    /// [`read_field`](Self::read_field).
+    pub fn read_u64_be(&self, offset: usize) -> Result<u64> {
+        self.read_bytes(offset).map(u64::from_be_bytes)
+    }
+
+    /// Reads a little endian `u64` from the account data at `offset`, see
+    /// [`read_field`](Self::read_field).
+    pub fn read_u64_le(&self, offset: usize) -> Result<u64> {
+        self.read_bytes(offset).map(u64::from_le_bytes)
+    }
+
+    fn read_bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        let data = self.info.try_borrow_data()?;
+        let bytes = offset
+            .checked_add(N)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| {
+                Error::from(ErrorCode::AccountDidNotDeserialize).with_values((offset, data.len()))
+            })?;
+        let mut array = [0u8; N];
+        array.copy_from_slice(bytes);
+        Ok(array)
+    }
+
+    /// Borrows the account data following the discriminator as a `T`,
+    /// without copying it.
+    ///
+    /// The view reflects the bytes currently stored in the account, not
+    /// changes made to the deserialized value that weren't written back yet.
+    /// Fails with [`ErrorCode::AccountDidNotDeserialize`] if the data is too
+    /// short or not aligned for `T`.
+    pub fn view(&self) -> Result<Ref<'_, T>>
+    where
+        T: bytemuck::Pod,
+    {
+        let data = self.info.try_borrow_data()?;
+        let len = std::mem::size_of::<T>();
+        if data.len() < 8 + len {
+            return Err(
+                Error::from(ErrorCode::AccountDidNotDeserialize).with_values((data.len(), 8 + len))
+            );
+        }
+        bytemuck::try_from_bytes::<T>(&data[8..8 + len])
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+        Ok(Ref::map(data, |data| {
+            bytemuck::from_bytes(&data[8..8 + len])
+        }))
+    }
+
+    /// Runs `f` on the mutably borrowed account data, e.g. to patch a few
+    /// bytes in place without a full re-serialization.
+    ///
+    /// Since the bytes are already written, `exit` no longer serializes the
+    /// in-memory value over them, but still applies what follows the write
+    /// back, e.g. the trailer of
+    /// [`try_from_checksummed`](Self::try_from_checksummed), to the value they
+    /// hold. The in-memory value is stale from here on, so call
+    /// [`reload`](Self::reload) before reading it again, which also restores
+    /// the normal write back on exit.
+    pub fn with_raw_data_mut<R>(&mut self, f: impl FnOnce(&mut [u8]) -> R) -> Result<R> {
+        let result = f(&mut self.info.try_borrow_mut_data()?);
+        self.policy_mut().raw_written = true;
+        Ok(result)
+    }
+
+    /// Sets the borsh serialization of the account value, without a
+    /// discriminator, as the return data of the instruction, e.g. to return
+    /// the current state of a shared account to the caller.
+    ///
+    /// Fails with [`ErrorCode::AccountReturnDataTooLarge`] if the value is
+    /// longer than [`MAX_RETURN_DATA`](solana_program::program::MAX_RETURN_DATA).
+    pub fn return_value(&self) -> Result<()>
+    where
+        T: AnchorSerialize,
+    {
+        let data = self.account.try_to_vec()?;
+        if data.len() > solana_program::program::MAX_RETURN_DATA {
+            return Err(Error::from(ErrorCode::AccountReturnDataTooLarge)
+                .with_values((data.len(), solana_program::program::MAX_RETURN_DATA)));
+        }
+        solana_program::program::set_return_data(&data);
+        Ok(())
+    }
+
+    /// Emits `event` like `emit!`, wrapped in an [`OrphanEvent`] carrying the
+    /// key of the account, e.g. for accounts that represent an entity whose
+    /// events are indexed per account.
+    pub fn emit_for(&self, event: impl crate::Event) {
+        let event = OrphanEvent {
+            key: *self.info.key,
+            event: event.data(),
+        };
+        solana_program::log::sol_log_data(&[&crate::Event::data(&event)]);
+    }
+
+    /// Streams the serialized account, as it would be written on exit, into
+    /// `hasher` without buffering it.
+    pub fn hash_into(&self, hasher: &mut impl Hasher) -> Result<()> {
+        C::serialize(&self.account, &mut HashWriter { hasher })
+    }
+
+    /// Returns the label and category attached to `T` by `#[account]`.
+    pub fn metadata() -> AccountMetadata
+    where
+        T: Labeled,
+    {
+        T::metadata()
+    }
+
+    /// Returns the rent exempt minimum balance of an account holding a `T`,
+    /// discriminator included, e.g. to budget lamports before creating one.
+    pub fn min_rent(rent: &Rent) -> u64
+    where
+        T: ConstSerializedLen,
+    {
+        rent.minimum_balance(T::SERIALIZED_LEN)
+    }
+
+    /// Best-effort check that the account was created in the current
+    /// transaction, failing with [`ErrorCode::AccountNotFreshlyCreated`]
+    /// unless it holds exactly the rent exempt minimum for its size and its
+    /// data past the 8 byte discriminator is all zeros.
+    ///
+    /// This only detects accounts that were used since creation. An older
+    /// account that was never funded beyond the minimum nor written to passes,
+    /// and so does an account that was created, emptied, and topped up again,
+    /// so it must not be the only protection against replaying accounts.
+    pub fn assert_freshly_created(&self, rent: &Rent) -> Result<()> {
+        let required = rent.minimum_balance(self.info.data_len());
+        if self.info.lamports() != required {
+            return Err(Error::from(ErrorCode::AccountNotFreshlyCreated)
+                .with_values((self.info.lamports(), required)));
+        }
+        let data = self.info.try_borrow_data()?;
+        if data.iter().skip(8).any(|byte| *byte != 0) {
+            return Err(ErrorCode::AccountNotFreshlyCreated.into());
+        }
+        Ok(())
+    }
+
+    /// Tops up the account to the rent exempt minimum for its current size,
+    /// transferring the shortfall from `payer` with a system program CPI.
+    /// Does nothing if the account is already rent exempt.
+    pub fn ensure_rent_exempt(&self, payer: &AccountInfo<'a>, rent: &Rent) -> Result<()> {
+        let required = rent.minimum_balance(self.info.data_len());
+        let lamports = self.info.lamports();
+        if lamports >= required {
+            return Ok(());
+        }
+        let ix = system_instruction::transfer(payer.key, self.info.key, required - lamports);
+        solana_program::program::invoke(&ix, &[payer.clone(), self.info.clone()])
+            .map_err(Into::into)
+    }
+
+    /// Invokes `instruction`, signing as this account with the given `seeds`
+    /// followed by the bump stored in the account.
+    pub fn invoke_signed_as(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo<'a>],
+        seeds: &[&[u8]],
+    ) -> Result<()>
+    where
+        T: Bump,
+    {
+        let bump = [self.account.seed()];
+        let mut signer_seeds = seeds.to_vec();
+        signer_seeds.push(&bump);
+        solana_program::program::invoke_signed(instruction, account_infos, &[&signer_seeds])
+            .map_err(Into::into)
+    }
+
+    /// Returns true if the account data is longer than when this
+    /// `OrphanAccount` was constructed, e.g. after a realloc by a CPI callee,
+    /// in which case size assumptions made since should be checked again.
+    pub fn grew_since_construction(&self) -> bool {
+        self.info.data_len() > self.initial_len
+    }
+
+    /// Returns the net change of the account's lamports since this
+    /// `OrphanAccount` was constructed, negative if it lost lamports, e.g. to
+    /// check that the fees a treasury collected match what payers paid.
+    pub fn lamport_delta(&self) -> i128 {
+        self.current_lamports() as i128 - self.snapshot_lamports() as i128
+    }
+
+    /// Returns the balance recorded when this `OrphanAccount` was
+    /// constructed.
+    pub fn lamport_snapshot(&self) -> LamportSnapshot {
+        LamportSnapshot {
+            key: *self.info.key,
+            lamports: self.initial_lamports,
+        }
+    }
+
+    /// Returns the lamports recorded when this `OrphanAccount` was
+    /// constructed, see [`current_lamports`](Self::current_lamports).
+    pub fn snapshot_lamports(&self) -> u64 {
+        self.initial_lamports
+    }
+
+    /// Returns the lamports the account holds now, e.g. after a transfer
+    /// made through another `AccountInfo` for the same account.
+    pub fn current_lamports(&self) -> u64 {
+        self.info.lamports()
+    }
+
+    /// Returns the number of meaningful bytes of the account, as reported by
+    /// [`LogicalLen`], which may be less than the allocated
+    /// [`data_len`](AccountInfo::data_len), e.g. for a length prefixed body.
+    ///
+    /// Fails with [`ErrorCode::AccountDidNotSerialize`] if the logical length
+    /// exceeds the allocation.
+    pub fn logical_len(&self) -> Result<usize>
+    where
+        T: LogicalLen,
+    {
+        let len = self.account.logical_len();
+        if len > self.info.data_len() {
+            return Err(Error::from(ErrorCode::AccountDidNotSerialize)
+                .with_values((len, self.info.data_len())));
+        }
+        Ok(len)
+    }
+
+    /// Returns true if the in-memory account differs from the account data,
+    /// i.e. if `exit` would change the stored bytes.
+    pub fn is_dirty(&self) -> Result<bool> {
+        let mut serialized = Vec::new();
+        C::serialize(&self.account, &mut serialized)?;
+        let data = self.info.try_borrow_data()?;
+        Ok(data.get(..serialized.len()) != Some(&serialized[..]))
+    }
+
+    /// Writes the account back like `exit`, but only if `predicate` holds
+    /// for the in-memory value. Otherwise the account data is left untouched.
+    ///
+    /// An account exited this way should not also be exited by its
+    /// `Accounts` struct, e.g. by reading it from the remaining accounts, as
+    /// that exit is unconditional.
+    pub fn exit_if(&self, program_id: &Pubkey, predicate: impl Fn(&T) -> bool) -> Result<()> {
+        if !predicate(&self.account) {
+            return Ok(());
+        }
+        AccountsExit::exit(self, program_id)
+    }
+
+    /// Writes back this account and `other` like `exit`, e.g. two vaults
+    /// whose balances must sum to a constant, but only if `invariant` holds
+    /// for their in-memory values. Fails with
+    /// [`ErrorCode::AccountInvariantViolated`] otherwise, leaving both
+    /// accounts untouched.
+    ///
+    /// Both accounts are prepared with [`prepare_exit`](Self::prepare_exit)
+    /// before either is written. As with [`exit_if`](Self::exit_if), neither
+    /// should also be exited by its `Accounts` struct. Passing the same
+    /// account twice fails with
+    /// [`ErrorCode::ConstraintDuplicateMutableAccount`], as the second write
+    /// would silently replace the first.
+    pub fn exit_with_invariant<U: Clone, D: Codec<U>>(
+        &self,
+        other: &OrphanAccount<'_, U, D>,
+        program_id: &Pubkey,
+        invariant: impl Fn(&T, &U) -> bool,
+    ) -> Result<()> {
+        if self.info.key == other.info.key {
+            return Err(Error::from(ErrorCode::ConstraintDuplicateMutableAccount)
+                .with_account_name(self.info.key));
+        }
+        if !invariant(&self.account, &other.account) {
+            return Err(Error::from(ErrorCode::AccountInvariantViolated)
+                .with_pubkeys((*self.info.key, *other.info.key)));
+        }
+        let prepared = self.prepare_exit(program_id)?;
+        let other_prepared = other
+            .prepare_exit(program_id)
+            .map_err(|e| e.with_account_name(other.info.key))?;
+        self.apply_exit(prepared)?;
+        other.apply_exit(other_prepared)
+    }
+
+    /// Shrinks the account to at most `new_logical_len` meaningful bytes, see
+    /// [`Truncate`], without reallocating it.
+    ///
+    /// From then on, `exit` also zeroes the data following the logical
+    /// length, so the freed tail doesn't keep stale content. Truncating to
+    /// more than the current logical length fails with
+    /// [`ErrorCode::AccountDidNotSerialize`].
+    pub fn truncate(&mut self, new_logical_len: usize) -> Result<()>
+    where
+        T: Truncate,
+    {
+        let len = self.account.logical_len();
+        if new_logical_len > len {
+            return Err(
+                Error::from(ErrorCode::AccountDidNotSerialize).with_values((new_logical_len, len))
+            );
+        }
+        self.account.truncate_to(new_logical_len);
+        self.policy_mut().zero_tail = Some(T::logical_len);
+        Ok(())
+    }
+
+    /// Checks every account referred to by the account, as returned by
+    /// [`HasReferences::references`], is among `provided`.
+    ///
+    /// The first missing reference fails with
+    /// [`ErrorCode::AccountNotEnoughKeys`], named after its key.
+    pub fn validate_refs(&self, provided: &[&AccountInfo]) -> Result<()>
+    where
+        T: HasReferences,
+    {
+        for reference in self.account.references() {
+            if !provided.iter().any(|info| *info.key == reference) {
+                return Err(
+                    Error::from(ErrorCode::AccountNotEnoughKeys).with_account_name(reference)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the account data is at least `min` bytes long, failing with
+    /// [`ErrorCode::AccountDidNotDeserialize`] otherwise, e.g. to reject a
+    /// foreign account with a valid discriminator but a truncated body.
+    pub fn assert_min_len(&self, min: usize) -> Result<()> {
+        if self.info.data_len() < min {
+            return Err(Error::from(ErrorCode::AccountDidNotDeserialize)
+                .with_values((self.info.data_len(), min)));
+        }
+        Ok(())
+    }
+
+    /// Checks the account data is exactly one of the `valid` lengths, failing
+    /// with [`ErrorCode::AccountDidNotDeserialize`] otherwise, e.g. for
+    /// foreign accounts that come in a few fixed size tiers.
+    pub fn assert_size_in(&self, valid: &[usize]) -> Result<()> {
+        if !valid.contains(&self.info.data_len()) {
+            return Err(Error::from(ErrorCode::AccountDidNotDeserialize)
+                .with_values((self.info.data_len(), format!("{:?}", valid))));
+        }
+        Ok(())
+    }
+
+    /// Checks the account's key is the program derived address of `seeds`
+    /// and `program_id`, returning the bump on success and failing with
+    /// [`ErrorCode::ConstraintSeeds`] otherwise.
+    pub fn assert_pda(&self, seeds: &[&[u8]], program_id: &Pubkey) -> Result<u8> {
+        let (address, bump) = Pubkey::find_program_address(seeds, program_id);
+        if *self.info.key != address {
+            return Err(
+                Error::from(ErrorCode::ConstraintSeeds).with_pubkeys((*self.info.key, address))
+            );
+        }
+        Ok(bump)
+    }
+
+    /// Checks the pubkey stored in the account, as returned by `field`,
+    /// matches the account's actual key, failing with
+    /// [`ErrorCode::ConstraintAddress`] otherwise.
+    pub fn assert_self_key(&self, field: impl Fn(&T) -> &Pubkey) -> Result<()> {
+        let stored = field(&self.account);
+        if stored != self.info.key {
+            return Err(
+                Error::from(ErrorCode::ConstraintAddress).with_pubkeys((*stored, *self.info.key))
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the account's key is none of `keys`, failing with
+    /// [`ErrorCode::ConstraintAddress`] otherwise, e.g. to keep a privileged
+    /// account from being passed where a user account is expected.
+    pub fn assert_not_in(&self, keys: &[Pubkey]) -> Result<()> {
+        if let Some(reserved) = keys.iter().find(|k| *k == self.info.key) {
+            return Err(
+                Error::from(ErrorCode::ConstraintAddress).with_pubkeys((*self.info.key, *reserved))
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the account's key may be in `filter`, failing with
+    /// [`ErrorCode::ConstraintAddress`] if it definitely isn't. See
+    /// [`BloomFilter`] for why a match alone doesn't prove membership.
+    pub fn assert_in_filter(&self, filter: &BloomFilter) -> Result<()> {
+        if !filter.contains(self.info.key) {
+            return Err(Error::from(ErrorCode::ConstraintAddress).with_account_name(self.info.key));
+        }
+        Ok(())
+    }
+
+    /// Checks the program owning the account is upgradeable by
+    /// `expected_authority`, e.g. before trusting a third party program's
+    /// accounts.
+    ///
+    /// `program_data` must be the program data account of the owner, failing
+    /// with [`ErrorCode::AccountNotProgramData`] otherwise. Any other, or no,
+    /// upgrade authority fails with
+    /// [`ErrorCode::AccountUpgradeAuthorityMismatch`].
+    pub fn assert_owner_upgrade_authority(
+        &self,
+        program_data: &AccountInfo,
+        expected_authority: &Pubkey,
+    ) -> Result<()> {
+        let (address, _) =
+            Pubkey::find_program_address(&[self.info.owner.as_ref()], &bpf_loader_upgradeable::ID);
+        if program_data.key != &address || program_data.owner != &bpf_loader_upgradeable::ID {
+            return Err(Error::from(ErrorCode::AccountNotProgramData)
+                .with_pubkeys((*program_data.key, address)));
+        }
+        let program_data =
+            crate::ProgramData::try_deserialize(&mut &program_data.try_borrow_data()?[..])?;
+        match program_data.upgrade_authority_address {
+            Some(authority) if authority == *expected_authority => Ok(()),
+            authority => Err(Error::from(ErrorCode::AccountUpgradeAuthorityMismatch)
+                .with_pubkeys((authority.unwrap_or_default(), *expected_authority))),
+        }
+    }
+
+    /// Checks the account has the same owner as `other`, failing with
+    /// [`ErrorCode::ConstraintOwner`] otherwise.
+    pub fn assert_same_owner<U: Clone, D: Codec<U>>(
+        &self,
+        other: &OrphanAccount<'_, U, D>,
+    ) -> Result<()> {
+        if !crate::__private::keys_eq(self.info.owner, other.info.owner) {
+            return Err(Error::from(ErrorCode::ConstraintOwner)
+                .with_pubkeys((*self.info.owner, *other.info.owner)));
+        }
+        Ok(())
+    }
+
+    /// Checks all the bits of `mask` are set in the flags of the account,
+    /// failing with [`ErrorCode::ConstraintFlags`] otherwise.
+    pub fn require_flags(&self, mask: u64) -> Result<()>
+    where
+        T: HasFlags,
+    {
+        let flags = self.account.flags();
+        if flags & mask != mask {
+            return Err(Error::from(ErrorCode::ConstraintFlags)
+                .with_values((format!("{:#x}", flags), format!("{:#x}", mask))));
+        }
+        Ok(())
+    }
+
+    /// Returns the fields of the account that differ from `previous`, e.g. a
+    /// [`snapshot`](Self::snapshot) taken before a mutation.
+    pub fn diff(&self, previous: &T) -> Vec<FieldChange>
+    where
+        T: Diffable,
+    {
+        self.account.diff_fields(previous)
+    }
+
+    /// Logs the account key followed by one `name: value` line per field,
+    /// truncating values longer than [`DEBUG_DUMP_MAX_VALUE_LEN`] to keep
+    /// logs under the byte limit.
+    #[cfg(feature = "anchor-debug")]
+    pub fn debug_dump(&self)
+    where
+        T: DebugFields,
+    {
+        solana_program::msg!("{}:", self.info.key);
+        for (name, mut value) in self.account.debug_fields() {
+            if value.len() > DEBUG_DUMP_MAX_VALUE_LEN {
+                let mut end = DEBUG_DUMP_MAX_VALUE_LEN;
+                while !value.is_char_boundary(end) {
+                    end -= 1;
+                }
+                value.truncate(end);
+                value.push_str("...");
+            }
+            solana_program::msg!("  {}: {}", name, value);
+        }
+    }
+
+    /// Logs a warning if the account is `fee_payer`, whose lamports the
+    /// runtime debits for fees outside of the program's accounting.
+    ///
+    /// Programs can't see the transaction fee payer directly, so pass the key
+    /// of the account the instruction expects to pay, e.g. its `payer` signer.
+    #[cfg(feature = "anchor-debug")]
+    pub fn debug_check_fee_payer(&self, fee_payer: &Pubkey) {
+        if self.info.key == fee_payer {
+            solana_program::msg!(
+                "Warning: OrphanAccount {} is also the fee payer",
+                self.info.key
+            );
+        }
+    }
+
+    /// Checks the authority of the account is `expected`, failing with
+    /// [`ErrorCode::ConstraintHasOne`] otherwise.
+    pub fn require_authority(&self, expected: &Pubkey) -> Result<()>
+    where
+        T: HasAuthority,
+    {
+        let authority = self.account.authority();
+        if !crate::__private::keys_eq(authority, expected) {
+            return Err(
+                Error::from(ErrorCode::ConstraintHasOne).with_pubkeys((*authority, *expected))
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the authority of the account is among `accounts` and signed
+    /// the instruction. Fails with [`ErrorCode::AccountNotEnoughKeys`] if the
+    /// authority wasn't passed, and [`ErrorCode::ConstraintSigner`] if it
+    /// didn't sign.
+    pub fn require_authority_signer(&self, accounts: &[AccountInfo]) -> Result<()>
+    where
+        T: HasAuthority,
+    {
+        let authority = self.account.authority();
+        match accounts
+            .iter()
+            .find(|info| crate::__private::keys_eq(info.key, authority))
+        {
+            None => Err(Error::from(ErrorCode::AccountNotEnoughKeys).with_account_name(authority)),
+            Some(info) if !info.is_signer => {
+                Err(Error::from(ErrorCode::ConstraintSigner).with_account_name(authority))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Closes the account like [`AccountsClose::close`], but only if
+    /// `is_empty` confirms the account holds nothing that would be lost,
+    /// failing with [`ErrorCode::AccountNotEmpty`] otherwise.
+    pub fn close_if_empty(
+        &self,
+        sol_destination: AccountInfo<'a>,
+        is_empty: impl Fn(&T) -> bool,
+    ) -> Result<()> {
+        if !is_empty(&self.account) {
+            return Err(ErrorCode::AccountNotEmpty.into());
+        }
+        self.close(sol_destination)
+    }
+
+    /// Returns the underlying [`AccountInfo`], without the type annotations
+    /// `as_ref` can need.
+    pub fn info(&self) -> &AccountInfo<'a> {
+        &self.info
+    }
+
+    pub fn into_inner(self) -> T {
+        self.account
+    }
+
+    /// Sets the inner account.
+    pub fn set_inner(&mut self, inner: T) {
+        self.account = inner;
+    }
+
+    /// Returns a copy of the in-memory account, to be passed to
+    /// [`restore`](Self::restore) to roll back later changes.
+    pub fn snapshot(&self) -> T {
+        self.account.clone()
+    }
+
+    /// Replaces the in-memory account with a value from
+    /// [`snapshot`](Self::snapshot).
+    pub fn restore(&mut self, snapshot: T) {
+        self.account = snapshot;
+    }
+
+    /// Returns a [`WeakOrphan`] holding only the key and owner of the account,
+    /// to be upgraded again from the instruction's accounts when needed.
+    pub fn downgrade(&self) -> WeakOrphan<T, C> {
+        WeakOrphan {
+            key: *self.info.key,
+            owner: *self.info.owner,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Consumes the account, returning a read-only [`FrozenOrphan`] that is
+    /// never written back on exit.
+    pub fn freeze(self) -> FrozenOrphan<'a, T, C> {
+        FrozenOrphan { inner: self }
+    }
+
+    /// Consumes the account once it is logically spent, e.g. a redeemed
+    /// ticket, returning a [`ConsumedOrphan`] that can't be read again.
+    /// Changes made before consuming it are still written back on exit.
+    pub fn consume(self) -> ConsumedOrphan<'a, T, C> {
+        ConsumedOrphan { inner: self }
+    }
+
+    /// Feeds arbitrary `data` through the checks and deserialization of
+    /// [`try_from`](Self::try_from), as an entry point for fuzzing foreign
+    /// account data. Malformed input must produce an error, any panic is a
+    /// bug.
+    ///
+    /// The data is read as an account with lamports, owned by a program other
+    /// than the system program.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn fuzz_deserialize(data: &[u8]) -> Result<T> {
+        let key = Pubkey::default();
+        let owner = Pubkey::new_from_array([1; 32]);
+        let mut lamports = 1;
+        let mut data = data.to_vec();
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        OrphanAccount::<T, C>::try_from(&info).map(|account| account.account)
+    }
+
+    /// Returns the account with the `is_writable` flag of its
+    /// [`AccountInfo`] set to `writable`, to exercise both the writable and
+    /// readonly paths in tests. Other clones of the info are unaffected.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_writable(mut self, writable: bool) -> Self {
+        self.info.is_writable = writable;
+        self
+    }
+}
+
+impl<'a, T: Clone + Discriminator, C: Codec<T>> OrphanAccount<'a, T, C> {
+    /// Deserializes just the header `H` at the start of the account data,
+    /// after checking the discriminator of `T`. The rest of the data is not
+    /// read, which makes this a cheap way to filter accounts with a large body
+    /// before deserializing them in full.
+    pub fn try_header<H: AnchorDeserialize>(info: &AccountInfo<'a>) -> Result<H> {
+        check_initialized(info)?;
+        let data = info.try_borrow_data()?;
+        check_discriminator::<T>(&data)?;
+        let mut header: &[u8] = &data[8..];
+        H::deserialize(&mut header).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    /// Reloads the account like [`reload`](Self::reload), after checking its
+    /// discriminator is still that of `T`, e.g. between instructions of a
+    /// flow during which a foreign account could be closed and re-created as
+    /// another type under the same key.
+    ///
+    /// Fails with [`ErrorCode::AccountTypeChanged`] if it isn't, whatever the
+    /// codec checks, leaving the in-memory value untouched.
+    pub fn reload_checked(&mut self) -> Result<()> {
+        let data = self.info.try_borrow_data()?;
+        if data.len() < 8 {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+        if data[..8] != T::discriminator() {
+            return Err(Error::from(ErrorCode::AccountTypeChanged).with_account_name(self.info.key));
+        }
+        drop(data);
+        self.reload()
+    }
+
+    /// Returns the account key followed by the discriminator of `T`, which
+    /// identifies the account as interpreted as `T`, e.g. to key caches that
+    /// may hold the same account deserialized as different types.
+    pub fn stable_id(&self) -> [u8; 40] {
+        let mut id = [0u8; 40];
+        id[..32].copy_from_slice(self.info.key.as_ref());
+        id[32..].copy_from_slice(&T::discriminator());
+        id
+    }
+}
+
+/// Fails if `info` doesn't hold an account: with
+/// [`ErrorCode::AccountNotInitialized`] if it was never created, and
+/// [`ErrorCode::AccountClosed`] if it was drained of its lamports, e.g. by a
+/// close earlier in the transaction.
+pub(crate) fn check_initialized(info: &AccountInfo) -> Result<()> {
+    if info.lamports() != 0 {
+        return Ok(());
+    }
+    if info.owner == &system_program::ID {
+        if info.data_is_empty() {
+            return Err(ErrorCode::AccountNotInitialized.into());
+        }
+        return Err(ErrorCode::AccountClosed.into());
+    }
+    let data = info.try_borrow_data()?;
+    if data.starts_with(&crate::__private::CLOSED_ACCOUNT_DISCRIMINATOR) {
+        return Err(ErrorCode::AccountClosed.into());
+    }
+    Ok(())
+}
+
+pub(crate) fn check_discriminator<T: Discriminator>(data: &[u8]) -> Result<()> {
+    if data.len() < 8 {
+        return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+    }
+    if data[..8] != T::discriminator() {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    Ok(())
+}
+
+type BuilderCheck<'info> = Box<dyn FnOnce(&AccountInfo<'info>) -> Result<()>>;
+
+/// Builder returned by [`OrphanAccount::checked`].
+///
+/// Checks run on build, in the order they are added, and stop at the first
+/// failure, whose error is returned by [`build`](Self::build).
+/// [`build_collecting`](Self::build_collecting) runs them all instead. The
+/// checks of [`OrphanAccount::try_from`] always run last.
+#[must_use]
+pub struct OrphanAccountBuilder<'a, 'info, T: Clone, C: Codec<T> = AnchorCodec> {
+    info: &'a AccountInfo<'info>,
+    checks: Vec<BuilderCheck<'info>>,
+    phantom: PhantomData<(T, C)>,
+}
+
+impl<'a, 'info, T: Clone, C: Codec<T>> OrphanAccountBuilder<'a, 'info, T, C> {
+    fn check(mut self, f: impl FnOnce(&AccountInfo<'info>) -> Result<()> + 'static) -> Self {
+        self.checks.push(Box::new(f));
+        self
+    }
+
+    /// Checks the account is owned by `owner`.
+    pub fn owner(self, owner: &Pubkey) -> Self {
+        let owner = *owner;
+        self.check(move |info| {
+            if info.owner != &owner {
+                return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+                    .with_pubkeys((*info.owner, owner)));
+            }
+            Ok(())
+        })
+    }
+
+    /// Checks the account is rent exempt.
+    pub fn rent_exempt(self, rent: &Rent) -> Self {
+        let rent = *rent;
+        self.check(move |info| {
+            if !rent.is_exempt(info.lamports(), info.try_data_len()?) {
+                return Err(ErrorCode::ConstraintRentExempt.into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Checks the account holds at least `lamports`.
+    pub fn min_lamports(self, lamports: u64) -> Self {
+        self.check(move |info| {
+            if info.lamports() < lamports {
+                return Err(Error::from(ErrorCode::AccountInsufficientLamports)
+                    .with_values((info.lamports(), lamports)));
+            }
+            Ok(())
+        })
+    }
+
+    /// Checks the account data starts with the discriminator of `T`, for
+    /// codecs that don't check it themselves or to fail before other checks.
+    pub fn discriminator(self) -> Self
+    where
+        T: Discriminator,
+    {
+        self.check(|info| check_discriminator::<T>(&info.try_borrow_data()?))
+    }
+
+    /// Deserializes the account if all checks passed.
+    pub fn build(self) -> Result<OrphanAccount<'info, T, C>> {
+        for check in self.checks {
+            check(self.info)?;
+        }
+        OrphanAccount::try_from(self.info)
+    }
+
+    /// Deserializes the account if all checks passed like
+    /// [`build`](Self::build), but runs every check rather than stopping at
+    /// the first failure, returning the errors of all that failed, e.g. to
+    /// report every problem with an account to a client at once.
+    pub fn build_collecting(self) -> std::result::Result<OrphanAccount<'info, T, C>, Vec<Error>> {
+        let info = self.info;
+        let mut errors: Vec<Error> = self
+            .checks
+            .into_iter()
+            .filter_map(|check| check(info).err())
+            .collect();
+        match OrphanAccount::try_from(info) {
+            Ok(account) if errors.is_empty() => Ok(account),
+            Ok(_) => Err(errors),
+            Err(e) => {
+                errors.push(e);
+                Err(errors)
+            }
+        }
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> Accounts<'info> for OrphanAccount<'info, T, C> {
+    #[inline(never)]
+    fn try_accounts(
+        _program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        _ix_data: &[u8],
+        _bumps: &mut BTreeMap<String, u8>,
+        _reallocs: &mut BTreeSet<Pubkey>,
+    ) -> Result<Self> {
+        if accounts.is_empty() {
+            return Err(ErrorCode::AccountNotEnoughKeys.into());
+        }
+        let account = &accounts[0];
+        *accounts = &accounts[1..];
+        OrphanAccount::try_from(account)
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> AccountsExit<'info> for OrphanAccount<'info, T, C> {
+    fn exit(&self, program_id: &Pubkey) -> Result<()> {
+        if !self.check_write_allowed(program_id)? {
+            return Ok(());
+        }
+        // Only persist if the owner is the current program.
+        if self.info.owner == program_id {
+            // Writable accounts that are never mutated likely shouldn't be
+            // marked `mut`, as exiting them costs a serialization for nothing.
+            #[cfg(feature = "anchor-debug")]
+            if self.info.is_writable && !self.is_dirty()? {
+                solana_program::msg!(
+                    "Warning: OrphanAccount {} is writable but was not mutated",
+                    self.info.key
+                );
+            }
+            let policy = match self.policy.as_deref() {
+                None => return self.write_back(),
+                Some(policy) => policy,
+            };
+            let mutated = policy.emit_mutations && (policy.raw_written || self.is_dirty()?);
+            self.write_with_policy(policy, &mut self.info.try_borrow_mut_data()?)?;
+            if mutated {
+                self.emit_mutated()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The data `exit` would write to an [`OrphanAccount`], computed by
+/// [`OrphanAccount::prepare_exit`] and written by
+/// [`OrphanAccount::apply_exit`].
+#[derive(Debug)]
+pub struct PreparedExit {
+    // The data to write, unless `exit` leaves the account untouched.
+    data: Option<Vec<u8>>,
+    // Whether an `OrphanMutated` event follows the write.
+    mutated: bool,
+}
+
+impl<'info, T: Clone, C: Codec<T>> OrphanAccount<'info, T, C> {
+    /// Computes the data `exit` would write to the account, going through the
+    /// same fallible steps but without writing anything, to be written later
+    /// with [`apply_exit`](Self::apply_exit), e.g. once several accounts are
+    /// all known to succeed, as [`OrphanTransaction`] does.
+    ///
+    /// Unlike `exit`, which leaves accounts owned by other programs untouched,
+    /// an account that changed but isn't owned by `program_id` fails with
+    /// [`ErrorCode::AccountOwnedByWrongProgram`], as its changes would be
+    /// lost. Data that doesn't fit fails with
+    /// [`ErrorCode::AccountDidNotSerialize`].
+    pub fn prepare_exit(&self, program_id: &Pubkey) -> Result<PreparedExit> {
+        let untouched = PreparedExit {
+            data: None,
+            mutated: false,
+        };
+        if !self.check_write_allowed(program_id)? {
+            return Ok(untouched);
+        }
+        if self.info.owner != program_id {
+            if self.is_dirty()? {
+                return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+                    .with_pubkeys((*self.info.owner, *program_id)));
+            }
+            return Ok(untouched);
+        }
+        let policy = match self.policy.as_deref() {
+            // A plain write back of an unchanged account writes the same bytes.
+            None if !self.is_dirty()? => return Ok(untouched),
+            policy => policy,
+        };
+        let mut data = self.info.try_borrow_data()?.to_vec();
+        let mutated = match policy {
+            None => {
+                self.write_value_into(&mut data, &self.account)?;
+                false
+            }
+            Some(policy) => {
+                let mutated = policy.emit_mutations && (policy.raw_written || self.is_dirty()?);
+                self.write_with_policy(policy, &mut data)?;
+                mutated
+            }
+        };
+        Ok(PreparedExit {
+            data: Some(data),
+            mutated,
+        })
+    }
+
+    /// Writes `prepared`, returned by [`prepare_exit`](Self::prepare_exit)
+    /// for this account, to the account data.
+    pub fn apply_exit(&self, prepared: PreparedExit) -> Result<()> {
+        if let Some(data) = prepared.data {
+            let mut dst = self.info.try_borrow_mut_data()?;
+            if dst.len() != data.len() {
+                return Err(Error::from(ErrorCode::AccountDidNotSerialize)
+                    .with_values((data.len(), dst.len())));
+            }
+            dst.copy_from_slice(&data);
+        }
+        if prepared.mutated {
+            self.emit_mutated()?;
+        }
+        Ok(())
+    }
+
+    /// Checks `exit` would succeed, see [`prepare_exit`](Self::prepare_exit),
+    /// without keeping the data it would write.
+    pub fn check_exit(&self, program_id: &Pubkey) -> Result<()> {
+        self.prepare_exit(program_id).map(drop)
+    }
+
+    fn emit_mutated(&self) -> Result<()> {
+        let data = self.info.try_borrow_data()?;
+        let mut discriminator = [0u8; 8];
+        let len = data.len().min(8);
+        discriminator[..len].copy_from_slice(&data[..len]);
+        let event = OrphanMutated {
+            key: *self.info.key,
+            discriminator,
+        };
+        solana_program::log::sol_log_data(&[&crate::Event::data(&event)]);
+        Ok(())
+    }
+
+    // Fails if the account changed but may not be written by `program_id` in
+    // the current instruction. Returns false if it must be left untouched.
+    fn check_write_allowed(&self, program_id: &Pubkey) -> Result<bool> {
+        let policy = match self.policy.as_deref() {
+            None => return Ok(true),
+            Some(policy) => policy,
+        };
+        if let Some(writer) = policy.writer.filter(|writer| writer != program_id) {
+            if self.is_dirty()? {
+                return Err(
+                    Error::from(ErrorCode::AccountWriteSealed).with_pubkeys((*program_id, writer))
+                );
+            }
+            return Ok(false);
+        }
+        if let Some((current, selector)) = &policy.write_selector {
+            if current[..] != selector[..] && self.is_dirty()? {
+                return Err(Error::from(ErrorCode::AccountWrongInstruction)
+                    .with_values((format!("{:?}", current), format!("{:?}", selector))));
+            }
+        }
+        if policy.lazy_owner && self.info.owner != program_id && self.is_dirty()? {
+            return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+                .with_pubkeys((*self.info.owner, *program_id)));
+        }
+        Ok(true)
+    }
+
+    fn write_back(&self) -> Result<()> {
+        self.write_value(&self.account)
+    }
+
+    // Writes the account into `dst`, holding the stored data, applying the
+    // behaviours opted into by `policy`.
+    fn write_with_policy(&self, policy: &ExitPolicy<T>, dst: &mut [u8]) -> Result<()> {
+        let mut written = None;
+        if !policy.raw_written {
+            if policy.version_bump.is_none() && policy.timestamp.is_none() {
+                self.write_value_into(dst, &self.account)?;
+            } else {
+                let mut account = self.account.clone();
+                if let Some(version) = policy.version_bump {
+                    account = Self::advance(dst, account, version, |stored| {
+                        stored
+                            .checked_add(1)
+                            .ok_or_else(|| ErrorCode::AccountDidNotSerialize.into())
+                    })?;
+                }
+                if let Some(timestamp) = policy.timestamp {
+                    account = Self::advance(dst, account, timestamp, |_| {
+                        Ok(Clock::get()?.unix_timestamp)
+                    })?;
+                }
+                self.write_value_into(dst, &account)?;
+                written = Some(account);
+            }
+        } else {
+            // The data was written directly and is newer than `self.account`,
+            // so it is upgraded in place, and the trailer and tail follow the
+            // value it holds instead.
+            if let Some(version) = policy.version_stamp {
+                if let Some(stored) = dst.get_mut(8) {
+                    *stored = version;
+                }
+            }
+            if policy.checksum.is_some() || policy.zero_tail.is_some() {
+                written = Some(C::deserialize(&mut &dst[..])?);
+            }
+        }
+        let account = written.as_ref().unwrap_or(&self.account);
+        if let Some(checksum) = policy.checksum {
+            let len = Self::len_of(account)?;
+            let trailer = checksum(&dst[..len.min(dst.len())]);
+            let end = len + trailer.len();
+            if end > dst.len() {
+                return Err(
+                    Error::from(ErrorCode::AccountDidNotSerialize).with_values((end, dst.len()))
+                );
+            }
+            dst[len..end].copy_from_slice(&trailer);
+        }
+        if let Some(logical_len) = policy.zero_tail {
+            let start = logical_len(account).min(dst.len());
+            dst[start..].fill(0);
+        }
+        Ok(())
+    }
+
+    // Resets `field` of `account` to its value in `data`, the stored data,
+    // then sets it to `next` of that value if `account` still differs from
+    // the stored bytes.
+    fn advance<V: Copy>(
+        data: &[u8],
+        mut account: T,
+        field: fn(&mut T) -> &mut V,
+        next: impl FnOnce(V) -> Result<V>,
+    ) -> Result<T> {
+        let stored = match C::deserialize(&mut &data[..]) {
+            Ok(mut stored) => *field(&mut stored),
+            Err(_) => *field(&mut account),
+        };
+        *field(&mut account) = stored;
+        let mut serialized = Vec::new();
+        C::serialize(&account, &mut serialized)?;
+        if data.get(..serialized.len()) != Some(&serialized[..]) {
+            *field(&mut account) = next(stored)?;
+        }
+        Ok(account)
+    }
+
+    fn write_value(&self, value: &T) -> Result<()> {
+        self.write_value_into(&mut self.info.try_borrow_mut_data()?, value)
+    }
+
+    fn write_value_into(&self, dst: &mut [u8], value: &T) -> Result<()> {
+        if let Some(len) = C::serialized_len(value) {
+            if len > dst.len() {
+                return Err(
+                    Error::from(ErrorCode::AccountDidNotSerialize).with_values((len, dst.len()))
+                );
+            }
+        }
+        // A migrated or legacy account changes layout, so it is always written
+        // in full.
+        let relayout = self
+            .policy
+            .as_ref()
+            .map_or(false, |policy| policy.migrated || policy.legacy);
+        if let Some(range) = C::changed_range(value).filter(|_| !relayout) {
+            if range.start > range.end || range.end > dst.len() {
+                return Err(Error::from(ErrorCode::AccountDidNotSerialize)
+                    .with_values((range.end, dst.len())));
+            }
+            let mut writer = RangeWriter { dst, range, pos: 0 };
+            return C::serialize(value, &mut writer);
+        }
+        let mut writer = BpfWriter::new(dst);
+        C::serialize(value, &mut writer)
+    }
+}
+
+/// This function is for INTERNAL USE ONLY.
+/// Do NOT use this function in a program.
+/// Manual closing of `OrphanAccount<'info, T>` types is NOT supported.
+///
+/// Details: Using `close` with `OrphanAccount<'info, T>` is not safe because
+/// it requires the `mut` constraint but for that type the constraint
+/// overwrites the "closed account" discriminator at the end of the instruction.
+impl<'info, T: Clone, C: Codec<T>> AccountsClose<'info> for OrphanAccount<'info, T, C> {
+    fn close(&self, sol_destination: AccountInfo<'info>) -> Result<()> {
+        crate::common::close(self.to_account_info(), sol_destination)
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> ToAccountMetas for OrphanAccount<'info, T, C> {
+    fn to_account_metas(&self, is_signer: Option<bool>) -> Vec<AccountMeta> {
+        let is_signer = is_signer.unwrap_or(self.info.is_signer);
+        let meta = match self.info.is_writable {
+            false => AccountMeta::new_readonly(*self.info.key, is_signer),
+            true => AccountMeta::new(*self.info.key, is_signer),
+        };
+        vec![meta]
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> ToAccountInfos<'info> for OrphanAccount<'info, T, C> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.info.clone()]
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> AsRef<AccountInfo<'info>> for OrphanAccount<'info, T, C> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        &self.info
+    }
+}
+
+impl<'a, T: Clone, C: Codec<T>> Deref for OrphanAccount<'a, T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.account
+    }
+}
+
+impl<'a, T: Clone, C: Codec<T>> DerefMut for OrphanAccount<'a, T, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        #[cfg(feature = "anchor-debug")]
+        if !self.info.is_writable {
+            solana_program::msg!("The given OrphanAccount is not mutable");
+            panic!();
+        }
+        &mut self.account
+    }
+}
+
+impl<'a, T: Clone + Index<I>, C: Codec<T>, I> Index<I> for OrphanAccount<'a, T, C> {
+    type Output = T::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.account.index(index)
+    }
+}
+
+impl<'a, T: Clone + IndexMut<I>, C: Codec<T>, I> IndexMut<I> for OrphanAccount<'a, T, C> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        // Goes through `deref_mut` for its mutability check.
+        self.deref_mut().index_mut(index)
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> Key for OrphanAccount<'info, T, C> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
+
+/// Type erased view of an [`OrphanAccount`], for handling accounts of
+/// different types together, e.g. with [`reload_all`].
+pub trait ErasedOrphan: Key {
+    /// Reloads the account from storage, see [`OrphanAccount::reload`].
+    fn reload(&mut self) -> Result<()>;
+
+    /// Reloads the account from storage if it changed, see
+    /// [`OrphanAccount::reload_status`].
+    fn reload_status(&mut self) -> Result<ReloadStatus>;
+
+    /// Writes the account back, see [`AccountsExit::exit`].
+    fn persist(&self, program_id: &Pubkey) -> Result<()>;
+
+    /// Computes the data written back by `persist`, see
+    /// [`OrphanAccount::prepare_exit`].
+    fn prepare_persist(&self, program_id: &Pubkey) -> Result<PreparedExit>;
+
+    /// Writes the result of `prepare_persist`, see
+    /// [`OrphanAccount::apply_exit`].
+    fn apply_persist(&self, prepared: PreparedExit) -> Result<()>;
+}
+
+impl<'info, T: Clone, C: Codec<T>> ErasedOrphan for OrphanAccount<'info, T, C> {
+    fn reload(&mut self) -> Result<()> {
+        OrphanAccount::reload(self)
+    }
+
+    fn reload_status(&mut self) -> Result<ReloadStatus> {
+        OrphanAccount::reload_status(self)
+    }
+
+    fn persist(&self, program_id: &Pubkey) -> Result<()> {
+        AccountsExit::exit(self, program_id)
+    }
+
+    fn prepare_persist(&self, program_id: &Pubkey) -> Result<PreparedExit> {
+        OrphanAccount::prepare_exit(self, program_id)
+    }
+
+    fn apply_persist(&self, prepared: PreparedExit) -> Result<()> {
+        OrphanAccount::apply_exit(self, prepared)
+    }
+}
+
+/// Reloads every account in `accounts`, e.g. after a CPI, stopping at the
+/// first failure, whose error is named after the key of the offending
+/// account.
+pub fn reload_all(accounts: &mut [&mut dyn ErasedOrphan]) -> Result<()> {
+    for account in accounts.iter_mut() {
+        let key = account.key();
+        account.reload().map_err(|e| e.with_account_name(key))?;
+    }
+    Ok(())
+}
+
+/// Reloads every account in `accounts` like [`reload_all`], returning the
+/// keys of those whose content changed, e.g. to react to the accounts a CPI
+/// touched. Accounts closed in the meantime count as changed.
+pub fn reload_and_diff(accounts: &mut [&mut dyn ErasedOrphan]) -> Result<Vec<Pubkey>> {
+    let mut changed = Vec::new();
+    for account in accounts.iter_mut() {
+        let key = account.key();
+        match account
+            .reload_status()
+            .map_err(|e| e.with_account_name(key))?
+        {
+            ReloadStatus::Unchanged => {}
+            ReloadStatus::Reloaded | ReloadStatus::Closed => changed.push(key),
+        }
+    }
+    Ok(changed)
+}
+
+/// Writes back every account in `accounts`, stopping at the first failure,
+/// whose error is named after the key of the offending account. Accounts
+/// before it stay written.
+pub fn exit_all(accounts: &[&dyn ErasedOrphan], program_id: &Pubkey) -> Result<()> {
+    for account in accounts {
+        account
+            .persist(program_id)
+            .map_err(|e| e.with_account_name(account.key()))?;
+    }
+    Ok(())
+}
+
+/// Writes back a set of orphan accounts, possibly of different types, all or
+/// nothing: the data of every account is prepared with
+/// [`ErasedOrphan::prepare_persist`] before any is written, so a failure
+/// leaves all the account data as it was.
+///
+/// Accounts are staged once mutated, and written in staging order.
+///
+/// # Example
+/// ```ignore
+/// pool.reserve -= amount;
+/// vault.balance += amount;
+/// let mut transaction = OrphanTransaction::new();
+/// transaction.stage(&pool).stage(&vault);
+/// transaction.commit(ctx.program_id)?;
+/// ```
+#[derive(Default)]
+pub struct OrphanTransaction<'a> {
+    accounts: Vec<&'a dyn ErasedOrphan>,
+}
+
+impl<'a> OrphanTransaction<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `account` to the accounts written by [`commit`](Self::commit).
+    pub fn stage(&mut self, account: &'a dyn ErasedOrphan) -> &mut Self {
+        self.accounts.push(account);
+        self
+    }
+
+    /// Prepares then writes back every staged account. The first account
+    /// that fails to prepare is returned named after its key, before anything
+    /// is written.
+    pub fn commit(self, program_id: &Pubkey) -> Result<()> {
+        let prepared = self
+            .accounts
+            .iter()
+            .map(|account| {
+                account
+                    .prepare_persist(program_id)
+                    .map_err(|e| e.with_account_name(account.key()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (account, prepared) in self.accounts.iter().zip(prepared) {
+            account
+                .apply_persist(prepared)
+                .map_err(|e| e.with_account_name(account.key()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks every account in `accounts`, e.g. the remaining accounts of an
+/// instruction, is owned by `owner` in a single pass. The first account owned
+/// by another program fails with [`ErrorCode::AccountOwnedByWrongProgram`],
+/// named after its index as `accounts[<index>]`.
+pub fn validate_owners(accounts: &[AccountInfo], owner: &Pubkey) -> Result<()> {
+    for (index, info) in accounts.iter().enumerate() {
+        if info.owner != owner {
+            return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+                .with_pubkeys((*info.owner, *owner))
+                .with_account_name(format!("accounts[{}]", index)));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the keys of `accounts`, e.g. orphan accounts read from the
+/// remaining accounts, match `expected` in order, e.g. a stored route of
+/// pools. The first mismatch fails named after its index as
+/// `accounts[<index>]`: with [`ErrorCode::ConstraintAddress`] for a different
+/// key, and with [`ErrorCode::AccountNotEnoughKeys`] or
+/// [`ErrorCode::AccountTooManyKeys`] if the lengths differ.
+pub fn assert_order<K: Key>(accounts: &[K], expected: &[Pubkey]) -> Result<()> {
+    for (index, (account, expected)) in accounts.iter().zip(expected).enumerate() {
+        if account.key() != *expected {
+            return Err(Error::from(ErrorCode::ConstraintAddress)
+                .with_pubkeys((account.key(), *expected))
+                .with_account_name(format!("accounts[{}]", index)));
+        }
+    }
+    if accounts.len() != expected.len() {
+        let code = match accounts.len() < expected.len() {
+            true => ErrorCode::AccountNotEnoughKeys,
+            false => ErrorCode::AccountTooManyKeys,
+        };
+        let index = accounts.len().min(expected.len());
+        return Err(Error::from(code).with_account_name(format!("accounts[{}]", index)));
+    }
+    Ok(())
+}
+
+/// Swaps the in-memory values of `a` and `b`, e.g. to rotate two buffers, so
+/// that the `exit` of each writes the prior value of the other, even if its
+/// data was written directly since.
+///
+/// Fails with [`ErrorCode::AccountDidNotSerialize`], leaving both accounts
+/// untouched, if either value doesn't fit the data of the other account.
+pub fn swap_contents<T: Clone, C: Codec<T>>(
+    a: &mut OrphanAccount<'_, T, C>,
+    b: &mut OrphanAccount<'_, T, C>,
+) -> Result<()> {
+    for (len, target, space) in [
+        (a.serialized_len()?, b.info.key, b.info.data_len()),
+        (b.serialized_len()?, a.info.key, a.info.data_len()),
+    ] {
+        if len > space {
+            return Err(Error::from(ErrorCode::AccountDidNotSerialize)
+                .with_account_name(target)
+                .with_values((len, space)));
+        }
+    }
+    std::mem::swap(&mut a.account, &mut b.account);
+    a.clear_raw_written();
+    b.clear_raw_written();
+    Ok(())
+}
+
+/// Moves lamports between a fixed set of accounts, e.g. a fee pool and the
+/// orphan accounts it pays into, checking after every move that their total
+/// is unchanged.
+///
+/// # Example
+/// ```ignore
+/// let ledger = LamportLedger::new(&[pool.to_account_info(), shared.to_account_info()])?;
+/// ledger.move_lamports(0, 1, fee)?;
+/// ```
+pub struct LamportLedger<'a, 'info> {
+    infos: &'a [AccountInfo<'info>],
+    total: u64,
+}
+
+impl<'a, 'info> LamportLedger<'a, 'info> {
+    /// Records the total lamports of `infos`.
+    pub fn new(infos: &'a [AccountInfo<'info>]) -> Result<LamportLedger<'a, 'info>> {
+        Ok(Self {
+            infos,
+            total: Self::sum(infos)?,
+        })
+    }
+
+    /// Returns the total lamports recorded on construction.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Moves `amount` lamports from the account at index `from` to the one
+    /// at index `to`, then checks the total is conserved.
+    pub fn move_lamports(&self, from: usize, to: usize, amount: u64) -> Result<()> {
+        let source = self.get(from)?;
+        let destination = self.get(to)?;
+        let balance = source.lamports();
+        if balance < amount {
+            return Err(
+                Error::from(ErrorCode::AccountInsufficientLamports).with_values((balance, amount))
+            );
+        }
+        **source.try_borrow_mut_lamports()? -= amount;
+        **destination.try_borrow_mut_lamports()? += amount;
+        self.check()
+    }
+
+    /// Checks the total lamports of the accounts still match the total
+    /// recorded on construction, failing with
+    /// [`ErrorCode::AccountLamportsNotConserved`] otherwise.
+    pub fn check(&self) -> Result<()> {
+        let total = Self::sum(self.infos)?;
+        if total != self.total {
+            return Err(Error::from(ErrorCode::AccountLamportsNotConserved)
+                .with_values((total, self.total)));
+        }
+        Ok(())
+    }
+
+    fn get(&self, index: usize) -> Result<&AccountInfo<'info>> {
+        self.infos.get(index).ok_or_else(|| {
+            Error::from(ErrorCode::AccountIndexOutOfRange).with_values((index, self.infos.len()))
+        })
+    }
+
+    fn sum(infos: &[AccountInfo]) -> Result<u64> {
+        infos.iter().try_fold(0u64, |total, info| {
+            total
+                .checked_add(info.lamports())
+                .ok_or_else(|| ErrorCode::AccountLamportsNotConserved.into())
+        })
+    }
+}
+
+/// A read-only [`OrphanAccount`], returned by [`OrphanAccount::freeze`].
+///
+/// The account can only be dereferenced immutably, and exiting it does
+/// nothing, so it can't be modified for the rest of the instruction.
+///
+/// ```compile_fail
+/// # use anchor_lang::accounts::orphan_account::FrozenOrphan;
+/// # use anchor_lang::prelude::*;
+/// # declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+/// # #[account]
+/// # pub struct Data {
+/// #     pub value: u64,
+/// # }
+/// fn mutate(frozen: &mut FrozenOrphan<Data>) {
+///     frozen.value = 1;
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FrozenOrphan<'info, T: Clone, C: Codec<T> = AnchorCodec> {
+    inner: OrphanAccount<'info, T, C>,
+}
+
+impl<'info, T: Clone, C: Codec<T>> FrozenOrphan<'info, T, C> {
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> AccountsExit<'info> for FrozenOrphan<'info, T, C> {
+    fn exit(&self, _program_id: &Pubkey) -> Result<()> {
+        // Frozen accounts are never persisted.
+        Ok(())
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> ToAccountMetas for FrozenOrphan<'info, T, C> {
+    fn to_account_metas(&self, is_signer: Option<bool>) -> Vec<AccountMeta> {
+        self.inner.to_account_metas(is_signer)
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> ToAccountInfos<'info> for FrozenOrphan<'info, T, C> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        self.inner.to_account_infos()
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> AsRef<AccountInfo<'info>> for FrozenOrphan<'info, T, C> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        self.inner.as_ref()
+    }
+}
+
+impl<'a, T: Clone, C: Codec<T>> Deref for FrozenOrphan<'a, T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> Key for FrozenOrphan<'info, T, C> {
+    fn key(&self) -> Pubkey {
+        self.inner.key()
+    }
+}
+
+/// A spent [`OrphanAccount`], returned by [`OrphanAccount::consume`].
+///
+/// The account can't be dereferenced, so its value can't be used by mistake
+/// after it was spent. Besides the account metas and infos required by
+/// [`AccountsExit`], its only capability is exiting, which writes the account
+/// back like [`OrphanAccount`] does.
+///
+/// ```compile_fail
+/// # use anchor_lang::accounts::orphan_account::ConsumedOrphan;
+/// # use anchor_lang::prelude::*;
+/// # declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+/// # #[account]
+/// # pub struct Ticket {
+/// #     pub redeemed: bool,
+/// # }
+/// fn read(consumed: &ConsumedOrphan<Ticket>) -> bool {
+///     consumed.redeemed
+/// }
+/// ```
+pub struct ConsumedOrphan<'info, T: Clone, C: Codec<T> = AnchorCodec> {
+    inner: OrphanAccount<'info, T, C>,
+}
+
+impl<'info, T: Clone, C: Codec<T>> AccountsExit<'info> for ConsumedOrphan<'info, T, C> {
+    fn exit(&self, program_id: &Pubkey) -> Result<()> {
+        self.inner.exit(program_id)
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> ToAccountMetas for ConsumedOrphan<'info, T, C> {
+    fn to_account_metas(&self, is_signer: Option<bool>) -> Vec<AccountMeta> {
+        self.inner.to_account_metas(is_signer)
+    }
+}
+
+impl<'info, T: Clone, C: Codec<T>> ToAccountInfos<'info> for ConsumedOrphan<'info, T, C> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        self.inner.to_account_infos()
+    }
+}
+
+/// A lightweight handle to an [`OrphanAccount`], returned by
+/// [`OrphanAccount::downgrade`], that holds only the account's key and owner
+/// rather than its data.
+///
+/// [`upgrade`](Self::upgrade) deserializes the account again from the
+/// instruction's accounts, so a handle can be passed down deep call stacks
+/// without cloning the account.
+pub struct WeakOrphan<T, C = AnchorCodec> {
+    key: Pubkey,
+    owner: Pubkey,
+    phantom: PhantomData<fn() -> (T, C)>,
+}
+
+impl<T: Clone, C: Codec<T>> WeakOrphan<T, C> {
+    /// Returns the owner of the account when the handle was created.
+    pub fn owner(&self) -> &Pubkey {
+        &self.owner
+    }
+
+    /// Finds the account in `accounts` and deserializes it again like
+    /// [`OrphanAccount::try_from`].
+    ///
+    /// Fails with [`ErrorCode::AccountNotEnoughKeys`] if the account is
+    /// missing, and with [`ErrorCode::AccountOwnedByWrongProgram`] if its
+    /// owner changed since the handle was created.
+    pub fn upgrade<'info>(
+        &self,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<OrphanAccount<'info, T, C>> {
+        let info = accounts
+            .iter()
+            .find(|info| info.key == &self.key)
+            .ok_or_else(|| {
+                Error::from(ErrorCode::AccountNotEnoughKeys).with_account_name(self.key)
+            })?;
+        if info.owner != &self.owner {
+            return Err(Error::from(ErrorCode::AccountOwnedByWrongProgram)
+                .with_pubkeys((*info.owner, self.owner)));
+        }
+        OrphanAccount::try_from(info)
+    }
+}
+
+impl<T, C> Clone for WeakOrphan<T, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, C> Copy for WeakOrphan<T, C> {}
+
+impl<T, C> fmt::Debug for WeakOrphan<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakOrphan")
+            .field("key", &self.key)
+            .field("owner", &self.owner)
+            .finish()
+    }
+}
+
+impl<T, C> Key for WeakOrphan<T, C> {
+    fn key(&self) -> Pubkey {
+        self.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::clock::Epoch;
+
+    fn account_info<'a>(
+        key: &'a Pubkey,
+        owner: &'a Pubkey,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(
+            key,
+            false,
+            true,
+            lamports,
+            data,
+            owner,
+            false,
+            Epoch::default(),
+        )
+    }
+
+    // Stores a single big-endian `u64` with no discriminator.
+    struct BigEndianCodec;
+
+    impl Codec<u64> for BigEndianCodec {
+        fn deserialize(buf: &mut &[u8]) -> Result<u64> {
+            if buf.len() < 8 {
+                return Err(ErrorCode::AccountDidNotDeserialize.into());
+            }
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&buf[..8]);
+            *buf = &buf[8..];
+            Ok(u64::from_be_bytes(bytes))
+        }
+
+        fn serialize<W: Write>(value: &u64, writer: &mut W) -> Result<()> {
+            writer
+                .write_all(&value.to_be_bytes())
+                .map_err(|_| ErrorCode::AccountDidNotSerialize.into())
+        }
+    }
+
+    #[test]
+    fn custom_codec_round_trip() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        assert_eq!(*account, 42);
+
+        *account = 7;
+        account.exit(&owner).unwrap();
+        assert_eq!(&info.try_borrow_data().unwrap()[..], &7u64.to_be_bytes());
+    }
+
+    #[test]
+    fn custom_codec_not_persisted_for_foreign_owner() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        *account = 7;
+        account.exit(&Pubkey::new_unique()).unwrap();
+        assert_eq!(&info.try_borrow_data().unwrap()[..], &42u64.to_be_bytes());
+    }
+
+    #[test]
+    fn is_dirty() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        assert!(!account.is_dirty().unwrap());
+        *account = 7;
+        assert!(account.is_dirty().unwrap());
+        // Writing the same value back is not a change.
+        *account = 42;
+        assert!(!account.is_dirty().unwrap());
+    }
+
+    #[test]
+    fn hash_into() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let owner = Pubkey::new_unique();
+        let (key_a, key_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports_a, mut lamports_b) = (1, 1);
+        let mut data_a = 1u64.to_be_bytes();
+        let mut data_b = 2u64.to_be_bytes();
+        let info_a = account_info(&key_a, &owner, &mut lamports_a, &mut data_a);
+        let info_b = account_info(&key_b, &owner, &mut lamports_b, &mut data_b);
+
+        let mut a = OrphanAccount::<u64, BigEndianCodec>::try_from(&info_a).unwrap();
+        let b = OrphanAccount::<u64, BigEndianCodec>::try_from(&info_b).unwrap();
+        *a = 10;
+        let mut hasher = DefaultHasher::new();
+        a.hash_into(&mut hasher).unwrap();
+        b.hash_into(&mut hasher).unwrap();
+
+        let mut expected = DefaultHasher::new();
+        expected.write(&10u64.to_be_bytes());
+        expected.write(&2u64.to_be_bytes());
+        assert_eq!(hasher.finish(), expected.finish());
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        *account = 10;
+        let snapshot = account.snapshot();
+        *account = 7;
+        account.restore(snapshot);
+        assert_eq!(*account, 10);
+        account.exit(&owner).unwrap();
+        assert_eq!(&info.try_borrow_data().unwrap()[..], &10u64.to_be_bytes());
+    }
+
+    #[test]
+    fn frozen_not_persisted() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        *account = 7;
+        let frozen = account.freeze();
+        assert_eq!(*frozen, 7);
+        frozen.exit(&owner).unwrap();
+        assert_eq!(&info.try_borrow_data().unwrap()[..], &42u64.to_be_bytes());
+    }
+
+    #[test]
+    fn consumed_persisted() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        *account = 7;
+        let consumed = account.consume();
+        consumed.exit(&owner).unwrap();
+        assert_eq!(&info.try_borrow_data().unwrap()[..], &7u64.to_be_bytes());
+    }
+
+    #[test]
+    fn with_writable() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+        let account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+
+        let readonly = account.clone().with_writable(false);
+        assert_eq!(
+            readonly.to_account_metas(None),
+            vec![AccountMeta::new_readonly(key, false)]
+        );
+        let mut writable = readonly.with_writable(true);
+        assert_eq!(
+            writable.to_account_metas(None),
+            vec![AccountMeta::new(key, false)]
+        );
+        *writable = 7;
+        writable.exit(&owner).unwrap();
+        assert_eq!(&info.try_borrow_data().unwrap()[..], &7u64.to_be_bytes());
+        assert!(info.is_writable);
+    }
+
+    impl UninitSentinel for u64 {
+        fn is_uninitialized(data: &[u8]) -> bool {
+            data.iter().all(|b| *b == 0xFF)
+        }
+    }
+
+    #[test]
+    fn sentinel_rejects_uninitialized() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = [0xFF; 8];
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        // Without the sentinel, the data is a valid u64.
+        let account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        assert_eq!(*account, u64::MAX);
+
+        let err = OrphanAccount::<u64, Sentinel<BigEndianCodec>>::try_from(&info).unwrap_err();
+        assert_eq!(err, Error::from(ErrorCode::AccountNotInitialized));
+        let err =
+            OrphanAccount::<u64, Sentinel<BigEndianCodec>>::try_from_unchecked(&info).unwrap_err();
+        assert_eq!(err, Error::from(ErrorCode::AccountNotInitialized));
+
+        info.try_borrow_mut_data().unwrap()[7] = 0;
+        let account = OrphanAccount::<u64, Sentinel<BigEndianCodec>>::try_from(&info).unwrap();
+        assert_eq!(*account, u64::MAX - 0xFF);
+    }
+
+    #[test]
+    fn custom_codec_rejects_uninitialized() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = [];
+        let info = account_info(&key, &system_program::ID, &mut lamports, &mut data);
+
+        let err = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap_err();
+        assert_eq!(err, Error::from(ErrorCode::AccountNotInitialized));
+    }
+
+    fn error_msg(err: Error) -> String {
+        match err {
+            Error::AnchorError(err) => err.error_msg,
+            Error::ProgramError(err) => panic!("unexpected program error: {}", err),
+        }
+    }
+
+    #[test]
+    fn uninitialized_never_created() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = [];
+        let info = account_info(&key, &system_program::ID, &mut lamports, &mut data);
+
+        let err = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap_err();
+        assert_eq!(
+            error_msg(err),
+            "The program expected this account to be already initialized"
+        );
+    }
+
+    #[test]
+    fn uninitialized_drained() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = crate::__private::CLOSED_ACCOUNT_DISCRIMINATOR;
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let err = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap_err();
+        assert_eq!(err, Error::from(ErrorCode::AccountClosed));
+        assert_eq!(
+            error_msg(err),
+            "The account appears to have been closed or drained of its lamports"
+        );
+
+        // Assigned back to the system program but not yet garbage collected.
+        let mut lamports = 0;
+        let mut data = [0; 8];
+        let info = account_info(&key, &system_program::ID, &mut lamports, &mut data);
+        let err = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap_err();
+        assert_eq!(err, Error::from(ErrorCode::AccountClosed));
+    }
+
+    #[test]
+    fn reload_status_unchanged() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        assert_eq!(account.reload_status().unwrap(), ReloadStatus::Unchanged);
+        assert_eq!(*account, 42);
+    }
+
+    #[test]
+    fn reload_status_reloaded() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        info.try_borrow_mut_data()
+            .unwrap()
+            .copy_from_slice(&7u64.to_be_bytes());
+        assert_eq!(account.reload_status().unwrap(), ReloadStatus::Reloaded);
+        assert_eq!(*account, 7);
+    }
+
+    #[test]
+    fn reload_status_closed() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = 42u64.to_be_bytes();
+        let info = account_info(&key, &owner, &mut lamports, &mut data);
+
+        let mut account = OrphanAccount::<u64, BigEndianCodec>::try_from(&info).unwrap();
+        info.try_borrow_mut_data()
+            .unwrap()
+            .copy_from_slice(&crate::__private::CLOSED_ACCOUNT_DISCRIMINATOR);
+        assert_eq!(account.reload_status().unwrap(), ReloadStatus::Closed);
+        assert_eq!(*account, 42);
+
+        **info.lamports.borrow_mut() = 0;
+        assert_eq!(account.reload_status().unwrap(), ReloadStatus::Closed);
+    }
+}
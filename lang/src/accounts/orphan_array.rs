@@ -0,0 +1,245 @@
+//! Random access to fixed-size records stored in a single orphan account.
+
+use crate::accounts::orphan_account::{check_initialized, ConstSerializedLen};
+use crate::bpf_writer::BpfWriter;
+use crate::error::{Error, ErrorCode};
+use crate::{AnchorDeserialize, AnchorSerialize, Key, Result, ToAccountInfos};
+use solana_program::account_info::AccountInfo;
+use solana_program::pubkey::Pubkey;
+use std::cell::RefMut;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Range};
+
+/// An account whose body, after the 8 byte discriminator, is an array of `N`
+/// fixed-size records of type `T`.
+///
+/// Unlike [`OrphanAccount`](crate::accounts::orphan_account::OrphanAccount),
+/// nothing is deserialized up front. [`get`](Self::get) and
+/// [`get_mut`](Self::get_mut) only read and write the byte range of the
+/// requested record, so accessing one element of a large array stays cheap.
+/// As with `OrphanAccount`, neither the owner nor the discriminator is checked.
+///
+/// # Example
+/// ```ignore
+/// let records = OrphanArray::<Record, 1024>::try_from(&ctx.accounts.records)?;
+/// let mut record = records.get_mut(index)?;
+/// record.count += 1;
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrphanArray<'info, T, const N: usize> {
+    info: AccountInfo<'info>,
+    phantom: PhantomData<T>,
+}
+
+impl<'info, T, const N: usize> OrphanArray<'info, T, N>
+where
+    T: AnchorSerialize + AnchorDeserialize + ConstSerializedLen,
+{
+    const OFFSET: usize = 8;
+
+    /// Wraps the given `info`, checking it is large enough to hold `N`
+    /// records.
+    pub fn try_from(info: &AccountInfo<'info>) -> Result<OrphanArray<'info, T, N>> {
+        check_initialized(info)?;
+        let required = Self::OFFSET + N * T::SERIALIZED_LEN;
+        if info.data_len() < required {
+            return Err(Error::from(ErrorCode::AccountDidNotDeserialize)
+                .with_values((info.data_len(), required)));
+        }
+        Ok(Self {
+            info: info.clone(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the number of records, `N`.
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if the array holds no records.
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Deserializes the record at `index`.
+    pub fn get(&self, index: usize) -> Result<T> {
+        let range = Self::range(index)?;
+        let data = self.info.try_borrow_data()?;
+        T::deserialize(&mut &data[range]).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    /// Deserializes the record at `index`, returning a guard that writes it
+    /// back to the same byte range when dropped.
+    ///
+    /// The account data stays mutably borrowed for the lifetime of the guard.
+    pub fn get_mut(&self, index: usize) -> Result<OrphanArrayElement<'_, T>> {
+        let range = Self::range(index)?;
+        let data = RefMut::map(self.info.try_borrow_mut_data()?, |data| &mut data[range]);
+        let value =
+            T::deserialize(&mut &data[..]).map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+        Ok(OrphanArrayElement { data, value })
+    }
+
+    /// Serializes `value` into the record at `index`.
+    pub fn set(&self, index: usize, value: &T) -> Result<()> {
+        let range = Self::range(index)?;
+        let mut data = self.info.try_borrow_mut_data()?;
+        let mut writer = BpfWriter::new(&mut data[range]);
+        value
+            .serialize(&mut writer)
+            .map_err(|_| ErrorCode::AccountDidNotSerialize.into())
+    }
+
+    fn range(index: usize) -> Result<Range<usize>> {
+        if index >= N {
+            return Err(Error::from(ErrorCode::AccountIndexOutOfRange).with_values((index, N)));
+        }
+        let start = Self::OFFSET + index * T::SERIALIZED_LEN;
+        Ok(start..start + T::SERIALIZED_LEN)
+    }
+}
+
+impl<'info, T, const N: usize> ToAccountInfos<'info> for OrphanArray<'info, T, N> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.info.clone()]
+    }
+}
+
+impl<'info, T, const N: usize> AsRef<AccountInfo<'info>> for OrphanArray<'info, T, N> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        &self.info
+    }
+}
+
+impl<'info, T, const N: usize> Key for OrphanArray<'info, T, N> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
+
+/// A record of an [`OrphanArray`] borrowed mutably, written back to the
+/// account when dropped.
+///
+/// # Panics
+///
+/// Dropping the guard panics if the record no longer serializes into
+/// [`SERIALIZED_LEN`](ConstSerializedLen::SERIALIZED_LEN) bytes.
+pub struct OrphanArrayElement<'a, T: AnchorSerialize> {
+    data: RefMut<'a, [u8]>,
+    value: T,
+}
+
+impl<'a, T: AnchorSerialize> Deref for OrphanArrayElement<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<'a, T: AnchorSerialize> DerefMut for OrphanArrayElement<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<'a, T: AnchorSerialize> Drop for OrphanArrayElement<'a, T> {
+    fn drop(&mut self) {
+        let mut writer = BpfWriter::new(&mut self.data[..]);
+        self.value
+            .serialize(&mut writer)
+            .expect("record exceeds its serialized length");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::clock::Epoch;
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq)]
+    struct Record {
+        id: u32,
+        count: u64,
+    }
+
+    impl ConstSerializedLen for Record {
+        const SERIALIZED_LEN: usize = 4 + 8;
+    }
+
+    fn records_data(n: u32) -> Vec<u8> {
+        let mut data = vec![0xAA; 8];
+        for id in 0..n {
+            Record {
+                id,
+                count: u64::from(id) * 10,
+            }
+            .serialize(&mut data)
+            .unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn random_access_get() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = records_data(4);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let records = OrphanArray::<Record, 4>::try_from(&info).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records.get(2).unwrap(), Record { id: 2, count: 20 });
+        assert_eq!(records.get(0).unwrap(), Record { id: 0, count: 0 });
+        assert_eq!(
+            records.get(4).unwrap_err(),
+            ErrorCode::AccountIndexOutOfRange.into()
+        );
+        assert_eq!(
+            OrphanArray::<Record, 5>::try_from(&info).unwrap_err(),
+            ErrorCode::AccountDidNotDeserialize.into()
+        );
+    }
+
+    #[test]
+    fn random_access_mutate() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = records_data(4);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let records = OrphanArray::<Record, 4>::try_from(&info).unwrap();
+        records.get_mut(1).unwrap().count += 5;
+        records.set(3, &Record { id: 9, count: 90 }).unwrap();
+        assert_eq!(records.get(1).unwrap(), Record { id: 1, count: 15 });
+        assert_eq!(records.get(3).unwrap(), Record { id: 9, count: 90 });
+        drop(records);
+
+        // Only the touched byte ranges changed.
+        let mut expected = records_data(4);
+        expected[8 + 12 + 4..8 + 2 * 12].copy_from_slice(&15u64.to_le_bytes());
+        expected[8 + 3 * 12..].copy_from_slice(&records_data(10)[8 + 9 * 12..]);
+        assert_eq!(&info.try_borrow_data().unwrap()[..], &expected[..]);
+    }
+}
@@ -0,0 +1,197 @@
+//! Zero copy access to accounts without checking ownership.
+
+use crate::accounts::orphan_account::{check_discriminator, check_initialized};
+use crate::error::{Error, ErrorCode};
+use crate::{Key, Result, ToAccountInfos, ZeroCopy};
+use solana_program::account_info::AccountInfo;
+use solana_program::pubkey::Pubkey;
+use std::cell::{Ref, RefMut};
+use std::marker::PhantomData;
+use std::mem;
+
+/// Zero copy counterpart of
+/// [`OrphanAccount`](crate::accounts::orphan_account::OrphanAccount), for
+/// large `#[account(zero_copy)]` accounts that may be owned by any program.
+///
+/// Like [`AccountLoader`](crate::accounts::account_loader::AccountLoader),
+/// the data is only reinterpreted as `T` by [`load`](Self::load) and
+/// [`load_mut`](Self::load_mut), which check that the data is aligned for
+/// `T`. The alignment of a foreign account's data isn't under the program's
+/// control, and casting misaligned data would be undefined behavior.
+///
+/// # Example
+/// ```ignore
+/// let book = OrphanLoader::<OrderBook>::try_from(&ctx.accounts.book)?;
+/// let best_bid = book.load()?.bids[0];
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrphanLoader<'info, T: ZeroCopy> {
+    info: AccountInfo<'info>,
+    phantom: PhantomData<&'info T>,
+}
+
+impl<'info, T: ZeroCopy> OrphanLoader<'info, T> {
+    /// Wraps the given `info`, checking the account is initialized and
+    /// starts with the discriminator of `T`.
+    pub fn try_from(info: &AccountInfo<'info>) -> Result<OrphanLoader<'info, T>> {
+        check_initialized(info)?;
+        check_discriminator::<T>(&info.try_borrow_data()?)?;
+        Ok(Self {
+            info: info.clone(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns a `Ref` to the account data structure for reading.
+    ///
+    /// Fails with [`ErrorCode::AccountDidNotDeserialize`] if the data is too
+    /// short for `T` or not aligned for it.
+    pub fn load(&self) -> Result<Ref<'_, T>> {
+        let data = self.info.try_borrow_data()?;
+        check_discriminator::<T>(&data)?;
+        check_layout::<T>(&data[8..])?;
+        Ok(Ref::map(data, |data| {
+            bytemuck::from_bytes(&data[8..mem::size_of::<T>() + 8])
+        }))
+    }
+
+    /// Returns a `RefMut` to the account data structure for reading or
+    /// writing, see [`load`](Self::load).
+    pub fn load_mut(&self) -> Result<RefMut<'_, T>> {
+        // AccountInfo api allows you to borrow mut even if the account isn't
+        // writable, so add this check for a better dev experience.
+        if !self.info.is_writable {
+            return Err(ErrorCode::AccountNotMutable.into());
+        }
+
+        let data = self.info.try_borrow_mut_data()?;
+        check_discriminator::<T>(&data)?;
+        check_layout::<T>(&data[8..])?;
+        Ok(RefMut::map(data, |data| {
+            bytemuck::from_bytes_mut(&mut data[8..mem::size_of::<T>() + 8])
+        }))
+    }
+}
+
+// Checks `data`, the account data after the discriminator, can be cast to `T`.
+fn check_layout<T>(data: &[u8]) -> Result<()> {
+    if data.len() < mem::size_of::<T>() {
+        return Err(Error::from(ErrorCode::AccountDidNotDeserialize)
+            .with_values((data.len(), mem::size_of::<T>())));
+    }
+    let align = mem::align_of::<T>();
+    if data.as_ptr() as usize % align != 0 {
+        let mut error = Error::from(ErrorCode::AccountDidNotDeserialize);
+        if let Error::AnchorError(e) = &mut error {
+            e.error_msg = format!("{}: data is not aligned to {} bytes", e.error_msg, align);
+        }
+        return Err(error);
+    }
+    Ok(())
+}
+
+impl<'info, T: ZeroCopy> ToAccountInfos<'info> for OrphanLoader<'info, T> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.info.clone()]
+    }
+}
+
+impl<'info, T: ZeroCopy> AsRef<AccountInfo<'info>> for OrphanLoader<'info, T> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        &self.info
+    }
+}
+
+impl<'info, T: ZeroCopy> Key for OrphanLoader<'info, T> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Discriminator;
+    use bytemuck::{Pod, Zeroable};
+    use solana_program::clock::Epoch;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Counter {
+        value: u64,
+    }
+
+    unsafe impl Pod for Counter {}
+    unsafe impl Zeroable for Counter {}
+
+    impl Discriminator for Counter {
+        fn discriminator() -> [u8; 8] {
+            [7; 8]
+        }
+    }
+
+    impl ZeroCopy for Counter {}
+
+    // Returns the words of an account holding a `Counter`, preceded by an
+    // extra word so that the account can also be placed at a misaligned
+    // offset.
+    fn counter_words(value: u64) -> [u64; 3] {
+        [0, u64::from_ne_bytes([7; 8]), value]
+    }
+
+    #[test]
+    fn load_aligned() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut words = counter_words(5);
+        let data: &mut [u8] = bytemuck::cast_slice_mut(&mut words[1..]);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let loader = OrphanLoader::<Counter>::try_from(&info).unwrap();
+        assert_eq!(loader.load().unwrap().value, 5);
+        loader.load_mut().unwrap().value = 6;
+        assert_eq!(loader.load().unwrap().value, 6);
+    }
+
+    #[test]
+    fn load_misaligned() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut words = counter_words(5);
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut words[..]);
+        bytes.copy_within(8.., 1);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut bytes[1..17],
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let loader = OrphanLoader::<Counter>::try_from(&info).unwrap();
+        for err in [
+            loader.load().err().unwrap(),
+            loader.load_mut().err().unwrap(),
+        ] {
+            assert_eq!(err, ErrorCode::AccountDidNotDeserialize.into());
+            match err {
+                Error::AnchorError(e) => assert!(e.error_msg.ends_with("not aligned to 8 bytes")),
+                Error::ProgramError(_) => panic!("unexpected error: {:?}", err),
+            }
+        }
+    }
+}
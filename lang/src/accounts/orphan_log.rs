@@ -0,0 +1,209 @@
+//! Streaming reads of length-prefixed records appended to an orphan account.
+
+use crate::accounts::orphan_account::check_initialized;
+use crate::error::ErrorCode;
+use crate::{AnchorDeserialize, Key, Result, ToAccountInfos};
+use solana_program::account_info::AccountInfo;
+use solana_program::pubkey::Pubkey;
+use std::cell::Ref;
+use std::marker::PhantomData;
+
+/// An append-only log stored in a single account, whose body, after the 8
+/// byte discriminator, is a sequence of records of type `T`, each prefixed
+/// with its length as a little endian `u32`.
+///
+/// [`records`](Self::records) deserializes one record at a time straight from
+/// the borrowed account data, so a large log can be scanned without
+/// collecting it into a `Vec`. The log ends at the end of the account data or
+/// at the first zero length prefix, i.e. at the unused, zeroed tail of the
+/// account. As with
+/// [`OrphanAccount`](crate::accounts::orphan_account::OrphanAccount), neither
+/// the owner nor the discriminator is checked.
+///
+/// # Example
+/// ```ignore
+/// let log = OrphanLog::<Entry>::try_from(&ctx.accounts.log)?;
+/// for entry in log.records()? {
+///     if entry?.id == id {
+///         break;
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrphanLog<'info, T> {
+    info: AccountInfo<'info>,
+    phantom: PhantomData<T>,
+}
+
+impl<'info, T: AnchorDeserialize> OrphanLog<'info, T> {
+    const OFFSET: usize = 8;
+
+    /// Wraps the given `info`, checking it is initialized.
+    pub fn try_from(info: &AccountInfo<'info>) -> Result<OrphanLog<'info, T>> {
+        check_initialized(info)?;
+        if info.data_len() < Self::OFFSET {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+        Ok(Self {
+            info: info.clone(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns an iterator deserializing the records of the log in order.
+    ///
+    /// The account data stays borrowed for the lifetime of the iterator. A
+    /// record that is truncated or fails to deserialize yields
+    /// [`ErrorCode::AccountDidNotDeserialize`] and ends the iteration.
+    pub fn records(&self) -> Result<OrphanLogRecords<'_, T>> {
+        Ok(OrphanLogRecords {
+            data: self.info.try_borrow_data()?,
+            pos: Self::OFFSET,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'info, T> ToAccountInfos<'info> for OrphanLog<'info, T> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.info.clone()]
+    }
+}
+
+impl<'info, T> AsRef<AccountInfo<'info>> for OrphanLog<'info, T> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        &self.info
+    }
+}
+
+impl<'info, T> Key for OrphanLog<'info, T> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
+
+/// Iterator over the records of an [`OrphanLog`], returned by
+/// [`OrphanLog::records`].
+pub struct OrphanLogRecords<'a, T> {
+    data: Ref<'a, &'a mut [u8]>,
+    pos: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<'a, T: AnchorDeserialize> OrphanLogRecords<'a, T> {
+    fn next_record(&mut self) -> Option<Result<T>> {
+        let rest = &self.data[self.pos..];
+        if rest.len() < 4 {
+            return None;
+        }
+        let len = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if len == 0 {
+            return None;
+        }
+        let record = match rest[4..].get(..len) {
+            Some(record) => record,
+            None => return Some(Err(ErrorCode::AccountDidNotDeserialize.into())),
+        };
+        self.pos += 4 + len;
+        Some(T::try_from_slice(record).map_err(|_| ErrorCode::AccountDidNotDeserialize.into()))
+    }
+}
+
+impl<'a, T: AnchorDeserialize> Iterator for OrphanLogRecords<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.next_record();
+        if !matches!(record, Some(Ok(_))) {
+            // Stop for good after the end of the log or a malformed record.
+            self.pos = self.data.len();
+        }
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnchorSerialize;
+    use solana_program::clock::Epoch;
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq)]
+    struct Entry {
+        id: u32,
+        memo: String,
+    }
+
+    fn log_data(entries: &[Entry], capacity: usize) -> Vec<u8> {
+        let mut data = vec![0xAA; 8];
+        for entry in entries {
+            let record = entry.try_to_vec().unwrap();
+            data.extend_from_slice(&(record.len() as u32).to_le_bytes());
+            data.extend_from_slice(&record);
+        }
+        data.resize(data.len().max(capacity), 0);
+        data
+    }
+
+    fn entry(id: u32) -> Entry {
+        Entry {
+            id,
+            memo: "x".repeat(id as usize),
+        }
+    }
+
+    #[test]
+    fn iterate_records() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = log_data(&[entry(1), entry(2), entry(3)], 128);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let log = OrphanLog::<Entry>::try_from(&info).unwrap();
+        let entries: Vec<Entry> = log.records().unwrap().map(Result::unwrap).collect();
+        assert_eq!(entries, vec![entry(1), entry(2), entry(3)]);
+
+        // Stopping early leaves the rest of the log unread.
+        let mut records = log.records().unwrap();
+        assert_eq!(records.next().unwrap().unwrap(), entry(1));
+        assert_eq!(records.pos, 8 + 4 + 9);
+    }
+
+    #[test]
+    fn truncated_record() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = log_data(&[entry(1), entry(5)], 0);
+        data.truncate(data.len() - 1);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let log = OrphanLog::<Entry>::try_from(&info).unwrap();
+        let mut records = log.records().unwrap();
+        assert_eq!(records.next().unwrap().unwrap(), entry(1));
+        assert_eq!(
+            records.next().unwrap().unwrap_err(),
+            ErrorCode::AccountDidNotDeserialize.into()
+        );
+        assert!(records.next().is_none());
+    }
+}
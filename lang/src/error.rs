@@ -105,6 +105,21 @@ pub enum ErrorCode {
     /// 2019 - A space constraint was violated
     #[msg("A space constraint was violated")]
     ConstraintSpace,
+    /// 2020 - A non-executable constraint was violated
+    #[msg("A non-executable constraint was violated")]
+    ConstraintNotExecutable,
+    /// 2021 - A deny self owner constraint was violated
+    #[msg("A deny self owner constraint was violated")]
+    ConstraintDenySelfOwner,
+    /// 2022 - A required_if constraint was violated
+    #[msg("A required_if constraint was violated")]
+    ConstraintRequiredIf,
+    /// 2023 - A flags constraint was violated
+    #[msg("A flags constraint was violated")]
+    ConstraintFlags,
+    /// 2024 - A duplicate mutable account constraint was violated
+    #[msg("A duplicate mutable account constraint was violated")]
+    ConstraintDuplicateMutableAccount,
 
     // Require
     /// 2500 - A require expression was violated
@@ -184,6 +199,63 @@ pub enum ErrorCode {
     /// 3017 - The account was duplicated for more than one reallocation
     #[msg("The account was duplicated for more than one reallocation")]
     AccountDuplicateReallocs,
+    /// 3018 - Too many account keys given to the instruction
+    #[msg("Too many account keys given to the instruction")]
+    AccountTooManyKeys,
+    /// 3019 - The account has fewer lamports than required
+    #[msg("The account has fewer lamports than required")]
+    AccountInsufficientLamports,
+    /// 3020 - The index is out of range for the account
+    #[msg("The index is out of range for the account")]
+    AccountIndexOutOfRange,
+    /// 3021 - The account discriminator is already registered to another type
+    #[msg("The account discriminator is already registered to another type")]
+    AccountDiscriminatorCollision,
+    /// 3022 - The account appears to have been closed or drained of its lamports
+    #[msg("The account appears to have been closed or drained of its lamports")]
+    AccountClosed,
+    /// 3023 - The account is not empty and cannot be closed
+    #[msg("The account is not empty and cannot be closed")]
+    AccountNotEmpty,
+    /// 3024 - The account version is not supported
+    #[msg("The account version is not supported")]
+    AccountVersionUnsupported,
+    /// 3025 - The total lamports of the accounts changed
+    #[msg("The total lamports of the accounts changed")]
+    AccountLamportsNotConserved,
+    /// 3026 - The account can only be written by its designated writer program
+    #[msg("The account can only be written by its designated writer program")]
+    AccountWriteSealed,
+    /// 3027 - The account was not freshly created
+    #[msg("The account was not freshly created")]
+    AccountNotFreshlyCreated,
+    /// 3028 - The account can not be written by this instruction
+    #[msg("The account can not be written by this instruction")]
+    AccountWrongInstruction,
+    /// 3029 - The program owning the account has an unexpected upgrade authority
+    #[msg("The program owning the account has an unexpected upgrade authority")]
+    AccountUpgradeAuthorityMismatch,
+    /// 3030 - The account checksum does not match its content
+    #[msg("The account checksum does not match its content")]
+    AccountChecksumMismatch,
+    /// 3031 - The account data is nested deeper than allowed
+    #[msg("The account data is nested deeper than allowed")]
+    AccountNestingTooDeep,
+    /// 3032 - The account is too large for the return data
+    #[msg("The account is too large for the return data")]
+    AccountReturnDataTooLarge,
+    /// 3033 - The account was re-initialized with a different type
+    #[msg("The account was re-initialized with a different type")]
+    AccountTypeChanged,
+    /// 3034 - The reserved padding of the account is not zeroed
+    #[msg("The reserved padding of the account is not zeroed")]
+    AccountPaddingNotZero,
+    /// 3035 - The account has no field at the given path
+    #[msg("The account has no field at the given path")]
+    AccountFieldNotFound,
+    /// 3036 - The accounts violate an invariant they must uphold jointly
+    #[msg("The accounts violate an invariant they must uphold jointly")]
+    AccountInvariantViolated,
 
     // State.
     /// 4000 - The given state account does not have the correct address
@@ -109,3 +109,217 @@ impl IdlAccount {
         "anchor:idl"
     }
 }
+
+/// Returns the discriminator of the account type `name` of an IDL, the
+/// leading bytes of the SHA256 of `account:<name>`.
+///
+/// The IDL doesn't record the `discriminator_namespace` or
+/// `discriminator_hash` arguments of `#[account]`, so the discriminator of a
+/// type declared with either isn't the one returned here, and the checks
+/// built on it always reject such accounts.
+#[cfg(feature = "client")]
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    let preimage = format!("account:{}", name);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&solana_program::hash::hash(preimage.as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// Checks that `data`, the data of an account, conforms to the borsh layout
+/// of `idl_type` as described by an IDL, e.g. before trusting an account of
+/// a third party program whose schema may have drifted. Types referenced by
+/// name are looked up in `types`, the `types` and `accounts` of the IDL.
+///
+/// The data must start with the discriminator of `idl_type`, see
+/// [`account_discriminator`], and may be followed by padding, as accounts are
+/// often allocated with room to grow.
+#[cfg(feature = "client")]
+pub fn validate_against_idl(
+    data: &[u8],
+    idl_type: &anchor_syn::idl::IdlTypeDefinition,
+    types: &[anchor_syn::idl::IdlTypeDefinition],
+) -> Result<()> {
+    if data.len() < 8 {
+        return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+    }
+    if data[..8] != account_discriminator(&idl_type.name) {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    let mut rest = &data[8..];
+    idl_layout::decode_definition(&mut rest, idl_type, types)
+        .map(drop)
+        .map_err(|e| e.with_values((data.len() - rest.len(), data.len())))
+}
+
+/// Reads the value of the field at `field_path` from `data`, the data of an
+/// account of type `type_name` declared in `idl`, without compiling the type,
+/// e.g. for a generic account inspector.
+///
+/// The path is a dotted list of field names, with indices for the elements
+/// of vectors and arrays, e.g. `fees.1.bps`. An empty path returns the whole
+/// account. Integers wider than 64 bits are returned as strings, public keys
+/// in base 58, and enum variants as an object keyed by the variant name.
+///
+/// Fails with [`ErrorCode::AccountDiscriminatorMismatch`] if `type_name`
+/// isn't an account of `idl` or `data` holds another account, see
+/// [`account_discriminator`], and with
+/// [`ErrorCode::AccountFieldNotFound`] if the path leads nowhere.
+#[cfg(feature = "client")]
+pub fn get_field(
+    data: &[u8],
+    idl: &anchor_syn::idl::Idl,
+    type_name: &str,
+    field_path: &str,
+) -> Result<serde_json::Value> {
+    let account = idl
+        .accounts
+        .iter()
+        .find(|account| account.name == type_name)
+        .ok_or(ErrorCode::AccountDiscriminatorMismatch)?;
+    if !data.starts_with(&account_discriminator(type_name)) {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    let types: Vec<_> = idl.types.iter().chain(&idl.accounts).cloned().collect();
+    let mut value = idl_layout::decode_definition(&mut &data[8..], account, &types)?;
+    for segment in field_path.split('.').filter(|segment| !segment.is_empty()) {
+        let next = match &mut value {
+            serde_json::Value::Object(fields) => fields.remove(segment),
+            serde_json::Value::Array(elements) => segment
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < elements.len())
+                .map(|index| elements.swap_remove(index)),
+            _ => None,
+        };
+        value = next.ok_or_else(|| {
+            Error::from(ErrorCode::AccountFieldNotFound).with_account_name(field_path)
+        })?;
+    }
+    Ok(value)
+}
+
+// Decodes borsh data along the layout described by an IDL. Validation
+// decodes and drops the value, so both agree on what a layout accepts.
+#[cfg(feature = "client")]
+mod idl_layout {
+    use crate::error::ErrorCode;
+    use crate::Result;
+    use anchor_syn::idl::{EnumFields, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+    use serde_json::{Map, Value};
+    use solana_program::pubkey::Pubkey;
+
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if data.len() < len {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(data: &mut &[u8]) -> Result<[u8; N]> {
+        Ok(take(data, N)?.try_into().unwrap())
+    }
+
+    fn take_len(data: &mut &[u8]) -> Result<usize> {
+        let len = take(data, 4)?;
+        Ok(u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize)
+    }
+
+    pub fn decode_definition(
+        data: &mut &[u8],
+        definition: &IdlTypeDefinition,
+        types: &[IdlTypeDefinition],
+    ) -> Result<Value> {
+        match &definition.ty {
+            IdlTypeDefinitionTy::Struct { fields } => decode_fields(data, fields, types),
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let variant = variants
+                    .get(take(data, 1)?[0] as usize)
+                    .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+                let fields = match &variant.fields {
+                    None => Value::Object(Map::new()),
+                    Some(EnumFields::Named(fields)) => decode_fields(data, fields, types)?,
+                    Some(EnumFields::Tuple(tys)) => Value::Array(
+                        tys.iter()
+                            .map(|ty| decode(data, ty, types))
+                            .collect::<Result<_>>()?,
+                    ),
+                };
+                let mut value = Map::new();
+                value.insert(variant.name.clone(), fields);
+                Ok(Value::Object(value))
+            }
+        }
+    }
+
+    fn decode_fields(
+        data: &mut &[u8],
+        fields: &[IdlField],
+        types: &[IdlTypeDefinition],
+    ) -> Result<Value> {
+        let mut value = Map::new();
+        for field in fields {
+            value.insert(field.name.clone(), decode(data, &field.ty, types)?);
+        }
+        Ok(Value::Object(value))
+    }
+
+    fn decode(data: &mut &[u8], ty: &IdlType, types: &[IdlTypeDefinition]) -> Result<Value> {
+        Ok(match ty {
+            IdlType::Bool => match take(data, 1)?[0] {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                _ => return Err(ErrorCode::AccountDidNotDeserialize.into()),
+            },
+            IdlType::U8 => take(data, 1)?[0].into(),
+            IdlType::I8 => (take(data, 1)?[0] as i8).into(),
+            IdlType::U16 => u16::from_le_bytes(array(data)?).into(),
+            IdlType::I16 => i16::from_le_bytes(array(data)?).into(),
+            IdlType::U32 => u32::from_le_bytes(array(data)?).into(),
+            IdlType::I32 => i32::from_le_bytes(array(data)?).into(),
+            IdlType::F32 => f32::from_le_bytes(array(data)?).into(),
+            IdlType::U64 => u64::from_le_bytes(array(data)?).into(),
+            IdlType::I64 => i64::from_le_bytes(array(data)?).into(),
+            IdlType::F64 => f64::from_le_bytes(array(data)?).into(),
+            IdlType::U128 => u128::from_le_bytes(array(data)?).to_string().into(),
+            IdlType::I128 => i128::from_le_bytes(array(data)?).to_string().into(),
+            IdlType::PublicKey => Pubkey::new_from_array(array(data)?).to_string().into(),
+            IdlType::Bytes => {
+                let len = take_len(data)?;
+                take(data, len)?.to_vec().into()
+            }
+            IdlType::String => {
+                let len = take_len(data)?;
+                std::str::from_utf8(take(data, len)?)
+                    .map_err(|_| ErrorCode::AccountDidNotDeserialize)?
+                    .into()
+            }
+            IdlType::Option(inner) => match take(data, 1)?[0] {
+                0 => Value::Null,
+                1 => decode(data, inner, types)?,
+                _ => return Err(ErrorCode::AccountDidNotDeserialize.into()),
+            },
+            IdlType::Vec(inner) => {
+                let len = take_len(data)?;
+                Value::Array(
+                    (0..len)
+                        .map(|_| decode(data, inner, types))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            IdlType::Array(inner, len) => Value::Array(
+                (0..*len)
+                    .map(|_| decode(data, inner, types))
+                    .collect::<Result<_>>()?,
+            ),
+            IdlType::Defined(name) => {
+                let definition = types
+                    .iter()
+                    .find(|definition| &definition.name == name)
+                    .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+                decode_definition(data, definition, types)?
+            }
+        })
+    }
+}
@@ -84,6 +84,20 @@ pub trait Accounts<'info>: ToAccountMetas + ToAccountInfos<'info> + Sized {
         bumps: &mut BTreeMap<String, u8>,
         reallocs: &mut BTreeSet<Pubkey>,
     ) -> Result<Self>;
+
+    /// Like [`try_accounts`](Self::try_accounts), for a struct nested in
+    /// another accounts struct, whose later fields consume the accounts left
+    /// over by this one. A `#[strict_accounts]` struct only rejects leftover
+    /// accounts when it is not nested.
+    fn try_accounts_nested(
+        program_id: &Pubkey,
+        accounts: &mut &[AccountInfo<'info>],
+        ix_data: &[u8],
+        bumps: &mut BTreeMap<String, u8>,
+        reallocs: &mut BTreeSet<Pubkey>,
+    ) -> Result<Self> {
+        Self::try_accounts(program_id, accounts, ix_data, bumps, reallocs)
+    }
 }
 
 /// The exit procedure for an account. Any cleanup or persistence to storage
@@ -214,6 +228,25 @@ pub trait Owner {
     fn owner() -> Pubkey;
 }
 
+/// Defines the set of programs trusted to own an account, generated by
+/// `#[account(orphan_owners = [..])]`.
+pub trait Owners {
+    fn owners() -> Vec<Pubkey>;
+}
+
+/// Human readable metadata of an account type, for client tooling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountMetadata {
+    pub label: Option<&'static str>,
+    pub category: Option<&'static str>,
+}
+
+/// Defines the metadata of an account type, generated by
+/// `#[account(label = "..", category = "..")]`.
+pub trait Labeled {
+    fn metadata() -> AccountMetadata;
+}
+
 /// Defines the id of a program.
 pub trait Id {
     fn id() -> Pubkey;
@@ -230,20 +263,32 @@ impl Key for Pubkey {
     }
 }
 
+/// Compares two keys in constant time, i.e. without returning early on the
+/// first differing byte.
+pub fn ct_eq(a: &Pubkey, b: &Pubkey) -> bool {
+    a.as_ref()
+        .iter()
+        .zip(b.as_ref())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
 /// The prelude contains all commonly used components of the crate.
 /// All programs should include it via `anchor_lang::prelude::*;`.
 pub mod prelude {
     pub use super::{
         access_control, account, accounts::account::Account,
-        accounts::account_loader::AccountLoader, accounts::program::Program,
-        accounts::signer::Signer, accounts::system_account::SystemAccount,
-        accounts::sysvar::Sysvar, accounts::unchecked_account::UncheckedAccount, constant,
-        context::Context, context::CpiContext, declare_id, emit, err, error, event, interface,
-        program, require, require_eq, require_gt, require_gte, require_keys_eq, require_keys_neq,
-        require_neq, solana_program::bpf_loader_upgradeable::UpgradeableLoaderState, source, state,
+        accounts::account_loader::AccountLoader, accounts::orphan_account::OrphanAccount,
+        accounts::orphan_array::OrphanArray, accounts::orphan_loader::OrphanLoader,
+        accounts::orphan_log::OrphanLog, accounts::program::Program, accounts::signer::Signer,
+        accounts::system_account::SystemAccount, accounts::sysvar::Sysvar,
+        accounts::unchecked_account::UncheckedAccount, constant, context::Context,
+        context::CpiContext, declare_id, emit, err, error, event, interface, program, require,
+        require_eq, require_gt, require_gte, require_keys_eq, require_keys_neq, require_neq,
+        solana_program::bpf_loader_upgradeable::UpgradeableLoaderState, source, state,
         system_program::System, zero_copy, AccountDeserialize, AccountSerialize, Accounts,
-        AccountsExit, AnchorDeserialize, AnchorSerialize, Id, Key, Owner, ProgramData, Result,
-        ToAccountInfo, ToAccountInfos, ToAccountMetas,
+        AccountsExit, AnchorDeserialize, AnchorSerialize, Id, Key, Labeled, Owner, Owners,
+        ProgramData, Result, ToAccountInfo, ToAccountInfos, ToAccountMetas,
     };
     pub use anchor_attribute_error::*;
     pub use borsh;
@@ -314,6 +359,17 @@ pub mod __private {
 
     #[doc(hidden)]
     pub use crate::accounts::state::PROGRAM_STATE_SEED;
+
+    // Key comparison used by the `OrphanAccount` owner and has_one checks.
+    // Constant time if the `constant-time` feature is enabled.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn keys_eq(a: &Pubkey, b: &Pubkey) -> bool {
+        #[cfg(feature = "constant-time")]
+        return crate::ct_eq(a, b);
+        #[cfg(not(feature = "constant-time"))]
+        return a == b;
+    }
 }
 
 /// Ensures a condition is true, otherwise returns with the given error.
@@ -579,3 +635,28 @@ macro_rules! source {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ct_eq() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        assert!(ct_eq(&a, &a));
+        assert!(!ct_eq(&a, &b));
+
+        let mut last_byte_differs = a.to_bytes();
+        last_byte_differs[31] ^= 1;
+        assert!(!ct_eq(&a, &Pubkey::new_from_array(last_byte_differs)));
+    }
+
+    #[test]
+    fn test_keys_eq() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        assert!(__private::keys_eq(&a, &a));
+        assert!(!__private::keys_eq(&a, &b));
+    }
+}
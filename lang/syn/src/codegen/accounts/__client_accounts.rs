@@ -61,9 +61,15 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                 } else {
                     quote!()
                 };
-                quote! {
-                    #docs
-                    pub #name: anchor_lang::solana_program::pubkey::Pubkey
+                match f.is_optional {
+                    false => quote! {
+                        #docs
+                        pub #name: anchor_lang::solana_program::pubkey::Pubkey
+                    },
+                    true => quote! {
+                        #docs
+                        pub #name: Option<anchor_lang::solana_program::pubkey::Pubkey>
+                    },
                 }
             }
         })
@@ -93,12 +99,104 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                     true => quote! { anchor_lang::solana_program::instruction::AccountMeta::new },
                 };
                 let name = &f.ident;
-                quote! {
-                    account_metas.push(#meta(self.#name, #is_signer));
+                match f.is_optional {
+                    // Absent accounts are replaced by the program id.
+                    true => quote! {
+                        match self.#name {
+                            Some(#name) => account_metas.push(#meta(#name, #is_signer)),
+                            None => account_metas.push(anchor_lang::solana_program::instruction::AccountMeta::new_readonly(crate::ID, false)),
+                        }
+                    },
+                    false => quote! {
+                        account_metas.push(#meta(self.#name, #is_signer));
+                    },
+                }
+            }
+        })
+        .collect();
+    let orphan_account_metas: Vec<proc_macro2::TokenStream> = accs
+        .fields
+        .iter()
+        .filter_map(|f: &AccountField| match f {
+            AccountField::CompositeField(s) => {
+                let name = &s.ident;
+                Some(quote! {
+                    account_metas.extend(self.#name.orphan_account_metas());
+                })
+            }
+            AccountField::Field(f) => {
+                if !matches!(f.ty, Ty::OrphanAccount(_)) {
+                    return None;
+                }
+                let meta = match f.constraints.is_mutable() {
+                    false => quote! { anchor_lang::solana_program::instruction::AccountMeta::new_readonly },
+                    true => quote! { anchor_lang::solana_program::instruction::AccountMeta::new },
+                };
+                let is_signer = f.constraints.is_signer();
+                let name = &f.ident;
+                Some(match f.is_optional {
+                    true => quote! {
+                        if let Some(#name) = self.#name {
+                            account_metas.push(#meta(#name, #is_signer));
+                        }
+                    },
+                    false => quote! {
+                        account_metas.push(#meta(self.#name, #is_signer));
+                    },
+                })
+            }
+        })
+        .collect();
+
+    // Keys of the accounts a seed may refer to on the client.
+    let client_keys: Vec<String> = accs
+        .fields
+        .iter()
+        .filter_map(|f: &AccountField| match f {
+            AccountField::Field(f) if !f.is_optional => Some(f.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    let orphan_seeds: Vec<proc_macro2::TokenStream> = accs
+        .fields
+        .iter()
+        .filter_map(|f: &AccountField| match f {
+            AccountField::Field(f) if matches!(f.ty, Ty::OrphanAccount(_)) => {
+                let seeds = f.constraints.seeds.as_ref()?;
+                let mut keys = Vec::new();
+                if !seeds
+                    .seeds
+                    .iter()
+                    .all(|seed| is_client_seed(seed, &client_keys, &mut keys))
+                {
+                    // Seeds using instruction arguments or account data
+                    // can't be evaluated from the keys alone.
+                    return None;
                 }
+                keys.sort();
+                keys.dedup();
+                let keys: Vec<syn::Ident> = keys
+                    .iter()
+                    .map(|k| syn::Ident::new(k, proc_macro2::Span::call_site()))
+                    .collect();
+                let seeds: Vec<&syn::Expr> = seeds.seeds.iter().collect();
+                let fn_name = quote::format_ident!("{}_seeds", f.ident);
+                let doc = format!(
+                    " Returns the seeds of the `{}` PDA, evaluated with the keys of this struct.",
+                    f.ident
+                );
+                Some(quote! {
+                    #[doc = #doc]
+                    pub fn #fn_name(&self) -> Vec<Vec<u8>> {
+                        #(let #keys: &anchor_lang::solana_program::pubkey::Pubkey = &self.#keys;)*
+                        vec![#(<[u8]>::to_vec(#seeds)),*]
+                    }
+                })
             }
+            _ => None,
         })
         .collect();
+
     // Re-export all composite account structs (i.e. other structs deriving
     // accounts embedded into this struct. Required because, these embedded
     // structs are *not* visible from the #[program] macro, which is responsible
@@ -166,6 +264,46 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                     account_metas
                 }
             }
+
+            #[automatically_derived]
+            impl #name {
+                /// Returns the metas of all `OrphanAccount` fields, including
+                /// those of nested accounts structs, marked writable if the
+                /// field is `mut`. Absent optional accounts are skipped.
+                #[allow(unused_mut)]
+                pub fn orphan_account_metas(&self) -> Vec<anchor_lang::solana_program::instruction::AccountMeta> {
+                    let mut account_metas = vec![];
+
+                    #(#orphan_account_metas)*
+
+                    account_metas
+                }
+
+                #(#orphan_seeds)*
+            }
         }
     }
 }
+
+// Returns true if `seed` only uses literals and the keys of `client_keys`,
+// collecting the keys it uses into `used`.
+fn is_client_seed(seed: &syn::Expr, client_keys: &[String], used: &mut Vec<String>) -> bool {
+    match seed {
+        syn::Expr::Lit(_) => true,
+        syn::Expr::Reference(r) => is_client_seed(&r.expr, client_keys, used),
+        syn::Expr::Paren(p) => is_client_seed(&p.expr, client_keys, used),
+        syn::Expr::MethodCall(m) => {
+            ["key", "as_ref", "as_bytes"].contains(&m.method.to_string().as_str())
+                && m.args.is_empty()
+                && is_client_seed(&m.receiver, client_keys, used)
+        }
+        syn::Expr::Path(p) => match p.path.get_ident() {
+            Some(ident) if client_keys.contains(&ident.to_string()) => {
+                used.push(ident.to_string());
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
@@ -62,9 +62,15 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                 } else {
                     quote!()
                 };
-                quote! {
-                    #docs
-                    pub #name: anchor_lang::solana_program::account_info::AccountInfo<'info>
+                match f.is_optional {
+                    false => quote! {
+                        #docs
+                        pub #name: anchor_lang::solana_program::account_info::AccountInfo<'info>
+                    },
+                    true => quote! {
+                        #docs
+                        pub #name: Option<anchor_lang::solana_program::account_info::AccountInfo<'info>>
+                    },
                 }
             }
         })
@@ -94,8 +100,17 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                     true => quote! { anchor_lang::solana_program::instruction::AccountMeta::new },
                 };
                 let name = &f.ident;
-                quote! {
-                    account_metas.push(#meta(anchor_lang::Key::key(&self.#name), #is_signer));
+                match f.is_optional {
+                    // Absent accounts are replaced by the program id.
+                    true => quote! {
+                        match &self.#name {
+                            Some(#name) => account_metas.push(#meta(anchor_lang::Key::key(#name), #is_signer)),
+                            None => account_metas.push(anchor_lang::solana_program::instruction::AccountMeta::new_readonly(crate::ID, false)),
+                        }
+                    },
+                    false => quote! {
+                        account_metas.push(#meta(anchor_lang::Key::key(&self.#name), #is_signer));
+                    },
                 }
             }
         })
@@ -113,8 +128,13 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
             }
             AccountField::Field(f) => {
                 let name = &f.ident;
-                quote! {
-                    account_infos.push(anchor_lang::ToAccountInfo::to_account_info(&self.#name));
+                match f.is_optional {
+                    false => quote! {
+                        account_infos.push(anchor_lang::ToAccountInfo::to_account_info(&self.#name));
+                    },
+                    true => quote! {
+                        account_infos.extend(self.#name.clone());
+                    },
                 }
             }
         })
@@ -1,6 +1,6 @@
 use crate::*;
 use proc_macro2_diagnostics::SpanDiagnosticExt;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::Expr;
 
 pub fn generate(f: &Field) -> proc_macro2::TokenStream {
@@ -14,12 +14,29 @@ pub fn generate(f: &Field) -> proc_macro2::TokenStream {
 
     let checks: Vec<proc_macro2::TokenStream> = constraints
         .iter()
+        .filter(|c| !matches!(c, Constraint::RequiredIf(_)))
         .map(|c| generate_constraint(f, c))
         .collect();
 
+    if !f.is_optional {
+        return quote! {
+            #rent
+            #(#checks)*
+        };
+    }
+
+    // Optional accounts are only checked when present.
+    let name = &f.ident;
+    let required_if = constraints
+        .iter()
+        .filter(|c| matches!(c, Constraint::RequiredIf(_)))
+        .map(|c| generate_constraint(f, c));
     quote! {
-        #rent
-        #(#checks)*
+        #(#required_if)*
+        if let Some(#name) = &#name {
+            #rent
+            #(#checks)*
+        }
     }
 }
 
@@ -53,6 +70,8 @@ pub fn linearize(c_group: &ConstraintGroup) -> Vec<Constraint> {
         rent_exempt,
         seeds,
         executable,
+        deny_self_owner,
+        required_if,
         state,
         close,
         address,
@@ -64,6 +83,10 @@ pub fn linearize(c_group: &ConstraintGroup) -> Vec<Constraint> {
 
     let mut constraints = Vec::new();
 
+    if let Some(c) = required_if {
+        constraints.push(Constraint::RequiredIf(c));
+    }
+
     if let Some(c) = zeroed {
         constraints.push(Constraint::Zeroed(c));
     }
@@ -97,6 +120,9 @@ pub fn linearize(c_group: &ConstraintGroup) -> Vec<Constraint> {
     if let Some(c) = executable {
         constraints.push(Constraint::Executable(c));
     }
+    if let Some(c) = deny_self_owner {
+        constraints.push(Constraint::DenySelfOwner(c));
+    }
     if let Some(c) = state {
         constraints.push(Constraint::State(c));
     }
@@ -128,6 +154,8 @@ fn generate_constraint(f: &Field, c: &Constraint) -> proc_macro2::TokenStream {
         Constraint::RentExempt(c) => generate_constraint_rent_exempt(f, c),
         Constraint::Seeds(c) => generate_constraint_seeds(f, c),
         Constraint::Executable(c) => generate_constraint_executable(f, c),
+        Constraint::DenySelfOwner(c) => generate_constraint_deny_self_owner(f, c),
+        Constraint::RequiredIf(c) => generate_constraint_required_if(f, c),
         Constraint::State(c) => generate_constraint_state(f, c),
         Constraint::Close(c) => generate_constraint_close(f, c),
         Constraint::Address(c) => generate_constraint_address(f, c),
@@ -224,11 +252,17 @@ pub fn generate_constraint_has_one(f: &Field, c: &ConstraintHasOne) -> proc_macr
         quote! { ConstraintHasOne },
         &Some(&(quote! { my_key }, quote! { target_key })),
     );
+    let keys_ne = match &f.ty {
+        Ty::OrphanAccount(_) => quote! {
+            !anchor_lang::__private::keys_eq(&my_key, &target_key)
+        },
+        _ => quote! { my_key != target_key },
+    };
     quote! {
         {
             let my_key = #field.#target;
             let target_key = #target.key();
-            if my_key != target_key {
+            if #keys_ne {
                 return #error;
             }
         }
@@ -241,6 +275,7 @@ pub fn generate_constraint_signer(f: &Field, c: &ConstraintSigner) -> proc_macro
         Ty::AccountInfo => quote! { #ident },
         Ty::ProgramAccount(_) => quote! { #ident.to_account_info() },
         Ty::Account(_) => quote! { #ident.to_account_info() },
+        Ty::OrphanAccount(_) => quote! { #ident.to_account_info() },
         Ty::Loader(_) => quote! { #ident.to_account_info() },
         Ty::AccountLoader(_) => quote! { #ident.to_account_info() },
         Ty::CpiAccount(_) => quote! { #ident.to_account_info() },
@@ -295,11 +330,17 @@ pub fn generate_constraint_owner(f: &Field, c: &ConstraintOwner) -> proc_macro2:
         quote! { ConstraintOwner },
         &Some(&(quote! { *my_owner }, quote! { owner_address })),
     );
+    let owners_ne = match &f.ty {
+        Ty::OrphanAccount(_) => quote! {
+            !anchor_lang::__private::keys_eq(my_owner, &owner_address)
+        },
+        _ => quote! { my_owner != &owner_address },
+    };
     quote! {
         {
             let my_owner = AsRef::<AccountInfo>::as_ref(&#ident).owner;
             let owner_address = #owner_address;
-            if my_owner != &owner_address {
+            if #owners_ne {
                 return #error;
             }
         }
@@ -578,6 +619,26 @@ fn generate_constraint_init_group(f: &Field, c: &ConstraintInitGroup) -> proc_ma
             // Define the space variable.
             let space = quote! {let space = #space;};
 
+            // Assert the space fits the account's default value, which is
+            // only known for orphan accounts. The length is spanned to the
+            // field, so a type without `Default` is reported there.
+            let space_check = match &f.ty {
+                Ty::OrphanAccount(_) => {
+                    let default_len = quote_spanned! { f.ident.span() =>
+                        <#ty_decl>::default_len()
+                    };
+                    quote! {
+                        {
+                            let required_space = #default_len?;
+                            if space < required_space {
+                                return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSpace).with_account_name(#name_str).with_values((space, required_space)));
+                            }
+                        }
+                    }
+                }
+                _ => quote! {},
+            };
+
             // Define the owner of the account being created. If not specified,
             // default to the currently executing program.
             let owner = match owner {
@@ -604,6 +665,7 @@ fn generate_constraint_init_group(f: &Field, c: &ConstraintInitGroup) -> proc_ma
 
                     // Define the account space variable.
                     #space
+                    #space_check
 
                     // Create the account. Always do this in the event
                     // if needed is not specified or the system program is the owner.
@@ -869,13 +931,48 @@ pub fn generate_create_account(
 
 pub fn generate_constraint_executable(
     f: &Field,
-    _c: &ConstraintExecutable,
+    c: &ConstraintExecutable,
+) -> proc_macro2::TokenStream {
+    let name = &f.ident;
+    let name_str = name.to_string();
+    if c.executable {
+        quote! {
+            if !#name.to_account_info().executable {
+                return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintExecutable).with_account_name(#name_str));
+            }
+        }
+    } else {
+        quote! {
+            if #name.to_account_info().executable {
+                return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintNotExecutable).with_account_name(#name_str));
+            }
+        }
+    }
+}
+
+pub fn generate_constraint_deny_self_owner(
+    f: &Field,
+    _c: &ConstraintDenySelfOwner,
+) -> proc_macro2::TokenStream {
+    let name = &f.ident;
+    let name_str = name.to_string();
+    quote! {
+        if #name.to_account_info().owner == program_id {
+            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintDenySelfOwner).with_account_name(#name_str));
+        }
+    }
+}
+
+pub fn generate_constraint_required_if(
+    f: &Field,
+    c: &ConstraintRequiredIf,
 ) -> proc_macro2::TokenStream {
     let name = &f.ident;
     let name_str = name.to_string();
+    let condition = &c.condition;
     quote! {
-        if !#name.to_account_info().executable {
-            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintExecutable).with_account_name(#name_str));
+        if (#condition) && #name.is_none() {
+            return Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintRequiredIf).with_account_name(#name_str));
         }
     }
 }
@@ -9,15 +9,21 @@ mod __client_accounts;
 mod __cpi_client_accounts;
 mod constraints;
 mod exit;
+mod snapshot_orphans;
+mod stack_budget;
 mod to_account_infos;
 mod to_account_metas;
 mod try_accounts;
+mod writable_orphan_keys;
 
 pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
     let impl_try_accounts = try_accounts::generate(accs);
     let impl_to_account_infos = to_account_infos::generate(accs);
     let impl_to_account_metas = to_account_metas::generate(accs);
     let impl_exit = exit::generate(accs);
+    let impl_writable_orphan_keys = writable_orphan_keys::generate(accs);
+    let stack_budget_assertions = stack_budget::generate(accs);
+    let snapshot_orphans = snapshot_orphans::generate(accs);
 
     let __client_accounts_mod = __client_accounts::generate(accs);
     let __cpi_client_accounts_mod = __cpi_client_accounts::generate(accs);
@@ -27,6 +33,9 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
         #impl_to_account_infos
         #impl_to_account_metas
         #impl_exit
+        #impl_writable_orphan_keys
+        #stack_budget_assertions
+        #snapshot_orphans
 
         #__client_accounts_mod
         #__cpi_client_accounts_mod
@@ -0,0 +1,70 @@
+use crate::{AccountField, AccountsStruct, Ty};
+use quote::{format_ident, quote};
+
+// Generates the `snapshot_orphans` method and the snapshot struct it
+// returns, if requested with `#[snapshot_orphans]`.
+pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
+    if !accs.snapshot {
+        return quote! {};
+    }
+    let name = &accs.ident;
+    let snapshot_name = format_ident!("{}OrphanSnapshot", name);
+
+    let orphans: Vec<(&syn::Ident, &syn::TypePath, bool)> = accs
+        .fields
+        .iter()
+        .filter_map(|af: &AccountField| match af {
+            AccountField::Field(f) => match &f.ty {
+                Ty::OrphanAccount(ty) => Some((&f.ident, &ty.account_type_path, f.is_optional)),
+                _ => None,
+            },
+            AccountField::CompositeField(_) => None,
+        })
+        .collect();
+
+    let snapshot_fields: Vec<proc_macro2::TokenStream> = orphans
+        .iter()
+        .map(|(ident, account_ty, is_optional)| {
+            if *is_optional {
+                quote! { pub #ident: Option<#account_ty> }
+            } else {
+                quote! { pub #ident: #account_ty }
+            }
+        })
+        .collect();
+    let snapshot_values: Vec<proc_macro2::TokenStream> = orphans
+        .iter()
+        .map(|(ident, _, is_optional)| {
+            if *is_optional {
+                quote! { #ident: self.#ident.as_ref().map(|account| account.snapshot()) }
+            } else {
+                quote! { #ident: self.#ident.snapshot() }
+            }
+        })
+        .collect();
+
+    let (impl_gen, ty_gen, where_clause) = accs.generics.split_for_impl();
+    let snapshot_doc = format!(
+        "Clones of the `OrphanAccount` values of [`{}`], returned by `snapshot_orphans`.",
+        name
+    );
+
+    quote! {
+        #[doc = #snapshot_doc]
+        #[derive(Clone)]
+        pub struct #snapshot_name {
+            #(#snapshot_fields,)*
+        }
+
+        #[automatically_derived]
+        impl #impl_gen #name #ty_gen #where_clause {
+            /// Returns a clone of the value of every `OrphanAccount` field, e.g.
+            /// to compare against after a CPI.
+            pub fn snapshot_orphans(&self) -> #snapshot_name {
+                #snapshot_name {
+                    #(#snapshot_values,)*
+                }
+            }
+        }
+    }
+}
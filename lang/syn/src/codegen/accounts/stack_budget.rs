@@ -0,0 +1,42 @@
+use crate::{AccountField, AccountsStruct, Ty};
+use quote::{quote, quote_spanned};
+
+// Generates a compile time assertion, for each orphan account field, that its
+// account type fits in the budget given by `#[orphan_stack_budget(..)]`.
+pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
+    let budget = match &accs.stack_budget {
+        None => return quote! {},
+        Some(budget) => budget,
+    };
+
+    let assertions: Vec<proc_macro2::TokenStream> = accs
+        .fields
+        .iter()
+        .filter_map(|af: &AccountField| match af {
+            AccountField::Field(f) => match &f.ty {
+                Ty::OrphanAccount(ty) => {
+                    let name = &f.ident;
+                    let account_ty = &ty.account_type_path;
+                    let message = format!(
+                        " The account type of `{}` must fit in the orphan stack \
+                        budget of {} bytes, consider boxing its large fields.",
+                        name, budget,
+                    );
+                    // Const panics need Rust 1.57, so an oversized account
+                    // type instead fails the array length computation,
+                    // reported at the field.
+                    Some(quote_spanned! { name.span() =>
+                        #[doc = #message]
+                        const _: [(); 0 - !(std::mem::size_of::<#account_ty>() <= #budget) as usize] = [];
+                    })
+                }
+                _ => None,
+            },
+            AccountField::CompositeField(_) => None,
+        })
+        .collect();
+
+    quote! {
+        #(#assertions)*
+    }
+}
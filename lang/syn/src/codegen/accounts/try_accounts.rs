@@ -1,5 +1,5 @@
 use crate::codegen::accounts::{constraints, generics, ParsedGenerics};
-use crate::{AccountField, AccountsStruct};
+use crate::{AccountField, AccountsStruct, Field, Ty};
 use quote::quote;
 use syn::Expr;
 
@@ -17,7 +17,8 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
     let deser_fields: Vec<proc_macro2::TokenStream> = accs
         .fields
         .iter()
-        .map(|af: &AccountField| {
+        .enumerate()
+        .map(|(i, af)| {
             match af {
                 AccountField::CompositeField(s) => {
                     let name = &s.ident;
@@ -25,7 +26,7 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                     quote! {
                         #[cfg(feature = "anchor-debug")]
                         ::solana_program::log::sol_log(stringify!(#name));
-                        let #name: #ty = anchor_lang::Accounts::try_accounts(program_id, accounts, ix_data, __bumps, __reallocs)?;
+                        let #name: #ty = anchor_lang::Accounts::try_accounts_nested(program_id, accounts, ix_data, __bumps, __reallocs)?;
                     }
                 }
                 AccountField::Field(f) => {
@@ -44,11 +45,14 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                     } else {
                         let name = f.ident.to_string();
                         let typed_name = f.typed_ident();
+                        let deser = generate_memoized(accs, i, f, quote! {
+                            anchor_lang::Accounts::try_accounts(program_id, accounts, ix_data, __bumps, __reallocs)
+                                .map_err(|e| e.with_account_name(#name))?
+                        });
                         quote! {
                             #[cfg(feature = "anchor-debug")]
                             ::solana_program::log::sol_log(stringify!(#typed_name));
-                            let #typed_name = anchor_lang::Accounts::try_accounts(program_id, accounts, ix_data, __bumps, __reallocs)
-                                .map_err(|e| e.with_account_name(#name))?;
+                            let #typed_name = #deser;
                         }
                     }
                 }
@@ -57,6 +61,7 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
         .collect();
 
     let constraints = generate_constraints(accs);
+    let alias_warnings = generate_alias_warnings(accs);
     let accounts_instance = generate_accounts_instance(accs);
 
     let ix_de = match &accs.instruction_api {
@@ -89,9 +94,23 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
         }
     };
 
-    quote! {
-        #[automatically_derived]
-        impl<#combined_generics> anchor_lang::Accounts<#trait_generics> for #name<#struct_generics> #where_clause {
+    let body = quote! {
+        // Deserialize instruction, if declared.
+        #ix_de
+        // Deserialize each account.
+        #(#deser_fields)*
+        // Execute accounts constraints.
+        #constraints
+        // Warn about writable orphan accounts aliasing other accounts.
+        #alias_warnings
+        // Success. Return the validated accounts.
+        Ok(#accounts_instance)
+    };
+
+    // A strict struct rejects leftover accounts, unless it's nested in another
+    // struct whose later fields consume them.
+    let try_accounts = match accs.strict {
+        false => quote! {
             #[inline(never)]
             fn try_accounts(
                 program_id: &anchor_lang::solana_program::pubkey::Pubkey,
@@ -100,15 +119,41 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                 __bumps: &mut std::collections::BTreeMap<String, u8>,
                 __reallocs: &mut std::collections::BTreeSet<anchor_lang::solana_program::pubkey::Pubkey>,
             ) -> anchor_lang::Result<Self> {
-                // Deserialize instruction, if declared.
-                #ix_de
-                // Deserialize each account.
-                #(#deser_fields)*
-                // Execute accounts constraints.
-                #constraints
-                // Success. Return the validated accounts.
-                Ok(#accounts_instance)
+                #body
+            }
+        },
+        true => quote! {
+            fn try_accounts(
+                program_id: &anchor_lang::solana_program::pubkey::Pubkey,
+                accounts: &mut &[anchor_lang::solana_program::account_info::AccountInfo<'info>],
+                ix_data: &[u8],
+                __bumps: &mut std::collections::BTreeMap<String, u8>,
+                __reallocs: &mut std::collections::BTreeSet<anchor_lang::solana_program::pubkey::Pubkey>,
+            ) -> anchor_lang::Result<Self> {
+                let __accounts = Self::try_accounts_nested(program_id, accounts, ix_data, __bumps, __reallocs)?;
+                if !accounts.is_empty() {
+                    return Err(anchor_lang::error::ErrorCode::AccountTooManyKeys.into());
+                }
+                Ok(__accounts)
             }
+
+            #[inline(never)]
+            fn try_accounts_nested(
+                program_id: &anchor_lang::solana_program::pubkey::Pubkey,
+                accounts: &mut &[anchor_lang::solana_program::account_info::AccountInfo<'info>],
+                ix_data: &[u8],
+                __bumps: &mut std::collections::BTreeMap<String, u8>,
+                __reallocs: &mut std::collections::BTreeSet<anchor_lang::solana_program::pubkey::Pubkey>,
+            ) -> anchor_lang::Result<Self> {
+                #body
+            }
+        },
+    };
+
+    quote! {
+        #[automatically_derived]
+        impl<#combined_generics> anchor_lang::Accounts<#trait_generics> for #name<#struct_generics> #where_clause {
+            #try_accounts
         }
     }
 }
@@ -171,9 +216,110 @@ pub fn generate_accounts_instance(accs: &AccountsStruct) -> proc_macro2::TokenSt
     }
 }
 
+// In debug mode, warns when a writable orphan account field holds the same
+// account as a writable field of a type that is also serialized on exit, as
+// whichever exits last overwrites the other.
+fn generate_alias_warnings(accs: &AccountsStruct) -> proc_macro2::TokenStream {
+    let writable_keys = |orphan: bool| -> Vec<proc_macro2::TokenStream> {
+        accs.fields
+            .iter()
+            .filter_map(|af| match af {
+                AccountField::Field(f) if f.constraints.is_mutable() => Some(f),
+                _ => None,
+            })
+            .filter(|f| match &f.ty {
+                Ty::OrphanAccount(_) => orphan,
+                Ty::Account(_) | Ty::AccountLoader(_) | Ty::Loader(_) | Ty::ProgramAccount(_) => {
+                    !orphan
+                }
+                _ => false,
+            })
+            .map(|f| {
+                let name = &f.ident;
+                let key = match f.is_optional {
+                    true => quote! { #name.as_ref().map(anchor_lang::Key::key) },
+                    false => quote! { Some(anchor_lang::Key::key(&#name)) },
+                };
+                quote! { (stringify!(#name), #key) }
+            })
+            .collect()
+    };
+    let orphans = writable_keys(true);
+    let accounts = writable_keys(false);
+    if orphans.is_empty() || accounts.is_empty() {
+        return quote! {};
+    }
+    quote! {
+        #[cfg(feature = "anchor-debug")]
+        {
+            let __orphans = [#(#orphans),*];
+            let __accounts = [#(#accounts),*];
+            for (__orphan, __orphan_key) in __orphans.iter() {
+                for (__account, __account_key) in __accounts.iter() {
+                    if let Some(__key) = __orphan_key.filter(|key| Some(*key) == *__account_key) {
+                        anchor_lang::solana_program::msg!(
+                            "Warning: writable fields {} and {} alias account {}",
+                            __orphan,
+                            __account,
+                            __key
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
 fn is_init(af: &AccountField) -> bool {
     match af {
         AccountField::CompositeField(_s) => false,
         AccountField::Field(f) => f.constraints.init.is_some(),
     }
 }
+
+// With `#[memoize_orphans]`, wraps the deserialization of an orphan account
+// to reuse the value of an earlier field of the same type and key.
+fn generate_memoized(
+    accs: &AccountsStruct,
+    idx: usize,
+    f: &Field,
+    deser: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if !accs.memoize || !matches!(f.ty, Ty::OrphanAccount(_)) || f.is_optional {
+        return deser;
+    }
+    let ty_decl = f.ty_decl().to_string();
+    let earlier: Vec<&syn::Ident> = accs.fields[..idx]
+        .iter()
+        .filter_map(|af| match af {
+            AccountField::Field(e)
+                if matches!(e.ty, Ty::OrphanAccount(_))
+                    && !e.is_optional
+                    && !is_init(af)
+                    && e.constraints.zeroed.is_none()
+                    && e.ty_decl().to_string() == ty_decl =>
+            {
+                Some(&e.ident)
+            }
+            _ => None,
+        })
+        .collect();
+    if earlier.is_empty() {
+        return deser;
+    }
+    quote! {
+        if let Some(__memoized) = accounts.first().and_then(|__info| {
+            #(
+                if *__info.key == anchor_lang::Key::key(&#earlier) {
+                    return Some(#earlier.clone());
+                }
+            )*
+            None
+        }) {
+            *accounts = &accounts[1..];
+            __memoized
+        } else {
+            #deser
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{AccountField, AccountsStruct, Ty};
+use quote::quote;
+
+// Generates the `writable_orphan_keys` method.
+pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
+    let name = &accs.ident;
+
+    let insert_keys: Vec<proc_macro2::TokenStream> = accs
+        .fields
+        .iter()
+        .filter_map(|af: &AccountField| match af {
+            AccountField::CompositeField(s) => {
+                let name = &s.ident;
+                Some(quote! {
+                    keys.extend(self.#name.writable_orphan_keys());
+                })
+            }
+            AccountField::Field(f) => match (&f.ty, f.constraints.is_mutable()) {
+                (Ty::OrphanAccount(_), true) if f.is_optional => {
+                    let name = &f.ident;
+                    Some(quote! {
+                        if let Some(account) = &self.#name {
+                            keys.insert(anchor_lang::Key::key(account));
+                        }
+                    })
+                }
+                (Ty::OrphanAccount(_), true) => {
+                    let name = &f.ident;
+                    Some(quote! {
+                        keys.insert(anchor_lang::Key::key(&self.#name));
+                    })
+                }
+                _ => None,
+            },
+        })
+        .collect();
+
+    let (impl_gen, ty_gen, where_clause) = accs.generics.split_for_impl();
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_gen #name #ty_gen #where_clause {
+            /// Returns the keys of all `mut` `OrphanAccount` fields, including
+            /// those of nested accounts structs.
+            #[allow(unused_mut)]
+            pub fn writable_orphan_keys(&self) -> std::collections::BTreeSet<anchor_lang::solana_program::pubkey::Pubkey> {
+                let mut keys = std::collections::BTreeSet::new();
+
+                #(#insert_keys)*
+
+                keys
+            }
+        }
+    }
+}
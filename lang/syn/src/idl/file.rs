@@ -1,6 +1,6 @@
 use crate::idl::*;
 use crate::parser::context::CrateContext;
-use crate::parser::{self, accounts, docs, error, program};
+use crate::parser::{self, account_metadata, accounts, docs, error, program};
 use crate::Ty;
 use crate::{AccountField, AccountsStruct, StateIx};
 use anyhow::Result;
@@ -167,6 +167,7 @@ pub fn parse(
                         name: state.name,
                         docs: None,
                         ty: IdlTypeDefinitionTy::Struct { fields },
+                        metadata: None,
                     }
                 };
 
@@ -264,6 +265,19 @@ pub fn parse(
         .iter()
         .map(|a| a.ident.to_string())
         .collect::<HashSet<_>>();
+    let account_metadata: HashMap<String, IdlAccountMetadata> = account_structs
+        .iter()
+        .filter_map(|a| {
+            let metadata = account_metadata::parse(a);
+            (!metadata.is_empty()).then(|| {
+                let metadata = IdlAccountMetadata {
+                    label: metadata.label,
+                    category: metadata.category,
+                };
+                (a.ident.to_string(), metadata)
+            })
+        })
+        .collect();
 
     let error_name = error.map(|e| e.name).unwrap_or_else(|| "".to_string());
 
@@ -272,6 +286,8 @@ pub fn parse(
         // Don't add the error type to the types or accounts sections.
         if ty_def.name != error_name {
             if account_names.contains(&ty_def.name) {
+                let mut ty_def = ty_def;
+                ty_def.metadata = account_metadata.get(&ty_def.name).cloned();
                 accounts.push(ty_def);
             } else if !events.iter().any(|e| e.name == ty_def.name) {
                 types.push(ty_def);
@@ -482,6 +498,7 @@ fn parse_ty_defs(ctx: &CrateContext, no_docs: bool) -> Result<Vec<IdlTypeDefinit
                 name,
                 docs: doc,
                 ty: IdlTypeDefinitionTy::Struct { fields },
+                metadata: None,
             }))
         })
         .chain(ctx.enums().map(|enm| {
@@ -535,6 +552,7 @@ fn parse_ty_defs(ctx: &CrateContext, no_docs: bool) -> Result<Vec<IdlTypeDefinit
                 name,
                 docs: doc,
                 ty: IdlTypeDefinitionTy::Enum { variants },
+                metadata: None,
             })
         }))
         .collect()
@@ -643,6 +661,7 @@ fn idl_accounts(
                     Ty::Signer => true,
                     _ => acc.constraints.is_signer(),
                 },
+                is_optional: if acc.is_optional { Some(true) } else { None },
                 docs: if !no_docs { acc.docs.clone() } else { None },
                 pda: pda::parse(ctx, accounts, acc, seeds_feature),
             }),
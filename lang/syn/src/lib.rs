@@ -119,6 +119,17 @@ pub struct AccountsStruct {
     pub fields: Vec<AccountField>,
     // Instruction data api expression.
     instruction_api: Option<Punctuated<Expr, Comma>>,
+    // True if leftover accounts are rejected via `#[strict_accounts]`.
+    pub strict: bool,
+    // True if orphan accounts are deserialized once per key and type via
+    // `#[memoize_orphans]`.
+    pub memoize: bool,
+    // True if a `snapshot_orphans` method is generated via
+    // `#[snapshot_orphans]`.
+    pub snapshot: bool,
+    // Maximum size in bytes of the account type of each orphan account field,
+    // via `#[orphan_stack_budget(..)]`.
+    pub stack_budget: Option<LitInt>,
 }
 
 impl Parse for AccountsStruct {
@@ -145,14 +156,31 @@ impl AccountsStruct {
         strct: ItemStruct,
         fields: Vec<AccountField>,
         instruction_api: Option<Punctuated<Expr, Comma>>,
+        stack_budget: Option<LitInt>,
     ) -> Self {
         let ident = strct.ident.clone();
+        let strict = strct
+            .attrs
+            .iter()
+            .any(|a| a.path.is_ident("strict_accounts"));
+        let memoize = strct
+            .attrs
+            .iter()
+            .any(|a| a.path.is_ident("memoize_orphans"));
+        let snapshot = strct
+            .attrs
+            .iter()
+            .any(|a| a.path.is_ident("snapshot_orphans"));
         let generics = strct.generics;
         Self {
             ident,
             generics,
             fields,
             instruction_api,
+            strict,
+            memoize,
+            snapshot,
+            stack_budget,
         }
     }
 
@@ -200,6 +228,9 @@ impl AccountField {
         match self {
             AccountField::Field(field) => match &field.ty {
                 Ty::Account(account) => Some(parser::tts_to_string(&account.account_type_path)),
+                Ty::OrphanAccount(account) => {
+                    Some(parser::tts_to_string(&account.account_type_path))
+                }
                 Ty::ProgramAccount(account) => {
                     Some(parser::tts_to_string(&account.account_type_path))
                 }
@@ -215,6 +246,9 @@ pub struct Field {
     pub ident: Ident,
     pub constraints: ConstraintGroup,
     pub ty: Ty,
+    /// True if the field is an `Option`, absent when the program id is
+    /// given in place of the account.
+    pub is_optional: bool,
     /// IDL Doc comment
     pub docs: Option<Vec<String>>,
 }
@@ -223,8 +257,13 @@ impl Field {
     pub fn typed_ident(&self) -> proc_macro2::TokenStream {
         let name = &self.ident;
         let ty_decl = self.ty_decl();
-        quote! {
-            #name: #ty_decl
+        match self.is_optional {
+            false => quote! {
+                #name: #ty_decl
+            },
+            true => quote! {
+                #name: Option<#ty_decl>
+            },
         }
     }
 
@@ -258,6 +297,12 @@ impl Field {
                     }
                 }
             }
+            Ty::OrphanAccount(OrphanAccountTy {
+                codec_type_path: Some(codec),
+                ..
+            }) => quote! {
+                #container_ty<#account_ty, #codec>
+            },
             Ty::Sysvar(ty) => {
                 let account = match ty {
                     SysvarTy::Clock => quote! {Clock},
@@ -327,6 +372,21 @@ impl Field {
                     stream
                 }
             }
+            Ty::OrphanAccount(_) => {
+                if checked {
+                    quote! {
+                        #container_ty::try_from(
+                            &#field,
+                        ).map_err(|e| e.with_account_name(#field_str))?
+                    }
+                } else {
+                    quote! {
+                        #container_ty::try_from_unchecked(
+                            &#field,
+                        ).map_err(|e| e.with_account_name(#field_str))?
+                    }
+                }
+            }
             Ty::CpiAccount(_) => {
                 if checked {
                     quote! {
@@ -389,6 +449,9 @@ impl Field {
             Ty::AccountLoader(_) => quote! {
                 anchor_lang::accounts::account_loader::AccountLoader
             },
+            Ty::OrphanAccount(_) => quote! {
+                anchor_lang::accounts::orphan_account::OrphanAccount
+            },
             Ty::Loader(_) => quote! {
                 anchor_lang::accounts::loader::Loader
             },
@@ -443,6 +506,12 @@ impl Field {
                     #ident
                 }
             }
+            Ty::OrphanAccount(ty) => {
+                let ident = &ty.account_type_path;
+                quote! {
+                    #ident
+                }
+            }
             Ty::Loader(ty) => {
                 let ident = &ty.account_type_path;
                 quote! {
@@ -512,6 +581,7 @@ pub enum Ty {
     CpiAccount(CpiAccountTy),
     Sysvar(SysvarTy),
     Account(AccountTy),
+    OrphanAccount(OrphanAccountTy),
     Program(ProgramTy),
     Signer,
     SystemAccount,
@@ -574,6 +644,14 @@ pub struct AccountTy {
     pub boxed: bool,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct OrphanAccountTy {
+    // The struct type of the account.
+    pub account_type_path: TypePath,
+    // The codec used to (de)serialize the account, if not the default.
+    pub codec_type_path: Option<TypePath>,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ProgramTy {
     // The struct type of the account.
@@ -626,6 +704,8 @@ pub struct ConstraintGroup {
     rent_exempt: Option<ConstraintRentExempt>,
     seeds: Option<ConstraintSeedsGroup>,
     executable: Option<ConstraintExecutable>,
+    deny_self_owner: Option<ConstraintDenySelfOwner>,
+    required_if: Option<ConstraintRequiredIf>,
     state: Option<ConstraintState>,
     has_one: Vec<ConstraintHasOne>,
     literal: Vec<ConstraintLiteral>,
@@ -674,6 +754,8 @@ pub enum Constraint {
     Seeds(ConstraintSeedsGroup),
     AssociatedToken(ConstraintAssociatedToken),
     Executable(ConstraintExecutable),
+    DenySelfOwner(ConstraintDenySelfOwner),
+    RequiredIf(ConstraintRequiredIf),
     State(ConstraintState),
     Close(ConstraintClose),
     Address(ConstraintAddress),
@@ -697,6 +779,8 @@ pub enum ConstraintToken {
     RentExempt(Context<ConstraintRentExempt>),
     Seeds(Context<ConstraintSeeds>),
     Executable(Context<ConstraintExecutable>),
+    DenySelfOwner(Context<ConstraintDenySelfOwner>),
+    RequiredIf(Context<ConstraintRequiredIf>),
     State(Context<ConstraintState>),
     Close(Context<ConstraintClose>),
     Payer(Context<ConstraintPayer>),
@@ -823,7 +907,18 @@ pub struct ConstraintSeeds {
 }
 
 #[derive(Debug, Clone)]
-pub struct ConstraintExecutable {}
+pub struct ConstraintExecutable {
+    // False if the account must *not* be executable, via `executable = false`.
+    pub executable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstraintDenySelfOwner {}
+
+#[derive(Debug, Clone)]
+pub struct ConstraintRequiredIf {
+    pub condition: Expr,
+}
 
 #[derive(Debug, Clone)]
 pub struct ConstraintState {
@@ -0,0 +1,56 @@
+use proc_macro2::{TokenStream, TokenTree};
+
+/// The `label` and `category` arguments of `#[account(..)]`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountMetadata {
+    pub label: Option<String>,
+    pub category: Option<String>,
+}
+
+impl AccountMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.label.is_none() && self.category.is_none()
+    }
+}
+
+// Removes the `label = ".."` and `category = ".."` arguments, if any, from the
+// `#[account(..)]` args, returning the remaining args and the given values.
+pub fn split(args: TokenStream) -> (TokenStream, AccountMetadata) {
+    let mut rest = Vec::new();
+    let mut metadata = AccountMetadata::default();
+    let mut tokens = args.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let slot = match &token {
+            TokenTree::Ident(ident) if ident == "label" => &mut metadata.label,
+            TokenTree::Ident(ident) if ident == "category" => &mut metadata.category,
+            _ => {
+                rest.push(token);
+                continue;
+            }
+        };
+        if !matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '=') {
+            rest.push(token);
+            continue;
+        }
+        tokens.next();
+        let value: syn::LitStr = tokens
+            .next()
+            .and_then(|t| syn::parse2(t.into()).ok())
+            .unwrap_or_else(|| panic!("{} must be a string literal", token));
+        *slot = Some(value.value());
+    }
+    (rest.into_iter().collect(), metadata)
+}
+
+/// Returns the metadata given to the `#[account(..)]` attribute of `strct`.
+pub fn parse(strct: &syn::ItemStruct) -> AccountMetadata {
+    strct
+        .attrs
+        .iter()
+        .find(|attr| attr.path.segments.last().unwrap().ident == "account")
+        .and_then(|attr| match attr.tokens.clone().into_iter().next() {
+            Some(TokenTree::Group(group)) => Some(split(group.stream()).1),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
@@ -4,7 +4,7 @@ use syn::parse::{Error as ParseError, Parse, ParseStream, Result as ParseResult}
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Comma;
-use syn::{bracketed, Expr, Ident, LitStr, Token};
+use syn::{bracketed, Expr, Ident, LitBool, LitStr, Token};
 
 pub fn parse(f: &syn::Field, f_ty: Option<&Ty>) -> ParseResult<ConstraintGroup> {
     let mut constraints = ConstraintGroupBuilder::new(f_ty);
@@ -59,7 +59,21 @@ pub fn parse_token(stream: ParseStream) -> ParseResult<ConstraintToken> {
             },
         )),
         "executable" => {
-            ConstraintToken::Executable(Context::new(ident.span(), ConstraintExecutable {}))
+            let executable = {
+                if stream.peek(Token![=]) {
+                    stream.parse::<Token![=]>()?;
+                    stream.parse::<LitBool>()?.value
+                } else {
+                    true
+                }
+            };
+            ConstraintToken::Executable(Context::new(
+                ident.span(),
+                ConstraintExecutable { executable },
+            ))
+        }
+        "deny_self_owner" => {
+            ConstraintToken::DenySelfOwner(Context::new(ident.span(), ConstraintDenySelfOwner {}))
         }
         "mint" => {
             stream.parse::<Token![:]>()?;
@@ -271,6 +285,12 @@ pub fn parse_token(stream: ParseStream) -> ParseResult<ConstraintToken> {
                         }
                     },
                 )),
+                "required_if" => ConstraintToken::RequiredIf(Context::new(
+                    span,
+                    ConstraintRequiredIf {
+                        condition: stream.parse()?,
+                    },
+                )),
                 "state" => ConstraintToken::State(Context::new(
                     span,
                     ConstraintState {
@@ -340,6 +360,8 @@ pub struct ConstraintGroupBuilder<'ty> {
     pub rent_exempt: Option<Context<ConstraintRentExempt>>,
     pub seeds: Option<Context<ConstraintSeeds>>,
     pub executable: Option<Context<ConstraintExecutable>>,
+    pub deny_self_owner: Option<Context<ConstraintDenySelfOwner>>,
+    pub required_if: Option<Context<ConstraintRequiredIf>>,
     pub state: Option<Context<ConstraintState>>,
     pub payer: Option<Context<ConstraintPayer>>,
     pub space: Option<Context<ConstraintSpace>>,
@@ -374,6 +396,8 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             rent_exempt: None,
             seeds: None,
             executable: None,
+            deny_self_owner: None,
+            required_if: None,
             state: None,
             payer: None,
             space: None,
@@ -575,6 +599,8 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             rent_exempt,
             seeds,
             executable,
+            deny_self_owner,
+            required_if,
             state,
             payer,
             space,
@@ -724,6 +750,8 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             owner: into_inner!(owner),
             rent_exempt: into_inner!(rent_exempt),
             executable: into_inner!(executable),
+            deny_self_owner: into_inner!(deny_self_owner),
+            required_if: into_inner!(required_if),
             state: into_inner!(state),
             close: into_inner!(close),
             address: into_inner!(address),
@@ -747,6 +775,8 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             ConstraintToken::RentExempt(c) => self.add_rent_exempt(c),
             ConstraintToken::Seeds(c) => self.add_seeds(c),
             ConstraintToken::Executable(c) => self.add_executable(c),
+            ConstraintToken::DenySelfOwner(c) => self.add_deny_self_owner(c),
+            ConstraintToken::RequiredIf(c) => self.add_required_if(c),
             ConstraintToken::State(c) => self.add_state(c),
             ConstraintToken::Payer(c) => self.add_payer(c),
             ConstraintToken::Space(c) => self.add_space(c),
@@ -1115,6 +1145,37 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
         Ok(())
     }
 
+    fn add_deny_self_owner(&mut self, c: Context<ConstraintDenySelfOwner>) -> ParseResult<()> {
+        if !matches!(self.f_ty, Some(Ty::OrphanAccount(_))) {
+            return Err(ParseError::new(
+                c.span(),
+                "deny_self_owner must be on an OrphanAccount",
+            ));
+        }
+        if self.deny_self_owner.is_some() {
+            return Err(ParseError::new(
+                c.span(),
+                "deny_self_owner already provided",
+            ));
+        }
+        self.deny_self_owner.replace(c);
+        Ok(())
+    }
+
+    fn add_required_if(&mut self, c: Context<ConstraintRequiredIf>) -> ParseResult<()> {
+        if !matches!(self.f_ty, Some(Ty::OrphanAccount(_))) {
+            return Err(ParseError::new(
+                c.span(),
+                "required_if must be on an Option<OrphanAccount>",
+            ));
+        }
+        if self.required_if.is_some() {
+            return Err(ParseError::new(c.span(), "required_if already provided"));
+        }
+        self.required_if.replace(c);
+        Ok(())
+    }
+
     fn add_state(&mut self, c: Context<ConstraintState>) -> ParseResult<()> {
         if self.state.is_some() {
             return Err(ParseError::new(c.span(), "state already provided"));
@@ -4,7 +4,7 @@ use syn::parse::{Error as ParseError, Result as ParseResult};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Comma;
-use syn::Expr;
+use syn::{Expr, LitInt};
 
 pub mod constraints;
 
@@ -19,6 +19,21 @@ pub fn parse(strct: &syn::ItemStruct) -> ParseResult<AccountsStruct> {
         })
         .map(|ix_attr| ix_attr.parse_args_with(Punctuated::<Expr, Comma>::parse_terminated))
         .transpose()?;
+    let stack_budget = strct
+        .attrs
+        .iter()
+        .find(|a| a.path.is_ident("orphan_stack_budget"))
+        .map(|a| {
+            a.parse_args::<LitInt>()
+                .and_then(|budget| budget.base10_parse::<usize>().map(|_| budget))
+                .map_err(|_| {
+                    ParseError::new_spanned(
+                        a,
+                        "orphan_stack_budget takes a byte count, e.g. #[orphan_stack_budget(4096)]",
+                    )
+                })
+        })
+        .transpose()?;
     let fields = match &strct.fields {
         syn::Fields::Named(fields) => fields
             .named
@@ -35,7 +50,12 @@ pub fn parse(strct: &syn::ItemStruct) -> ParseResult<AccountsStruct> {
 
     let _ = constraints_cross_checks(&fields)?;
 
-    Ok(AccountsStruct::new(strct.clone(), fields, instruction_api))
+    Ok(AccountsStruct::new(
+        strct.clone(),
+        fields,
+        instruction_api,
+        stack_budget,
+    ))
 }
 
 fn constraints_cross_checks(fields: &[AccountField]) -> ParseResult<()> {
@@ -202,14 +222,24 @@ fn constraints_cross_checks(fields: &[AccountField]) -> ParseResult<()> {
 pub fn parse_account_field(f: &syn::Field) -> ParseResult<AccountField> {
     let ident = f.ident.clone().unwrap();
     let docs = docs::parse(&f.attrs);
+    if let Some(inner) = option_inner_field(f) {
+        return parse_optional_account_field(&inner, ident, docs);
+    }
     let account_field = match is_field_primitive(f)? {
         true => {
             let ty = parse_ty(f)?;
             let account_constraints = constraints::parse(f, Some(&ty))?;
+            if let Some(c) = &account_constraints.required_if {
+                return Err(ParseError::new(
+                    c.condition.span(),
+                    "required_if must be on an Option<OrphanAccount>",
+                ));
+            }
             AccountField::Field(Field {
                 ident,
                 ty,
                 constraints: account_constraints,
+                is_optional: false,
                 docs,
             })
         }
@@ -227,6 +257,62 @@ pub fn parse_account_field(f: &syn::Field) -> ParseResult<AccountField> {
     Ok(account_field)
 }
 
+// Returns a copy of the field with the type unwrapped, if it is an `Option`.
+fn option_inner_field(f: &syn::Field) -> Option<syn::Field> {
+    let path = match &f.ty {
+        syn::Type::Path(ty_path) if ty_path.path.segments.len() == 1 => &ty_path.path,
+        _ => return None,
+    };
+    let segment = &path.segments[0];
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(ty) => Some(syn::Field {
+                ty: ty.clone(),
+                ..f.clone()
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Optional accounts are limited to `OrphanAccount`s, which can't be created,
+// reallocated or closed by the constraints.
+fn parse_optional_account_field(
+    f: &syn::Field,
+    ident: Ident,
+    docs: Option<Vec<String>>,
+) -> ParseResult<AccountField> {
+    let ty = parse_ty(f)?;
+    if !matches!(ty, Ty::OrphanAccount(_)) {
+        return Err(ParseError::new(
+            f.ty.span(),
+            "only OrphanAccount fields can be optional",
+        ));
+    }
+    let account_constraints = constraints::parse(f, Some(&ty))?;
+    if account_constraints.init.is_some()
+        || account_constraints.zeroed.is_some()
+        || account_constraints.realloc.is_some()
+        || account_constraints.close.is_some()
+    {
+        return Err(ParseError::new(
+            ident.span(),
+            "init, zero, realloc and close are not supported on optional accounts",
+        ));
+    }
+    Ok(AccountField::Field(Field {
+        ident,
+        ty,
+        constraints: account_constraints,
+        is_optional: true,
+        docs,
+    }))
+}
+
 fn is_field_primitive(f: &syn::Field) -> ParseResult<bool> {
     let r = matches!(
         ident_string(f)?.as_str(),
@@ -240,6 +326,7 @@ fn is_field_primitive(f: &syn::Field) -> ParseResult<bool> {
             | "Loader"
             | "AccountLoader"
             | "Account"
+            | "OrphanAccount"
             | "Program"
             | "Signer"
             | "SystemAccount"
@@ -264,6 +351,7 @@ fn parse_ty(f: &syn::Field) -> ParseResult<Ty> {
         "Loader" => Ty::Loader(parse_program_account_zero_copy(&path)?),
         "AccountLoader" => Ty::AccountLoader(parse_program_account_loader(&path)?),
         "Account" => Ty::Account(parse_account_ty(&path)?),
+        "OrphanAccount" => Ty::OrphanAccount(parse_orphan_account_ty(&path)?),
         "Program" => Ty::Program(parse_program_ty(&path)?),
         "Signer" => Ty::Signer,
         "SystemAccount" => Ty::SystemAccount,
@@ -349,6 +437,35 @@ fn parse_account_ty(path: &syn::Path) -> ParseResult<AccountTy> {
     })
 }
 
+fn parse_orphan_account_ty(path: &syn::Path) -> ParseResult<OrphanAccountTy> {
+    let segments = &path.segments[0];
+    match &segments.arguments {
+        syn::PathArguments::AngleBracketed(args) => {
+            // Expected: <'info, MyType> or <'info, MyType, MyCodec>.
+            if args.args.len() != 2 && args.args.len() != 3 {
+                return Err(ParseError::new(
+                    args.args.span(),
+                    "bracket arguments must be the lifetime, type, and optional codec",
+                ));
+            }
+            let mut type_paths = args.args.iter().skip(1).map(|arg| match arg {
+                syn::GenericArgument::Type(syn::Type::Path(ty_path)) => Ok(ty_path.clone()),
+                _ => Err(ParseError::new(arg.span(), "expected a type")),
+            });
+            let account_type_path = type_paths.next().unwrap()?;
+            let codec_type_path = type_paths.next().transpose()?;
+            Ok(OrphanAccountTy {
+                account_type_path,
+                codec_type_path,
+            })
+        }
+        _ => Err(ParseError::new(
+            segments.arguments.span(),
+            "expected angle brackets with a lifetime, type, and optional codec",
+        )),
+    }
+}
+
 fn parse_program_ty(path: &syn::Path) -> ParseResult<ProgramTy> {
     let account_type_path = parse_account(path)?;
     Ok(ProgramTy { account_type_path })
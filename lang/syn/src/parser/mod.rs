@@ -1,3 +1,4 @@
+pub mod account_metadata;
 pub mod accounts;
 pub mod context;
 pub mod docs;
@@ -0,0 +1,88 @@
+//! Kept apart from the other tests since it counts the allocations of the
+//! whole test binary.
+
+use anchor_lang::accounts::cow_orphan::{BorrowedView, CowOrphan};
+use anchor_lang::error::ErrorCode;
+use anchor_lang::Result;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const DISCRIMINATOR: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+#[derive(Debug, PartialEq)]
+struct Profile {
+    name: String,
+}
+
+// Borrows the borsh encoded name that follows the discriminator.
+struct ProfileView<'a> {
+    name: &'a str,
+}
+
+impl<'a> BorrowedView<'a> for ProfileView<'a> {
+    type Owned = Profile;
+
+    fn view(data: &'a [u8]) -> Result<Self> {
+        if data.get(..8) != Some(&DISCRIMINATOR[..]) {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        let len = data
+            .get(8..12)
+            .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+            .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+        let name = data
+            .get(12..12 + len)
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+        Ok(ProfileView { name })
+    }
+
+    fn to_owned_value(&self) -> Profile {
+        Profile {
+            name: self.name.to_string(),
+        }
+    }
+}
+
+#[test]
+fn test_borrowed_read_does_not_allocate() {
+    let mut data = DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(b"alice");
+
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let mut profile = CowOrphan::<ProfileView>::try_from(&data).unwrap();
+    let name = match &profile {
+        CowOrphan::Borrowed(view) => view.name,
+        CowOrphan::Owned(_) => panic!("expected a borrowed view"),
+    };
+    assert_eq!(name, "alice");
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), before);
+
+    profile.to_mut().name.push('!');
+    assert!(!profile.is_borrowed());
+    assert_eq!(
+        profile.into_owned(),
+        Profile {
+            name: "alice!".to_string()
+        }
+    );
+}
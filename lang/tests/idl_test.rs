@@ -0,0 +1,203 @@
+#![cfg(feature = "client")]
+
+use anchor_lang::accounts::orphan_account::OrphanAccount;
+use anchor_lang::error::ErrorCode;
+use anchor_lang::idl::{get_field, validate_against_idl};
+use anchor_lang::prelude::*;
+use anchor_syn::idl::{Idl, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy};
+use serde_json::json;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Fee {
+    pub bps: u16,
+}
+
+#[account]
+pub struct Pool {
+    pub authority: Pubkey,
+    pub fees: Vec<Fee>,
+    pub name: String,
+    pub cap: Option<u64>,
+}
+
+fn field(name: &str, ty: IdlType) -> IdlField {
+    IdlField {
+        name: name.to_string(),
+        docs: None,
+        ty,
+    }
+}
+
+fn definition(name: &str, fields: Vec<IdlField>) -> IdlTypeDefinition {
+    IdlTypeDefinition {
+        name: name.to_string(),
+        docs: None,
+        ty: IdlTypeDefinitionTy::Struct { fields },
+        metadata: None,
+    }
+}
+
+fn pool_idl() -> (IdlTypeDefinition, Vec<IdlTypeDefinition>) {
+    let pool = definition(
+        "Pool",
+        vec![
+            field("authority", IdlType::PublicKey),
+            field(
+                "fees",
+                IdlType::Vec(Box::new(IdlType::Defined("Fee".to_string()))),
+            ),
+            field("name", IdlType::String),
+            field("cap", IdlType::Option(Box::new(IdlType::U64))),
+        ],
+    );
+    let fee = definition("Fee", vec![field("bps", IdlType::U16)]);
+    (pool, vec![fee])
+}
+
+fn pool_data() -> Vec<u8> {
+    let mut data = Vec::new();
+    Pool {
+        authority: Pubkey::new_unique(),
+        fees: vec![Fee { bps: 30 }, Fee { bps: 5 }],
+        name: "pool".to_string(),
+        cap: Some(100),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data
+}
+
+#[test]
+fn test_validate_against_idl() {
+    let (pool, types) = pool_idl();
+    let mut data = pool_data();
+    validate_against_idl(&data, &pool, &types).unwrap();
+
+    // Padding after the account is allowed.
+    data.resize(data.len() + 16, 0);
+    validate_against_idl(&data, &pool, &types).unwrap();
+}
+
+#[test]
+fn test_validate_against_idl_drifted() {
+    let (pool, types) = pool_idl();
+    let data = pool_data();
+
+    // The account has fewer bytes than the layout requires.
+    assert_eq!(
+        validate_against_idl(&data[..data.len() - 4], &pool, &types).unwrap_err(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+
+    // The external program widened a field.
+    let mut widened = pool.clone();
+    if let IdlTypeDefinitionTy::Struct { fields } = &mut widened.ty {
+        fields[3] = field("cap", IdlType::Option(Box::new(IdlType::U128)));
+    }
+    assert_eq!(
+        validate_against_idl(&data, &widened, &types).unwrap_err(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+
+    // An invalid option tag.
+    let mut tagged = data.clone();
+    let cap = tagged.len() - 9;
+    tagged[cap] = 2;
+    assert_eq!(
+        validate_against_idl(&tagged, &pool, &types).unwrap_err(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+
+    // A referenced type missing from the IDL.
+    assert_eq!(
+        validate_against_idl(&data, &pool, &[]).unwrap_err(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+
+    let mut renamed = pool;
+    renamed.name = "Vault".to_string();
+    assert_eq!(
+        validate_against_idl(&data, &renamed, &types).unwrap_err(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+}
+
+fn program_idl() -> Idl {
+    let (pool, types) = pool_idl();
+    Idl {
+        version: "0.1.0".to_string(),
+        name: "pools".to_string(),
+        docs: None,
+        constants: Vec::new(),
+        instructions: Vec::new(),
+        state: None,
+        accounts: vec![pool],
+        types,
+        events: None,
+        errors: None,
+        metadata: None,
+    }
+}
+
+#[test]
+fn test_assert_idl_account() {
+    let idl = program_idl();
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = pool_data();
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    let account = OrphanAccount::<Pool>::try_from(&info).unwrap();
+    account.assert_idl_account(&idl, "Pool").unwrap();
+
+    // `Fee` is a type of the IDL, but not an account.
+    for name in ["Fee", "Vault"] {
+        assert_eq!(
+            account.assert_idl_account(&idl, name).unwrap_err(),
+            ErrorCode::AccountDiscriminatorMismatch.into()
+        );
+    }
+}
+
+#[test]
+fn test_get_field() {
+    let idl = program_idl();
+    let data = pool_data();
+
+    assert_eq!(
+        get_field(&data, &idl, "Pool", "fees.1.bps").unwrap(),
+        json!(5)
+    );
+    assert_eq!(get_field(&data, &idl, "Pool", "cap").unwrap(), json!(100));
+    assert_eq!(
+        get_field(&data, &idl, "Pool", "fees").unwrap(),
+        json!([{ "bps": 30 }, { "bps": 5 }])
+    );
+    let authority = Pubkey::try_from_slice(&data[8..40]).unwrap();
+    assert_eq!(
+        get_field(&data, &idl, "Pool", "").unwrap()["authority"],
+        json!(authority.to_string())
+    );
+
+    for path in ["fees.2.bps", "fees.bps", "name.len"] {
+        assert_eq!(
+            get_field(&data, &idl, "Pool", path).unwrap_err(),
+            ErrorCode::AccountFieldNotFound.into()
+        );
+    }
+    assert_eq!(
+        get_field(&data, &idl, "Fee", "bps").unwrap_err(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+}
@@ -0,0 +1,4600 @@
+#![allow(dead_code)]
+
+use anchor_lang::accounts::cached_orphan::CachedOrphan;
+use anchor_lang::accounts::dynamic_orphan::{DynamicDeserializer, DynamicOrphan};
+use anchor_lang::accounts::orphan_account::{
+    self, assert_order, exit_all, register_discriminator, registered_type, reload_all,
+    reload_and_diff, swap_contents, validate_owners, Bare, BloomFilter, ChangedRange, Checksummed,
+    Codec, Compressed, Compression, ConstLen, ConstSerializedLen, ContextualDeserialize,
+    DebugFields, Diffable, FieldChange, HasAuthority, HasFlags, HasReferences, LamportLedger,
+    LamportSnapshot, LogicalLen, Migrate, NoDiscriminator, OrphanEvent, OrphanMutated,
+    OrphanTransaction, Padded, Partial, PodCopy, StreamSerialize, Streamed, SupportedVersions,
+    Tagged, Truncate, WriteSelector, DEFAULT_MAX_DEPTH,
+};
+use anchor_lang::error::{AnchorError, ComparedValues, ErrorCode, ErrorOrigin};
+use anchor_lang::prelude::*;
+use anchor_lang::{AccountMetadata, Discriminator, Event};
+use solana_program::clock::Epoch;
+use solana_program::rent::ACCOUNT_STORAGE_OVERHEAD;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Index, IndexMut, Range};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[account]
+#[derive(Default)]
+pub struct Shared {
+    pub value: u64,
+}
+
+#[derive(Accounts)]
+pub struct Mixed<'info> {
+    #[account(mut)]
+    pub writable: OrphanAccount<'info, Shared>,
+    pub readonly: OrphanAccount<'info, Shared>,
+    #[account(mut)]
+    pub unchecked: UncheckedAccount<'info>,
+    pub nested: Nested<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Nested<'info> {
+    #[account(mut)]
+    pub writable: OrphanAccount<'info, Shared>,
+}
+
+#[derive(Accounts)]
+pub struct Outer<'info> {
+    #[account(mut)]
+    pub top: OrphanAccount<'info, Shared>,
+    pub middle: Middle<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Middle<'info> {
+    pub readonly: OrphanAccount<'info, Shared>,
+    pub inner: Nested<'info>,
+}
+
+#[derive(Accounts)]
+pub struct NonExecutable<'info> {
+    #[account(executable = false)]
+    pub shared: OrphanAccount<'info, Shared>,
+}
+
+#[derive(Accounts)]
+pub struct Foreign<'info> {
+    #[account(deny_self_owner)]
+    pub shared: OrphanAccount<'info, Shared>,
+}
+
+#[derive(Accounts)]
+pub struct SignedShared<'info> {
+    #[account(signer)]
+    pub shared: OrphanAccount<'info, Shared>,
+}
+
+#[derive(Accounts)]
+#[strict_accounts]
+pub struct Strict<'info> {
+    pub shared: OrphanAccount<'info, Shared>,
+}
+
+#[derive(Accounts)]
+#[strict_accounts]
+pub struct OuterStrict<'info> {
+    pub inner: Strict<'info>,
+    pub other: OrphanAccount<'info, Shared>,
+}
+
+#[derive(Accounts)]
+pub struct InitShared<'info> {
+    #[account(init, payer = payer, space = 8)]
+    pub shared: OrphanAccount<'info, Shared>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Counts deserializations, to check memoization.
+static DESERIALIZED: AtomicUsize = AtomicUsize::new(0);
+
+pub struct CountingCodec;
+
+impl Codec<Shared> for CountingCodec {
+    fn deserialize(buf: &mut &[u8]) -> Result<Shared> {
+        DESERIALIZED.fetch_add(1, Ordering::SeqCst);
+        Shared::try_deserialize(buf)
+    }
+
+    fn serialize<W: std::io::Write>(value: &Shared, writer: &mut W) -> Result<()> {
+        value.try_serialize(writer)
+    }
+}
+
+impl ConstSerializedLen for Shared {
+    const SERIALIZED_LEN: usize = 8 + 8;
+}
+
+pub struct NoSerializeCodec;
+
+impl Codec<Shared> for NoSerializeCodec {
+    fn deserialize(buf: &mut &[u8]) -> Result<Shared> {
+        Shared::try_deserialize(buf)
+    }
+
+    fn serialize<W: std::io::Write>(_value: &Shared, _writer: &mut W) -> Result<()> {
+        panic!("serialized");
+    }
+}
+
+thread_local! {
+    static FLAKY_FAILURES: RefCell<usize> = RefCell::new(0);
+}
+
+// Fails with a borrow conflict the next `FLAKY_FAILURES` times.
+pub struct FlakyCodec;
+
+impl Codec<Shared> for FlakyCodec {
+    fn deserialize(buf: &mut &[u8]) -> Result<Shared> {
+        let failing = FLAKY_FAILURES.with(|failures| {
+            let mut failures = failures.borrow_mut();
+            let failing = *failures > 0;
+            *failures = failures.saturating_sub(1);
+            failing
+        });
+        if failing {
+            return Err(ProgramError::AccountBorrowFailed.into());
+        }
+        Shared::try_deserialize(buf)
+    }
+
+    fn serialize<W: std::io::Write>(value: &Shared, writer: &mut W) -> Result<()> {
+        value.try_serialize(writer)
+    }
+}
+
+#[derive(Accounts)]
+#[orphan_stack_budget(16)]
+pub struct Budgeted<'info> {
+    pub shared: OrphanAccount<'info, Shared>,
+}
+
+#[derive(Accounts)]
+#[memoize_orphans]
+pub struct Memoized<'info> {
+    pub first: OrphanAccount<'info, Shared, CountingCodec>,
+    pub second: OrphanAccount<'info, Shared, CountingCodec>,
+}
+
+#[derive(Accounts)]
+#[instruction(with_config: bool)]
+pub struct RequiredIf<'info> {
+    #[account(mut, required_if = with_config)]
+    pub config: Option<OrphanAccount<'info, Shared>>,
+    pub unchecked: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OwnedBy<'info> {
+    #[account(owner = registry.key(), has_one = registry)]
+    pub shared: OrphanAccount<'info, Registered>,
+    pub registry: UncheckedAccount<'info>,
+}
+
+#[account]
+pub struct Registered {
+    pub registry: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct Header {
+    pub version: u8,
+    pub authority: Pubkey,
+}
+
+#[account]
+pub struct Large {
+    pub header: Header,
+    pub body: Vec<u64>,
+}
+
+#[account]
+pub struct Authorized {
+    pub authority: Pubkey,
+}
+
+impl HasAuthority for Authorized {
+    fn authority(&self) -> &Pubkey {
+        &self.authority
+    }
+}
+
+fn shared_data(value: u64) -> Vec<u8> {
+    let mut data = Vec::new();
+    Shared { value }.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn account_info<'a>(
+    key: &'a Pubkey,
+    owner: &'a Pubkey,
+    is_writable: bool,
+    executable: bool,
+    lamports: &'a mut u64,
+    data: &'a mut [u8],
+) -> AccountInfo<'a> {
+    AccountInfo::new(
+        key,
+        false,
+        is_writable,
+        lamports,
+        data,
+        owner,
+        executable,
+        Epoch::default(),
+    )
+}
+
+fn try_accounts<'info, T: Accounts<'info>>(infos: &[AccountInfo<'info>]) -> Result<T> {
+    T::try_accounts(
+        &ID,
+        &mut &infos[..],
+        &[],
+        &mut BTreeMap::new(),
+        &mut BTreeSet::new(),
+    )
+}
+
+#[test]
+fn test_non_executable_accepts_data_account() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let infos = [account_info(
+        &key,
+        &owner,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+    )];
+
+    let accounts = try_accounts::<NonExecutable>(&infos).ok().unwrap();
+    assert_eq!(accounts.shared.value, 7);
+}
+
+#[test]
+fn test_non_executable_rejects_executable_account() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let infos = [account_info(
+        &key,
+        &owner,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+    )];
+
+    let err = try_accounts::<NonExecutable>(&infos).err().unwrap();
+    assert_eq!(err, ErrorCode::ConstraintNotExecutable.into());
+}
+
+#[test]
+fn test_writable_orphan_keys() {
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let owner = Pubkey::new_unique();
+    let mut lamports = [1; 4];
+    let mut data: Vec<Vec<u8>> = (0..4).map(shared_data).collect();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &owner, true, false, lamports, data))
+        .collect();
+
+    let accounts = try_accounts::<Mixed>(&infos).ok().unwrap();
+    let writable = accounts.writable_orphan_keys();
+    assert_eq!(writable, BTreeSet::from([keys[0], keys[3]]));
+}
+
+#[test]
+fn test_client_orphan_account_metas() {
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let accounts = __client_accounts_mixed::Mixed {
+        writable: keys[0],
+        readonly: keys[1],
+        unchecked: keys[2],
+        nested: __client_accounts_nested::Nested { writable: keys[3] },
+    };
+
+    let metas = accounts.orphan_account_metas();
+    let reads: Vec<Pubkey> = metas
+        .iter()
+        .filter(|m| !m.is_writable)
+        .map(|m| m.pubkey)
+        .collect();
+    let writes: Vec<Pubkey> = metas
+        .iter()
+        .filter(|m| m.is_writable)
+        .map(|m| m.pubkey)
+        .collect();
+    assert_eq!(reads, vec![keys[1]]);
+    assert_eq!(writes, vec![keys[0], keys[3]]);
+}
+
+#[test]
+fn test_two_level_nested_orphans() {
+    let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = [1; 3];
+    let mut data: Vec<Vec<u8>> = (0..3).map(shared_data).collect();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &ID, true, false, lamports, data))
+        .collect();
+
+    let mut accounts = try_accounts::<Outer>(&infos).ok().unwrap();
+    assert_eq!(accounts.top.value, 0);
+    assert_eq!(accounts.middle.readonly.value, 1);
+    assert_eq!(accounts.middle.inner.writable.value, 2);
+    assert_eq!(
+        accounts.writable_orphan_keys(),
+        BTreeSet::from([keys[0], keys[2]])
+    );
+
+    // On-chain metas mirror the given account infos, which are all writable.
+    let metas: Vec<(Pubkey, bool)> = accounts
+        .to_account_metas(None)
+        .iter()
+        .map(|m| (m.pubkey, m.is_writable))
+        .collect();
+    assert_eq!(
+        metas,
+        vec![(keys[0], true), (keys[1], true), (keys[2], true)]
+    );
+    assert_eq!(accounts.to_account_infos().len(), 3);
+
+    let client = __client_accounts_outer::Outer {
+        top: keys[0],
+        middle: __client_accounts_middle::Middle {
+            readonly: keys[1],
+            inner: __client_accounts_nested::Nested { writable: keys[2] },
+        },
+    };
+    let client_metas: Vec<(Pubkey, bool)> = client
+        .to_account_metas(None)
+        .iter()
+        .map(|m| (m.pubkey, m.is_writable))
+        .collect();
+    assert_eq!(
+        client_metas,
+        vec![(keys[0], true), (keys[1], false), (keys[2], true)]
+    );
+    assert_eq!(client.orphan_account_metas().len(), 3);
+
+    // Exits reach the innermost accounts, but skip those not marked `mut`.
+    accounts.top.value = 10;
+    accounts.middle.readonly.value = 11;
+    accounts.middle.inner.writable.value = 12;
+    accounts.exit(&ID).unwrap();
+    drop(accounts);
+    drop(infos);
+    assert_eq!(data, vec![shared_data(10), shared_data(1), shared_data(12)]);
+}
+
+// Runs `OwnedBy` with the shared account owned by, and storing, the registry
+// key unless overridden.
+fn owned_by(shared_owner: Option<Pubkey>, stored_registry: Option<Pubkey>) -> Result<()> {
+    let key = Pubkey::new_unique();
+    let registry = Pubkey::new_unique();
+    let owner = shared_owner.unwrap_or(registry);
+    let mut shared_lamports = 1;
+    let mut shared_data = Vec::new();
+    Registered {
+        registry: stored_registry.unwrap_or(registry),
+    }
+    .try_serialize(&mut shared_data)
+    .unwrap();
+    let mut registry_lamports = 1;
+    let mut registry_data = vec![];
+    let infos = [
+        account_info(
+            &key,
+            &owner,
+            false,
+            false,
+            &mut shared_lamports,
+            &mut shared_data,
+        ),
+        account_info(
+            &registry,
+            &ID,
+            false,
+            false,
+            &mut registry_lamports,
+            &mut registry_data,
+        ),
+    ];
+    try_accounts::<OwnedBy>(&infos).map(|_| ())
+}
+
+#[test]
+fn test_owner_and_has_one() {
+    assert!(owned_by(None, None).is_ok());
+    assert_eq!(
+        owned_by(Some(Pubkey::new_unique()), None).unwrap_err(),
+        ErrorCode::ConstraintOwner.into()
+    );
+    assert_eq!(
+        owned_by(None, Some(Pubkey::new_unique())).unwrap_err(),
+        ErrorCode::ConstraintHasOne.into()
+    );
+}
+
+#[test]
+fn test_deny_self_owner() {
+    let key = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let infos = [account_info(
+        &key,
+        &other,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+    )];
+    assert!(try_accounts::<Foreign>(&infos).is_ok());
+
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let infos = [account_info(
+        &key,
+        &ID,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+    )];
+    let err = try_accounts::<Foreign>(&infos).err().unwrap();
+    assert_eq!(err, ErrorCode::ConstraintDenySelfOwner.into());
+}
+
+#[test]
+fn test_try_header() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let header = Header {
+        version: 2,
+        authority: Pubkey::new_unique(),
+    };
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Large {
+        header: header.clone(),
+        body: vec![u64::MAX; 1024],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    assert_eq!(
+        OrphanAccount::<Large>::try_header::<Header>(&info).unwrap(),
+        header
+    );
+    assert_eq!(
+        OrphanAccount::<Shared>::try_header::<Header>(&info).unwrap_err(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+}
+
+#[test]
+fn test_strict_accounts() {
+    let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let owner = Pubkey::new_unique();
+    let mut lamports = [1; 2];
+    let mut data = [shared_data(1), shared_data(2)];
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &owner, false, false, lamports, data))
+        .collect();
+
+    assert!(try_accounts::<Strict>(&infos[..1]).is_ok());
+    let err = try_accounts::<Strict>(&infos).err().unwrap();
+    assert_eq!(err, ErrorCode::AccountTooManyKeys.into());
+    // Without the attribute, the extra account is left for `remaining_accounts`.
+    assert!(try_accounts::<NonExecutable>(&infos).is_ok());
+}
+
+#[test]
+fn test_strict_accounts_nested() {
+    let keys = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let owner = Pubkey::new_unique();
+    let mut lamports = [1; 3];
+    let mut data = [shared_data(1), shared_data(2), shared_data(3)];
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &owner, false, false, lamports, data))
+        .collect();
+
+    // The nested struct leaves the second account to the outer one.
+    let accounts = try_accounts::<OuterStrict>(&infos[..2]).unwrap();
+    assert_eq!((accounts.inner.shared.value, accounts.other.value), (1, 2));
+    let err = try_accounts::<OuterStrict>(&infos).err().unwrap();
+    assert_eq!(err, ErrorCode::AccountTooManyKeys.into());
+}
+
+#[test]
+fn test_reset_to_default() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 100;
+    let mut data = shared_data(7);
+    data.extend([1; 8]);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+
+    let mut shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert_eq!(
+        shared.reset_to_default(&Pubkey::new_unique()).unwrap_err(),
+        ErrorCode::AccountOwnedByWrongProgram.into()
+    );
+    shared.reset_to_default(&ID).unwrap();
+    assert_eq!(shared.value, 0);
+
+    shared.reload().unwrap();
+    assert_eq!(shared.value, 0);
+    assert_eq!(info.lamports(), 100);
+    let data = info.try_borrow_data().unwrap();
+    assert_eq!(data[..8], Shared::discriminator());
+    assert!(data[8..].iter().all(|b| *b == 0));
+}
+
+// The unix timestamp of the stubbed clock sysvar.
+const NOW: i64 = 1_700_000_000;
+
+// Provides the rent and clock sysvars, executes system program transfers,
+// account creations and `returner` invocations, records the accounts signed for by seeds in other
+// invocations, and keeps the return data.
+struct TestStubs;
+
+thread_local! {
+    static SIGNED_INVOCATIONS: RefCell<Vec<(Pubkey, Vec<Pubkey>)>> = RefCell::new(Vec::new());
+    static LOGS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    static LOGGED_DATA: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+    static RETURN_DATA: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+}
+
+impl solana_program::program_stubs::SyscallStubs for TestStubs {
+    fn sol_log(&self, message: &str) {
+        LOGS.with(|logs| logs.borrow_mut().push(message.to_string()));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        LOGGED_DATA.with(|logged| logged.borrow_mut().push(fields.concat()));
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|returned| *returned.borrow_mut() = data.to_vec());
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        solana_program::entrypoint::SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        solana_program::entrypoint::SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &solana_program::instruction::Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> solana_program::entrypoint::ProgramResult {
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &ID))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+        for meta in instruction.accounts.iter().filter(|meta| meta.is_signer) {
+            let signed = account_infos
+                .iter()
+                .any(|info| info.key == &meta.pubkey && info.is_signer);
+            if !signed && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+        if !signers.is_empty() {
+            SIGNED_INVOCATIONS.with(|invocations| {
+                invocations
+                    .borrow_mut()
+                    .push((instruction.program_id, signers))
+            });
+        }
+        let transfer = 2u32.to_le_bytes();
+        if instruction.program_id == System::id() && instruction.data[..4] == transfer {
+            let amount = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let find = |key: &Pubkey| account_infos.iter().find(|info| info.key == key).unwrap();
+            **find(&instruction.accounts[0].pubkey).lamports.borrow_mut() -= amount;
+            **find(&instruction.accounts[1].pubkey).lamports.borrow_mut() += amount;
+        }
+        // Funds, allocates and assigns the new account. Data the test sizes
+        // up front is kept as it is.
+        let create_account = 0u32.to_le_bytes();
+        if instruction.program_id == System::id() && instruction.data[..4] == create_account {
+            let amount = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(instruction.data[12..20].try_into().unwrap());
+            let owner = Pubkey::new(&instruction.data[20..52]);
+            let find = |key: &Pubkey| account_infos.iter().find(|info| info.key == key).unwrap();
+            **find(&instruction.accounts[0].pubkey).lamports.borrow_mut() -= amount;
+            let new_account = find(&instruction.accounts[1].pubkey);
+            **new_account.lamports.borrow_mut() += amount;
+            allocate(new_account, space as usize);
+            new_account.assign(&owner);
+        }
+        let allocate_ix = 8u32.to_le_bytes();
+        if instruction.program_id == System::id() && instruction.data[..4] == allocate_ix {
+            let space = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let info = account_infos
+                .iter()
+                .find(|info| info.key == &instruction.accounts[0].pubkey)
+                .unwrap();
+            allocate(info, space as usize);
+        }
+        let assign = 1u32.to_le_bytes();
+        if instruction.program_id == System::id() && instruction.data[..4] == assign {
+            let info = account_infos
+                .iter()
+                .find(|info| info.key == &instruction.accounts[0].pubkey)
+                .unwrap();
+            info.assign(&Pubkey::new(&instruction.data[4..36]));
+        }
+        // Writes the instruction data into the body of the return buffer.
+        if instruction.program_id == returner::ID {
+            let buffer = account_infos
+                .iter()
+                .find(|info| info.key == &instruction.accounts[0].pubkey)
+                .unwrap();
+            let mut data = buffer.try_borrow_mut_data()?;
+            data[8..8 + instruction.data.len()].copy_from_slice(&instruction.data);
+        }
+        Ok(())
+    }
+}
+
+/// Gives an account without data `space` zeroed bytes, like the system
+/// program's `allocate`.
+fn allocate(info: &AccountInfo, space: usize) {
+    if info.data_is_empty() {
+        *info.data.borrow_mut() = Box::leak(vec![0; space].into_boxed_slice());
+    }
+}
+
+#[test]
+fn test_init_space_too_small() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let (shared, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (system_program, loader) = (System::id(), Pubkey::default());
+    // Its own owner, as the stub assigns the created account.
+    let shared_owner = System::id();
+    let (mut shared_lamports, mut shared_data) = (0, vec![0; 8]);
+    let (mut payer_lamports, mut payer_data) = (1_000_000_000, vec![]);
+    let (mut system_lamports, mut system_data) = (1, vec![]);
+    let mut payer_info = account_info(
+        &payer,
+        &system_program,
+        true,
+        false,
+        &mut payer_lamports,
+        &mut payer_data,
+    );
+    payer_info.is_signer = true;
+    let infos = [
+        account_info(
+            &shared,
+            &shared_owner,
+            true,
+            false,
+            &mut shared_lamports,
+            &mut shared_data,
+        ),
+        payer_info,
+        account_info(
+            &system_program,
+            &loader,
+            false,
+            true,
+            &mut system_lamports,
+            &mut system_data,
+        ),
+    ];
+
+    let err = try_accounts::<InitShared>(&infos).err().unwrap();
+    assert_eq!(err, ErrorCode::ConstraintSpace.into());
+    match err {
+        Error::AnchorError(AnchorError {
+            compared_values: Some(ComparedValues::Values((space, required_space))),
+            ..
+        }) => assert_eq!((space.as_str(), required_space.as_str()), ("8", "16")),
+        _ => panic!("expected the space values"),
+    }
+}
+
+#[test]
+fn test_require_authority() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Authorized { authority }.try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let account = OrphanAccount::<Authorized>::try_from(&info).unwrap();
+    assert!(account.require_authority(&authority).is_ok());
+    assert_eq!(
+        account
+            .require_authority(&Pubkey::new_unique())
+            .unwrap_err(),
+        ErrorCode::ConstraintHasOne.into()
+    );
+}
+
+#[test]
+fn test_reload_after_realloc() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let header = Header {
+        version: 1,
+        authority: Pubkey::new_unique(),
+    };
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Large {
+        header: header.clone(),
+        body: vec![1],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut large = OrphanAccount::<Large>::try_from(&info).unwrap();
+    assert!(!large.grew_since_construction());
+
+    // Mock a realloc by the runtime, which swaps in the grown data region.
+    let mut grown = Vec::new();
+    Large {
+        header,
+        body: vec![1, 2, 3],
+    }
+    .try_serialize(&mut grown)
+    .unwrap();
+    let grown_len = grown.len();
+    *info.data.borrow_mut() = Box::leak(grown.into_boxed_slice());
+    assert!(large.grew_since_construction());
+
+    large.reload().unwrap();
+    assert_eq!(large.body, vec![1, 2, 3]);
+    assert_eq!(large.to_account_info().data_len(), grown_len);
+}
+
+#[test]
+fn test_checked_builder() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let rent = Rent::default();
+    let mut data = shared_data(7);
+    let mut lamports = rent.minimum_balance(data.len());
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let shared = OrphanAccount::<Shared>::checked(&info)
+        .owner(&owner)
+        .discriminator()
+        .rent_exempt(&rent)
+        .min_lamports(1)
+        .build()
+        .unwrap();
+    assert_eq!(shared.value, 7);
+
+    // The first failing check is reported, even if later ones fail too.
+    let err = OrphanAccount::<Shared>::checked(&info)
+        .owner(&owner)
+        .min_lamports(u64::MAX)
+        .owner(&Pubkey::new_unique())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, ErrorCode::AccountInsufficientLamports.into());
+
+    let err = OrphanAccount::<Large>::checked(&info)
+        .rent_exempt(&rent)
+        .discriminator()
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, ErrorCode::AccountDiscriminatorMismatch.into());
+}
+
+#[test]
+fn test_checked_builder_collecting() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let rent = Rent::default();
+    let mut data = shared_data(7);
+    let mut lamports = 1;
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let errors = OrphanAccount::<Shared>::checked(&info)
+        .owner(&Pubkey::new_unique())
+        .discriminator()
+        .rent_exempt(&rent)
+        .build_collecting()
+        .err()
+        .unwrap();
+    assert_eq!(
+        errors,
+        vec![
+            ErrorCode::AccountOwnedByWrongProgram.into(),
+            ErrorCode::ConstraintRentExempt.into(),
+        ]
+    );
+
+    let shared = OrphanAccount::<Shared>::checked(&info)
+        .owner(&owner)
+        .build_collecting()
+        .ok()
+        .unwrap();
+    assert_eq!(shared.value, 7);
+}
+
+#[test]
+fn test_memoize_orphans() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let infos = [info.clone(), info];
+
+    let accounts = try_accounts::<Memoized>(&infos).ok().unwrap();
+    assert_eq!(DESERIALIZED.load(Ordering::SeqCst), 1);
+    assert_eq!(accounts.first.value, 7);
+    assert_eq!(accounts.second.value, 7);
+}
+
+#[test]
+fn test_const_serialized_len() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let counted = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert_eq!(counted.serialized_len().unwrap(), 16);
+
+    // The codec panics if asked to serialize, so the const must be used.
+    let fixed = OrphanAccount::<Shared, ConstLen<NoSerializeCodec>>::try_from(&info).unwrap();
+    assert_eq!(fixed.serialized_len().unwrap(), Shared::SERIALIZED_LEN);
+}
+
+pub struct Colliding;
+
+impl Discriminator for Colliding {
+    fn discriminator() -> [u8; 8] {
+        Shared::discriminator()
+    }
+}
+
+#[test]
+fn test_discriminator_collision() {
+    register_discriminator::<Shared>().unwrap();
+    register_discriminator::<Shared>().unwrap();
+    register_discriminator::<Large>().unwrap();
+    assert_eq!(
+        registered_type(&shared_data(7)),
+        Some(std::any::type_name::<Shared>())
+    );
+
+    assert_eq!(
+        register_discriminator::<Colliding>().unwrap_err(),
+        ErrorCode::AccountDiscriminatorCollision.into()
+    );
+    // The first registration is kept.
+    assert_eq!(
+        registered_type(&Colliding::discriminator()),
+        Some(std::any::type_name::<Shared>())
+    );
+}
+
+#[test]
+fn test_assert_pda() {
+    let owner = Pubkey::new_unique();
+    let (key, bump) = Pubkey::find_program_address(&[b"shared", owner.as_ref()], &ID);
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    assert_eq!(
+        shared
+            .assert_pda(&[b"shared", owner.as_ref()], &ID)
+            .unwrap(),
+        bump
+    );
+
+    let err = shared.assert_pda(&[b"other"], &ID).unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintSeeds.into());
+    let err = shared
+        .assert_pda(&[b"shared", owner.as_ref()], &owner)
+        .unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintSeeds.into());
+}
+
+#[test]
+fn test_required_if() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut placeholder_lamports = 1;
+    let placeholder = account_info(&ID, &owner, false, true, &mut placeholder_lamports, &mut []);
+    let present = [info.clone(), info];
+    let absent = [placeholder.clone(), placeholder];
+
+    let try_with_config = |infos: &[AccountInfo<'_>], with_config: bool| {
+        RequiredIf::try_accounts(
+            &ID,
+            &mut &infos[..],
+            &[with_config.into()],
+            &mut BTreeMap::new(),
+            &mut BTreeSet::new(),
+        )
+        .map(|accounts| {
+            let keys = accounts.writable_orphan_keys();
+            accounts.config.map(|config| (config.value, keys.len()))
+        })
+    };
+
+    assert_eq!(try_with_config(&present, true).unwrap(), Some((7, 1)));
+    assert_eq!(try_with_config(&present, false).unwrap(), Some((7, 1)));
+    assert_eq!(try_with_config(&absent, false).unwrap(), None);
+    assert_eq!(
+        try_with_config(&absent, true).unwrap_err(),
+        ErrorCode::ConstraintRequiredIf.into()
+    );
+
+    // An absent account keeps its position in the metas.
+    let infos = [absent[0].clone(), present[0].clone()];
+    let accounts = RequiredIf::try_accounts(
+        &ID,
+        &mut &infos[..],
+        &[false.into()],
+        &mut BTreeMap::new(),
+        &mut BTreeSet::new(),
+    )
+    .unwrap();
+    let keys: Vec<Pubkey> = accounts
+        .to_account_metas(None)
+        .iter()
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert_eq!(keys, vec![ID, key]);
+}
+
+#[account]
+pub struct Counters {
+    pub a: u64,
+    pub b: u64,
+}
+
+impl ConstSerializedLen for Counters {
+    const SERIALIZED_LEN: usize = 8 + 8 + 8;
+}
+
+impl ChangedRange for Counters {
+    // Only `b` is ever reported as changed.
+    fn changed_range(&self) -> Option<Range<usize>> {
+        Some(16..24)
+    }
+}
+
+#[test]
+fn test_exit_changed_range() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Counters { a: 1, b: 2 }.try_serialize(&mut data).unwrap();
+    let original = data.clone();
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+
+    let mut counters = OrphanAccount::<Counters, Partial>::try_from(&info).unwrap();
+    counters.a = 10;
+    counters.b = 20;
+    counters.exit(&owner).unwrap();
+
+    let data = info.try_borrow_data().unwrap();
+    assert_eq!(&data[..16], &original[..16]);
+    assert_eq!(&data[16..], &20u64.to_le_bytes());
+}
+
+#[test]
+fn test_ensure_rent_exempt() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let rent = Rent::default();
+    let (key, payer, owner) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let system_program = System::id();
+    let mut data = shared_data(7);
+    let minimum = rent.minimum_balance(data.len());
+    let mut lamports = minimum;
+    let mut payer_lamports = 1_000_000_000;
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let payer_info = AccountInfo::new(
+        &payer,
+        true,
+        true,
+        &mut payer_lamports,
+        &mut [],
+        &system_program,
+        false,
+        Epoch::default(),
+    );
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    // Already exempt.
+    shared.ensure_rent_exempt(&payer_info, &rent).unwrap();
+    assert_eq!(info.lamports(), minimum);
+    assert_eq!(payer_info.lamports(), 1_000_000_000);
+
+    // Needs a top up, e.g. after a realloc.
+    **info.lamports.borrow_mut() = minimum - 100;
+    shared.ensure_rent_exempt(&payer_info, &rent).unwrap();
+    assert_eq!(info.lamports(), minimum);
+    assert_eq!(payer_info.lamports(), 1_000_000_000 - 100);
+}
+
+#[account]
+pub struct ConfigV1 {
+    pub fee: u64,
+}
+
+#[account]
+pub struct Config {
+    pub fee: u64,
+    pub cap: u64,
+}
+
+impl Migrate for Config {
+    fn migrate(data: &[u8]) -> Result<Self> {
+        let v1 = ConfigV1::try_deserialize(&mut &data[..])?;
+        Ok(Config {
+            fee: v1.fee,
+            cap: u64::MAX,
+        })
+    }
+}
+
+#[test]
+fn test_try_from_migrating() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    // A v1 account, already reallocated to fit the v2 layout.
+    let mut data = Vec::new();
+    ConfigV1 { fee: 5 }.try_serialize(&mut data).unwrap();
+    data.resize(8 + 8 + 8, 0);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+
+    // `legacy-fallback` reads accounts with a mismatched discriminator.
+    #[cfg(not(feature = "legacy-fallback"))]
+    assert_eq!(
+        OrphanAccount::<Config>::try_from(&info).err().unwrap(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+    let config = OrphanAccount::<Config>::try_from_migrating(&info).unwrap();
+    assert!(config.is_migrated());
+    assert_eq!((config.fee, config.cap), (5, u64::MAX));
+    config.exit(&owner).unwrap();
+
+    // Written back in the v2 layout.
+    let config = OrphanAccount::<Config>::try_from_migrating(&info).unwrap();
+    assert!(!config.is_migrated());
+    assert_eq!((config.fee, config.cap), (5, u64::MAX));
+
+    // Data that isn't any known layout still fails.
+    info.try_borrow_mut_data().unwrap()[..8].copy_from_slice(&[0; 8]);
+    assert_eq!(
+        OrphanAccount::<Config>::try_from_migrating(&info)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+}
+
+#[account]
+pub struct Vault {
+    pub bump: u8,
+}
+
+impl anchor_lang::Bump for Vault {
+    fn seed(&self) -> u8 {
+        self.bump
+    }
+}
+
+#[test]
+fn test_invoke_signed_as() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let (key, bump) = Pubkey::find_program_address(&[b"vault"], &ID);
+    let noop_program = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Vault { bump }.try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    let vault = OrphanAccount::<Vault>::try_from(&info).unwrap();
+
+    let ix = solana_program::instruction::Instruction {
+        program_id: noop_program,
+        accounts: vec![AccountMeta::new_readonly(key, true)],
+        data: vec![],
+    };
+    vault
+        .invoke_signed_as(&ix, std::slice::from_ref(&info), &[b"vault"])
+        .unwrap();
+    assert!(SIGNED_INVOCATIONS
+        .with(|invocations| invocations.borrow().contains(&(noop_program, vec![key]))));
+
+    // The stored bump must produce the account's address.
+    let err = vault
+        .invoke_signed_as(&ix, std::slice::from_ref(&info), &[b"other"])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ProgramError(ref e) if matches!(
+            e.program_error,
+            ProgramError::MissingRequiredSignature | ProgramError::InvalidSeeds
+        )
+    ));
+}
+
+#[account]
+pub struct Slots {
+    pub values: [u64; 4],
+}
+
+impl Index<usize> for Slots {
+    type Output = u64;
+
+    fn index(&self, index: usize) -> &u64 {
+        &self.values[index]
+    }
+}
+
+impl IndexMut<usize> for Slots {
+    fn index_mut(&mut self, index: usize) -> &mut u64 {
+        &mut self.values[index]
+    }
+}
+
+#[test]
+fn test_index() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Slots {
+        values: [1, 2, 3, 4],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+
+    let mut slots = OrphanAccount::<Slots>::try_from(&info).unwrap();
+    assert_eq!(slots[2], 3);
+    assert!(!slots.is_dirty().unwrap());
+    slots[2] = 30;
+    assert_eq!(slots.values, [1, 2, 30, 4]);
+    assert!(slots.is_dirty().unwrap());
+}
+
+mod returner {
+    anchor_lang::declare_id!("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin");
+}
+
+mod prog_a {
+    anchor_lang::declare_id!("6Q8bvUxCJ9ViDUGjnZC6PjaXLBhQ8HGhuXaCU6WtYmUF");
+}
+
+mod prog_b {
+    anchor_lang::declare_id!("HmbTLCmaGvZhKnn1Zfa1JVnp7vkMV4DYVxPLWBVoN65L");
+}
+
+#[account(orphan_owners = [prog_a::ID, prog_b::ID])]
+pub struct Trusted {
+    pub value: u64,
+}
+
+#[test]
+fn test_orphan_owners() {
+    assert_eq!(Trusted::owners(), vec![prog_a::ID, prog_b::ID]);
+
+    let key = Pubkey::new_unique();
+    let mut data = Vec::new();
+    Trusted { value: 7 }.try_serialize(&mut data).unwrap();
+    for owner in [prog_a::ID, prog_b::ID] {
+        let mut lamports = 1;
+        let mut data = data.clone();
+        let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+        let trusted = OrphanAccount::<Trusted>::try_from_trusted(&info).unwrap();
+        assert_eq!(trusted.value, 7);
+    }
+
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Trusted>::try_from_trusted(&info)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountOwnedByWrongProgram.into()
+    );
+}
+
+#[test]
+fn test_close_if_empty() {
+    let key = Pubkey::new_unique();
+    let dest_key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 100;
+    let mut dest_lamports = 1;
+    let mut dest_data = [];
+    let dest = account_info(
+        &dest_key,
+        &owner,
+        true,
+        false,
+        &mut dest_lamports,
+        &mut dest_data,
+    );
+
+    let mut data = shared_data(5);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert_eq!(
+        shared
+            .close_if_empty(dest.clone(), |shared| shared.value == 0)
+            .unwrap_err(),
+        ErrorCode::AccountNotEmpty.into()
+    );
+    assert_eq!(info.lamports(), 100);
+    assert_eq!(dest.lamports(), 1);
+
+    let mut data = shared_data(0);
+    let mut lamports = 100;
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    shared
+        .close_if_empty(dest.clone(), |shared| shared.value == 0)
+        .unwrap();
+    assert_eq!(info.lamports(), 0);
+    assert_eq!(dest.lamports(), 101);
+    assert_eq!(
+        &info.try_borrow_data().unwrap()[..8],
+        &anchor_lang::__private::CLOSED_ACCOUNT_DISCRIMINATOR
+    );
+}
+
+#[account]
+#[derive(Diffable)]
+pub struct Audited {
+    pub authority: Pubkey,
+    pub balance: u64,
+}
+
+#[test]
+fn test_diff() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Audited {
+        authority: owner,
+        balance: 10,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+
+    let mut audited = OrphanAccount::<Audited>::try_from(&info).unwrap();
+    let previous = audited.snapshot();
+    assert_eq!(audited.diff(&previous), vec![]);
+    audited.balance = 20;
+    assert_eq!(
+        audited.diff(&previous),
+        vec![FieldChange { field: "balance" }]
+    );
+}
+
+#[account]
+#[derive(Copy, Default)]
+#[repr(C)]
+pub struct PodCounters {
+    pub hits: u64,
+    pub misses: u32,
+    pub flags: [u8; 4],
+}
+
+unsafe impl anchor_lang::__private::bytemuck::Pod for PodCounters {}
+unsafe impl anchor_lang::__private::bytemuck::Zeroable for PodCounters {}
+
+#[test]
+fn test_pod_copy_matches_borsh() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    PodCounters::default().try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+
+    let mut counters = OrphanAccount::<PodCounters, PodCopy>::try_from(&info).unwrap();
+    assert_eq!(counters.serialized_len().unwrap(), 8 + 16);
+    counters.hits = 0x0102_0304_0506_0708;
+    counters.misses = 9;
+    counters.flags = [1, 2, 3, 4];
+    counters.exit(&ID).unwrap();
+
+    let mut expected = Vec::new();
+    counters.try_serialize(&mut expected).unwrap();
+    assert_eq!(&info.try_borrow_data().unwrap()[..], &expected[..]);
+}
+
+#[test]
+fn test_read_field() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    PodCounters {
+        hits: 5,
+        misses: 2,
+        flags: [1, 0, 0, 1],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let counters = OrphanAccount::<PodCounters, PodCopy>::try_from(&info).unwrap();
+
+    assert_eq!(counters.read_field::<u64>(8).unwrap(), 5);
+    assert_eq!(counters.read_field::<u32>(16).unwrap(), 2);
+    assert_eq!(counters.read_field::<[u8; 4]>(20).unwrap(), [1, 0, 0, 1]);
+    assert_eq!(
+        counters.read_field::<u64>(20).unwrap_err(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+    assert_eq!(
+        counters.read_field::<u8>(25).unwrap_err(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_try_from_bytes_borrowed() {
+    let owner = Pubkey::new_unique();
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let snapshot: Vec<u8> = (0..4).flat_map(shared_data).collect();
+
+    let values: Vec<u64> = snapshot
+        .chunks(16)
+        .zip(&keys)
+        .map(|(record, key)| {
+            OrphanAccount::<Shared>::try_from_bytes_borrowed(key, &owner, record)
+                .unwrap()
+                .value
+        })
+        .collect();
+    assert_eq!(values, vec![0, 1, 2, 3]);
+
+    let err = OrphanAccount::<Shared>::try_from_bytes_borrowed(&keys[1], &owner, &[0; 16])
+        .err()
+        .unwrap();
+    assert_eq!(err, ErrorCode::AccountDiscriminatorMismatch.into());
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == keys[1].to_string()
+    ));
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_bytes_borrowed(&keys[0], &System::id(), &snapshot[..16])
+            .err()
+            .unwrap(),
+        ErrorCode::AccountClosed.into()
+    );
+}
+
+#[test]
+fn test_try_from_auto() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = vec![0xFF; 8];
+    data.extend_from_slice(&3u64.to_le_bytes());
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    // Accounts shorter than the threshold skip the discriminator check.
+    let small = OrphanAccount::<Shared>::try_from_auto(&info, 17).unwrap();
+    assert_eq!(small.value, 3);
+
+    #[cfg(not(feature = "legacy-fallback"))]
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_auto(&info, 16)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+}
+
+#[test]
+fn test_read_big_endian() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = vec![0u8; 8];
+    data.extend_from_slice(&0x0102_0304_0506_0708u64.to_be_bytes());
+    data.extend_from_slice(&0xBEEFu16.to_be_bytes());
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let foreign = OrphanAccount::<Shared>::try_from_unchecked(&info).unwrap();
+
+    assert_eq!(foreign.read_u64_be(8).unwrap(), 0x0102_0304_0506_0708);
+    assert_eq!(foreign.read_u64_le(8).unwrap(), 0x0807_0605_0403_0201);
+    assert_eq!(foreign.read_u16_be(16).unwrap(), 0xBEEF);
+    assert_eq!(foreign.read_u32_be(12).unwrap(), 0x0506_0708);
+    assert_eq!(
+        foreign.read_u32_be(16).unwrap_err(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+}
+
+#[test]
+fn test_pod_view() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    PodCounters {
+        hits: 5,
+        misses: 2,
+        flags: [1, 0, 0, 1],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+
+    let mut counters = OrphanAccount::<PodCounters, PodCopy>::try_from(&info).unwrap();
+    {
+        let view = counters.view().unwrap();
+        assert_eq!(view.hits, 5);
+        assert_eq!(view.misses, 2);
+        assert_eq!(view.flags, [1, 0, 0, 1]);
+    }
+
+    // The view only sees changes once they are written back.
+    counters.hits = 6;
+    assert_eq!(counters.view().unwrap().hits, 5);
+    counters.exit(&ID).unwrap();
+    assert_eq!(counters.view().unwrap().hits, 6);
+}
+
+#[test]
+fn test_lazy_owner() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(3);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+
+    // Reads never check the owner.
+    let mut shared = OrphanAccount::<Shared>::try_from_lazy_owner(&info).unwrap();
+    assert_eq!(shared.value, 3);
+    shared.exit(&ID).unwrap();
+
+    // Writes to an account the program doesn't own fail on exit.
+    shared.value = 4;
+    assert_eq!(
+        shared.exit(&ID).unwrap_err(),
+        Error::from(ErrorCode::AccountOwnedByWrongProgram).with_pubkeys((owner, ID))
+    );
+    assert_eq!(info.try_borrow_data().unwrap()[..], shared_data(3)[..]);
+
+    // Writes to an owned account are persisted.
+    let mut lamports = 1;
+    let mut data = shared_data(3);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from_lazy_owner(&info).unwrap();
+    shared.value = 4;
+    shared.exit(&ID).unwrap();
+    assert_eq!(info.try_borrow_data().unwrap()[..], shared_data(4)[..]);
+}
+
+#[account]
+#[derive(DebugFields)]
+pub struct Labeled {
+    pub count: u8,
+    pub label: String,
+}
+
+#[cfg(feature = "anchor-debug")]
+#[test]
+fn test_debug_dump() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Labeled {
+        count: 2,
+        label: "x".repeat(100),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let labeled = OrphanAccount::<Labeled>::try_from(&info).unwrap();
+    LOGS.with(|logs| logs.borrow_mut().clear());
+    labeled.debug_dump();
+    let expected = vec![
+        format!("{}:", key),
+        "  count: 2".to_string(),
+        format!("  label: \"{}...", "x".repeat(63)),
+    ];
+    assert_eq!(LOGS.with(|logs| logs.borrow().clone()), expected);
+}
+
+#[test]
+fn test_assert_same_owner() {
+    let (owner, other_owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (shared_key, trusted_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut shared_lamports, mut trusted_lamports) = (1, 1);
+    let mut shared_data = shared_data(1);
+    let mut trusted_data = Vec::new();
+    Trusted { value: 2 }
+        .try_serialize(&mut trusted_data)
+        .unwrap();
+    let shared_info = account_info(
+        &shared_key,
+        &owner,
+        false,
+        false,
+        &mut shared_lamports,
+        &mut shared_data,
+    );
+    let trusted_info = account_info(
+        &trusted_key,
+        &owner,
+        false,
+        false,
+        &mut trusted_lamports,
+        &mut trusted_data,
+    );
+    let shared = OrphanAccount::<Shared>::try_from(&shared_info).unwrap();
+    let trusted = OrphanAccount::<Trusted>::try_from(&trusted_info).unwrap();
+    shared.assert_same_owner(&trusted).unwrap();
+    trusted.assert_same_owner(&shared).unwrap();
+
+    let mut other_lamports = 1;
+    let mut other_data = trusted_info.try_borrow_data().unwrap().to_vec();
+    let other_info = account_info(
+        &trusted_key,
+        &other_owner,
+        false,
+        false,
+        &mut other_lamports,
+        &mut other_data,
+    );
+    let other = OrphanAccount::<Trusted>::try_from(&other_info).unwrap();
+    assert_eq!(
+        shared.assert_same_owner(&other).unwrap_err(),
+        Error::from(ErrorCode::ConstraintOwner).with_pubkeys((owner, other_owner))
+    );
+}
+
+// A foreign account stored as two little endian `u32`s, with no
+// discriminator to check.
+#[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct RawPoint {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl NoDiscriminator for RawPoint {}
+
+impl AccountSerialize for RawPoint {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        self.serialize(writer)
+            .map_err(|_| ErrorCode::AccountDidNotSerialize.into())
+    }
+}
+
+impl AccountDeserialize for RawPoint {
+    fn try_deserialize(_buf: &mut &[u8]) -> Result<Self> {
+        Err(ErrorCode::AccountDiscriminatorMismatch.into())
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        Self::deserialize(buf).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+#[test]
+fn test_no_discriminator() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = RawPoint { x: 1, y: 2 }.try_to_vec().unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    // `legacy-fallback` reads accounts with a mismatched discriminator.
+    #[cfg(not(feature = "legacy-fallback"))]
+    assert_eq!(
+        OrphanAccount::<RawPoint>::try_from(&info).unwrap_err(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+    let point = OrphanAccount::<RawPoint, Bare>::try_from(&info).unwrap();
+    assert_eq!(*point, RawPoint { x: 1, y: 2 });
+
+    // Uninitialized accounts are still rejected.
+    let system = System::id();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let info = account_info(&key, &system, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<RawPoint, Bare>::try_from(&info).unwrap_err(),
+        ErrorCode::AccountNotInitialized.into()
+    );
+}
+
+#[test]
+fn test_reload_all() {
+    let owner = Pubkey::new_unique();
+    let (shared_key, trusted_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut shared_lamports, mut trusted_lamports) = (1, 1);
+    let mut shared_data = shared_data(1);
+    let mut trusted_data = Vec::new();
+    Trusted { value: 2 }
+        .try_serialize(&mut trusted_data)
+        .unwrap();
+    let shared_info = account_info(
+        &shared_key,
+        &owner,
+        true,
+        false,
+        &mut shared_lamports,
+        &mut shared_data,
+    );
+    let trusted_info = account_info(
+        &trusted_key,
+        &owner,
+        true,
+        false,
+        &mut trusted_lamports,
+        &mut trusted_data,
+    );
+    let mut shared = OrphanAccount::<Shared>::try_from(&shared_info).unwrap();
+    let mut trusted = OrphanAccount::<Trusted>::try_from(&trusted_info).unwrap();
+
+    // Simulate a CPI changing both accounts.
+    shared_info.try_borrow_mut_data().unwrap()[8..].copy_from_slice(&10u64.to_le_bytes());
+    trusted_info.try_borrow_mut_data().unwrap()[8..].copy_from_slice(&20u64.to_le_bytes());
+    reload_all(&mut [&mut shared, &mut trusted]).unwrap();
+    assert_eq!(shared.value, 10);
+    assert_eq!(trusted.value, 20);
+
+    // Failures are named after the account that failed to reload.
+    trusted_info.try_borrow_mut_data().unwrap()[..8].copy_from_slice(&[0; 8]);
+    let err = reload_all(&mut [&mut shared, &mut trusted]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == trusted_key.to_string()
+    ));
+}
+
+#[test]
+fn test_exit_if() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    shared.value = 2;
+    shared.exit_if(&ID, |shared| shared.value > 5).unwrap();
+    assert_eq!(&info.try_borrow_data().unwrap()[..], &shared_data(1)[..]);
+
+    shared.value = 6;
+    shared.exit_if(&ID, |shared| shared.value > 5).unwrap();
+    assert_eq!(&info.try_borrow_data().unwrap()[..], &shared_data(6)[..]);
+}
+
+#[test]
+fn test_exit_all() {
+    let (first_key, second_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut first_lamports, mut second_lamports) = (1, 1);
+    let (mut first_data, mut second_data) = (shared_data(1), shared_data(2));
+    let first_info = account_info(
+        &first_key,
+        &ID,
+        true,
+        false,
+        &mut first_lamports,
+        &mut first_data,
+    );
+    let second_info = account_info(
+        &second_key,
+        &ID,
+        true,
+        false,
+        &mut second_lamports,
+        &mut second_data,
+    );
+    let mut first = OrphanAccount::<Shared>::try_from(&first_info).unwrap();
+    let mut second =
+        OrphanAccount::<Shared>::try_from_write_sealed(&second_info, &Pubkey::new_unique())
+            .unwrap();
+    first.value = 10;
+    exit_all(&[&first, &second], &ID).unwrap();
+
+    // The sealed account can't be written by this program.
+    second.value = 20;
+    let err = exit_all(&[&first, &second], &ID).unwrap_err();
+    assert_eq!(err, ErrorCode::AccountWriteSealed.into());
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == second_key.to_string()
+    ));
+    assert_eq!(
+        &first_info.try_borrow_data().unwrap()[..],
+        &shared_data(10)[..]
+    );
+    assert_eq!(
+        &second_info.try_borrow_data().unwrap()[..],
+        &shared_data(2)[..]
+    );
+}
+
+#[test]
+fn test_signer() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let err = try_accounts::<SignedShared>(&[info]).err().unwrap();
+    assert_eq!(err, ErrorCode::ConstraintSigner.into());
+
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = AccountInfo::new(
+        &key,
+        true,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        Epoch::default(),
+    );
+    assert!(try_accounts::<SignedShared>(&[info]).is_ok());
+}
+
+#[test]
+fn test_stable_id() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    let trusted = OrphanAccount::<Trusted>::try_from_unchecked(&info).unwrap();
+    assert_eq!(shared.stable_id()[..32], key.to_bytes());
+    assert_eq!(shared.stable_id()[32..], Shared::discriminator());
+    assert_ne!(shared.stable_id(), trusted.stable_id());
+}
+
+#[account]
+pub struct Gated {
+    pub flags: u64,
+}
+
+impl HasFlags for Gated {
+    fn flags(&self) -> u64 {
+        self.flags
+    }
+}
+
+#[test]
+fn test_require_flags() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Gated { flags: 0b0101 }.try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let gated = OrphanAccount::<Gated>::try_from(&info).unwrap();
+    gated.require_flags(0b0001).unwrap();
+    gated.require_flags(0b0101).unwrap();
+    assert_eq!(
+        gated.require_flags(0b0011).unwrap_err(),
+        Error::from(ErrorCode::ConstraintFlags).with_values(("0x5", "0x3"))
+    );
+}
+
+#[account]
+pub struct Versioned {
+    pub version: u8,
+    pub value: u64,
+}
+
+impl SupportedVersions for Versioned {
+    const RANGE: std::ops::RangeInclusive<u8> = 1..=2;
+}
+
+#[test]
+fn test_try_from_versioned() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    for version in [1, 2] {
+        let mut lamports = 1;
+        let mut data = Vec::new();
+        Versioned { version, value: 5 }
+            .try_serialize(&mut data)
+            .unwrap();
+        let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+        let versioned = OrphanAccount::<Versioned>::try_from_versioned(&info).unwrap();
+        assert_eq!(versioned.version, version);
+    }
+
+    // The body of version 3 isn't even read.
+    let mut lamports = 1;
+    let mut data = Versioned::discriminator().to_vec();
+    data.push(3);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Versioned>::try_from_versioned(&info)
+            .err()
+            .unwrap(),
+        Error::from(ErrorCode::AccountVersionUnsupported).with_values((3, "1..=2"))
+    );
+}
+
+#[test]
+fn test_lamport_ledger() {
+    let owner = Pubkey::new_unique();
+    let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = [100, 10, 0];
+    let mut data: Vec<Vec<u8>> = (0..3).map(shared_data).collect();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &owner, true, false, lamports, data))
+        .collect();
+
+    let ledger = LamportLedger::new(&infos).unwrap();
+    assert_eq!(ledger.total(), 110);
+    ledger.move_lamports(0, 1, 30).unwrap();
+    ledger.move_lamports(1, 2, 40).unwrap();
+    assert_eq!(
+        infos.iter().map(|info| info.lamports()).collect::<Vec<_>>(),
+        vec![70, 0, 40]
+    );
+    assert_eq!(
+        ledger.move_lamports(1, 0, 1).unwrap_err(),
+        Error::from(ErrorCode::AccountInsufficientLamports).with_values((0, 1))
+    );
+    assert_eq!(
+        ledger.move_lamports(0, 3, 1).unwrap_err(),
+        Error::from(ErrorCode::AccountIndexOutOfRange).with_values((3, 3))
+    );
+
+    // Lamports created outside the ledger break the invariant.
+    **infos[2].lamports.borrow_mut() += 1;
+    assert_eq!(
+        ledger.move_lamports(0, 1, 1).unwrap_err(),
+        Error::from(ErrorCode::AccountLamportsNotConserved).with_values((111, 110))
+    );
+}
+
+#[test]
+fn test_reload_while_borrowed() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    shared.value = 2;
+
+    let borrow = info.try_borrow_mut_data().unwrap();
+    assert_eq!(
+        shared.reload().unwrap_err(),
+        ProgramError::AccountBorrowFailed.into()
+    );
+    assert_eq!(shared.value, 2);
+    drop(borrow);
+
+    shared.reload().unwrap();
+    assert_eq!(shared.value, 1);
+}
+
+#[test]
+fn test_write_sealed() {
+    let key = Pubkey::new_unique();
+    let writer = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+
+    // Another program, even the owner, can read but not write.
+    let mut shared = OrphanAccount::<Shared>::try_from_write_sealed(&info, &writer).unwrap();
+    shared.exit(&ID).unwrap();
+    shared.value = 2;
+    assert_eq!(
+        shared.exit(&ID).unwrap_err(),
+        Error::from(ErrorCode::AccountWriteSealed).with_pubkeys((ID, writer))
+    );
+    assert_eq!(info.try_borrow_data().unwrap()[..], shared_data(1)[..]);
+
+    // The designated writer persists changes as usual.
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &writer, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from_write_sealed(&info, &writer).unwrap();
+    shared.value = 2;
+    shared.exit(&writer).unwrap();
+    assert_eq!(info.try_borrow_data().unwrap()[..], shared_data(2)[..]);
+}
+
+#[test]
+fn test_mutation_events() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from(&info)
+        .unwrap()
+        .with_mutation_events();
+    LOGGED_DATA.with(|logged| logged.borrow_mut().clear());
+
+    // A no-op exit emits nothing.
+    shared.exit(&ID).unwrap();
+    assert!(LOGGED_DATA.with(|logged| logged.borrow().is_empty()));
+
+    shared.value = 2;
+    shared.exit(&ID).unwrap();
+    let expected = OrphanMutated {
+        key,
+        discriminator: Shared::discriminator(),
+    };
+    assert_eq!(
+        LOGGED_DATA.with(|logged| logged.borrow().clone()),
+        vec![Event::data(&expected)]
+    );
+}
+
+#[test]
+fn test_reload_retry() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared, FlakyCodec>::try_from(&info).unwrap();
+    info.try_borrow_mut_data().unwrap()[8..].copy_from_slice(&2u64.to_le_bytes());
+
+    // The first attempt fails, the second succeeds.
+    FLAKY_FAILURES.with(|failures| *failures.borrow_mut() = 1);
+    shared.reload_retry(3).unwrap();
+    assert_eq!(shared.value, 2);
+
+    FLAKY_FAILURES.with(|failures| *failures.borrow_mut() = 1);
+    assert_eq!(
+        shared.reload_retry(1).unwrap_err(),
+        ProgramError::AccountBorrowFailed.into()
+    );
+}
+
+#[test]
+fn test_assert_min_len() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    shared.assert_min_len(16).unwrap();
+    assert_eq!(
+        shared.assert_min_len(24).unwrap_err(),
+        Error::from(ErrorCode::AccountDidNotDeserialize).with_values((16, 24))
+    );
+}
+
+#[error_code]
+pub enum SharedError {
+    #[msg("The shared account has not been set up")]
+    SharedMissing,
+}
+
+#[test]
+fn test_try_from_mapped() {
+    let key = Pubkey::new_unique();
+    let system = System::id();
+    let mut lamports = 0;
+    let mut data = Vec::new();
+    let info = account_info(&key, &system, false, false, &mut lamports, &mut data);
+
+    let map = |code| match code {
+        ErrorCode::AccountNotInitialized => SharedError::SharedMissing.into(),
+        code => code.into(),
+    };
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_mapped(&info, map)
+            .err()
+            .unwrap(),
+        SharedError::SharedMissing.into()
+    );
+
+    // Unmapped codes are passed through.
+    let mut lamports = 1;
+    let mut data = vec![0; 4];
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_mapped(&info, map)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountDiscriminatorNotFound.into()
+    );
+}
+
+#[account]
+pub struct SelfKeyed {
+    pub address: Pubkey,
+}
+
+#[test]
+fn test_assert_self_key() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    SelfKeyed { address: key }.try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut self_keyed = OrphanAccount::<SelfKeyed>::try_from(&info).unwrap();
+
+    self_keyed.assert_self_key(|s| &s.address).unwrap();
+    let stored = Pubkey::new_unique();
+    self_keyed.address = stored;
+    assert_eq!(
+        self_keyed.assert_self_key(|s| &s.address).unwrap_err(),
+        Error::from(ErrorCode::ConstraintAddress).with_pubkeys((stored, key))
+    );
+}
+
+#[cfg(feature = "anchor-debug")]
+#[test]
+fn test_debug_check_fee_payer() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    LOGS.with(|logs| logs.borrow_mut().clear());
+
+    shared.debug_check_fee_payer(&Pubkey::new_unique());
+    assert!(LOGS.with(|logs| logs.borrow().is_empty()));
+
+    shared.debug_check_fee_payer(&key);
+    assert_eq!(
+        LOGS.with(|logs| logs.borrow().clone()),
+        vec![format!(
+            "Warning: OrphanAccount {} is also the fee payer",
+            key
+        )]
+    );
+}
+
+#[account]
+pub struct TrustList {
+    pub owners: Vec<Pubkey>,
+}
+
+#[test]
+fn test_try_from_dynamic_owners() {
+    let (trusted, untrusted) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let config_key = Pubkey::new_unique();
+    let mut config_lamports = 1;
+    let mut config_data = Vec::new();
+    TrustList {
+        owners: vec![Pubkey::new_unique(), trusted],
+    }
+    .try_serialize(&mut config_data)
+    .unwrap();
+    let config_info = account_info(
+        &config_key,
+        &ID,
+        false,
+        false,
+        &mut config_lamports,
+        &mut config_data,
+    );
+    let config = OrphanAccount::<TrustList>::try_from(&config_info).unwrap();
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &trusted, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from_dynamic_owners(&info, &config.owners).unwrap();
+    assert_eq!(shared.value, 1);
+
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &untrusted, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_dynamic_owners(&info, &config.owners)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountOwnedByWrongProgram.into()
+    );
+}
+
+#[test]
+fn test_min_rent() {
+    let rent = Rent::default();
+    let expected = ((ACCOUNT_STORAGE_OVERHEAD + 8 + 8) * rent.lamports_per_byte_year) as f64
+        * rent.exemption_threshold;
+    assert_eq!(OrphanAccount::<Shared>::min_rent(&rent), expected as u64);
+}
+
+#[test]
+fn test_info() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    assert_eq!(shared.info().key, &key);
+    assert_eq!(shared.info().data_len(), 16);
+    assert_eq!(shared.info().key, AsRef::<AccountInfo>::as_ref(&shared).key);
+}
+
+#[test]
+fn test_try_from_resolver() {
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let owner = Pubkey::new_unique();
+    let (mut first_lamports, mut second_lamports) = (1, 1);
+    let (mut first_data, mut second_data) = (shared_data(1), shared_data(2));
+    let infos = [
+        account_info(
+            &first,
+            &owner,
+            false,
+            false,
+            &mut first_lamports,
+            &mut first_data,
+        ),
+        account_info(
+            &second,
+            &owner,
+            false,
+            false,
+            &mut second_lamports,
+            &mut second_data,
+        ),
+    ];
+
+    let shared = OrphanAccount::<Shared>::try_from_resolver(|| {
+        infos
+            .iter()
+            .find(|info| info.key == &second)
+            .cloned()
+            .ok_or_else(|| ErrorCode::AccountNotEnoughKeys.into())
+    })
+    .unwrap();
+    assert_eq!(shared.key(), second);
+    assert_eq!(shared.value, 2);
+}
+
+#[test]
+fn test_to_borsh_bytes() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    shared.value = 2;
+
+    let mut cached = shared.to_borsh_bytes().unwrap();
+    assert_eq!(cached, shared_data(2));
+    let mut lamports = 1;
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut cached);
+    assert_eq!(OrphanAccount::<Shared>::try_from(&info).unwrap().value, 2);
+}
+
+#[test]
+fn test_serialized_eq() {
+    let owner = Pubkey::new_unique();
+    let (key_a, key_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut lamports_a, mut lamports_b) = (1, 1);
+    let (mut data_a, mut data_b) = (shared_data(1), shared_data(1));
+    let info_a = account_info(&key_a, &owner, true, false, &mut lamports_a, &mut data_a);
+    let info_b = account_info(&key_b, &owner, true, false, &mut lamports_b, &mut data_b);
+    let a = OrphanAccount::<Shared>::try_from(&info_a).unwrap();
+    let mut b = OrphanAccount::<Shared>::try_from(&info_b).unwrap();
+
+    assert!(a.serialized_eq(&b).unwrap());
+    b.value = 2;
+    assert!(!a.serialized_eq(&b).unwrap());
+}
+
+#[account(discriminator_namespace = "custom")]
+pub struct Namespaced {
+    pub value: u64,
+}
+
+#[test]
+fn test_discriminator_namespace() {
+    assert_eq!(
+        Namespaced::discriminator(),
+        solana_program::hash::hash(b"custom:Namespaced").to_bytes()[..8]
+    );
+    assert_eq!(Namespaced::owner(), ID);
+
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Namespaced { value: 3 }.try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Namespaced>::try_from(&info).unwrap().value,
+        3
+    );
+
+    // Accounts with the default `account` namespace are rejected.
+    let mut lamports = 1;
+    let mut data = shared_data(3);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    #[cfg(not(feature = "legacy-fallback"))]
+    assert_eq!(
+        OrphanAccount::<Namespaced>::try_from(&info).err().unwrap(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+    #[cfg(feature = "legacy-fallback")]
+    assert!(OrphanAccount::<Namespaced>::try_from(&info)
+        .unwrap()
+        .is_legacy());
+}
+
+#[account(discriminator_hash = "blake3")]
+pub struct Blake3Hashed {
+    pub value: u64,
+}
+
+#[account(
+    zero_copy,
+    discriminator_namespace = "custom",
+    discriminator_hash = "blake3"
+)]
+pub struct Blake3ZeroCopy {
+    pub value: u64,
+}
+
+#[test]
+fn test_discriminator_args_combined() {
+    assert_eq!(
+        Blake3ZeroCopy::discriminator(),
+        solana_program::blake3::hash(b"custom:Blake3ZeroCopy").to_bytes()[..8]
+    );
+}
+
+#[test]
+fn test_discriminator_hash_blake3() {
+    assert_eq!(
+        Blake3Hashed::discriminator(),
+        solana_program::blake3::hash(b"account:Blake3Hashed").to_bytes()[..8]
+    );
+
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Blake3Hashed { value: 5 }.try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Blake3Hashed>::try_from(&info)
+            .unwrap()
+            .value,
+        5
+    );
+
+    // The SHA256 discriminator of the same name is rejected.
+    let mut lamports = 1;
+    let mut data = solana_program::hash::hash(b"account:Blake3Hashed").to_bytes()[..8].to_vec();
+    data.extend_from_slice(&5u64.to_le_bytes());
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    #[cfg(not(feature = "legacy-fallback"))]
+    assert_eq!(
+        OrphanAccount::<Blake3Hashed>::try_from(&info)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+    #[cfg(feature = "legacy-fallback")]
+    assert!(OrphanAccount::<Blake3Hashed>::try_from(&info)
+        .unwrap()
+        .is_legacy());
+}
+
+#[test]
+fn test_validate_owners() {
+    let (owner, other_owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let keys: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = [1; 6];
+    let mut datas = vec![shared_data(1); 6];
+    let mut infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(datas.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &owner, false, false, lamports, data))
+        .collect();
+    validate_owners(&infos, &owner).unwrap();
+
+    infos[4].owner = &other_owner;
+    let err = validate_owners(&infos, &owner).unwrap_err();
+    assert_eq!(
+        err,
+        Error::from(ErrorCode::AccountOwnedByWrongProgram).with_pubkeys((other_owner, owner))
+    );
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == "accounts[4]"
+    ));
+}
+
+#[account]
+#[derive(Default)]
+pub struct SharedV2 {
+    pub value: u64,
+    pub label: String,
+}
+
+#[test]
+fn test_migrate_into() {
+    let (old_key, new_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut old_lamports = 1;
+    let mut old_data = shared_data(7);
+    let old_info = account_info(&old_key, &ID, true, false, &mut old_lamports, &mut old_data);
+    let old = OrphanAccount::<Shared>::try_from(&old_info).unwrap();
+
+    let mut new_lamports = 1;
+    let mut new_data = Vec::new();
+    SharedV2::default().try_serialize(&mut new_data).unwrap();
+    new_data.resize(32, 0);
+    let new_info = account_info(&new_key, &ID, true, false, &mut new_lamports, &mut new_data);
+    let mut new = OrphanAccount::<SharedV2>::try_from(&new_info).unwrap();
+
+    let convert = |v1: &Shared| SharedV2 {
+        value: v1.value,
+        label: "v2".to_string(),
+    };
+    old.migrate_into(&mut new, convert).unwrap();
+    new.exit(&ID).unwrap();
+    let migrated =
+        SharedV2::try_deserialize(&mut &new_info.try_borrow_data().unwrap()[..]).unwrap();
+    assert_eq!((migrated.value, migrated.label.as_str()), (7, "v2"));
+
+    // A value that doesn't fit leaves the destination untouched.
+    let too_long = |v1: &Shared| SharedV2 {
+        value: v1.value,
+        label: "x".repeat(32),
+    };
+    assert_eq!(
+        old.migrate_into(&mut new, too_long).unwrap_err(),
+        Error::from(ErrorCode::AccountDidNotSerialize).with_values((52, 32))
+    );
+    assert_eq!(new.label, "v2");
+}
+
+#[cfg(feature = "legacy-fallback")]
+#[test]
+fn test_legacy_fallback() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert!(!shared.is_legacy());
+
+    // A legacy account has a header other than the discriminator.
+    let mut lamports = 1;
+    let mut data = shared_data(2);
+    data[..8].fill(0xFF);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert!(shared.is_legacy());
+    assert_eq!(shared.value, 2);
+
+    // Exiting normalizes the account to the current layout.
+    shared.exit(&ID).unwrap();
+    assert_eq!(info.try_borrow_data().unwrap()[..], shared_data(2)[..]);
+}
+
+#[test]
+fn test_weak_orphan() {
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let owner = Pubkey::new_unique();
+    let (mut first_lamports, mut second_lamports) = (1, 1);
+    let (mut first_data, mut second_data) = (shared_data(1), shared_data(2));
+    let infos = [
+        account_info(
+            &first,
+            &owner,
+            false,
+            false,
+            &mut first_lamports,
+            &mut first_data,
+        ),
+        account_info(
+            &second,
+            &owner,
+            true,
+            false,
+            &mut second_lamports,
+            &mut second_data,
+        ),
+    ];
+    let shared = OrphanAccount::<Shared>::try_from(&infos[1]).unwrap();
+    let weak = shared.downgrade();
+    drop(shared);
+    assert_eq!((weak.key(), *weak.owner()), (second, owner));
+
+    let upgraded = weak.upgrade(&infos).unwrap();
+    assert_eq!(upgraded.key(), second);
+    assert_eq!(upgraded.value, 2);
+
+    assert_eq!(
+        weak.upgrade(&infos[..1]).err().unwrap(),
+        ErrorCode::AccountNotEnoughKeys.into()
+    );
+    let other_owner = Pubkey::new_unique();
+    infos[1].assign(&other_owner);
+    assert_eq!(
+        weak.upgrade(&infos).err().unwrap(),
+        Error::from(ErrorCode::AccountOwnedByWrongProgram).with_pubkeys((other_owner, owner))
+    );
+}
+
+#[derive(Accounts)]
+#[snapshot_orphans]
+pub struct Snapshotted<'info> {
+    pub shared: OrphanAccount<'info, Shared>,
+    pub config: Option<OrphanAccount<'info, Shared>>,
+}
+
+#[test]
+fn test_snapshot_orphans() {
+    let (shared_key, config_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let owner = Pubkey::new_unique();
+    let (mut shared_lamports, mut config_lamports) = (1, 1);
+    let (mut shared_data, mut config_data) = (shared_data(1), shared_data(2));
+    let infos = [
+        account_info(
+            &shared_key,
+            &owner,
+            true,
+            false,
+            &mut shared_lamports,
+            &mut shared_data,
+        ),
+        account_info(
+            &config_key,
+            &owner,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+        ),
+    ];
+    let mut accounts = try_accounts::<Snapshotted>(&infos).unwrap();
+    let snapshot = accounts.snapshot_orphans();
+    assert_eq!(snapshot.shared.value, 1);
+    assert_eq!(snapshot.config.as_ref().map(|config| config.value), Some(2));
+
+    // Mock a CPI changing the shared account.
+    infos[0].try_borrow_mut_data().unwrap()[8..].copy_from_slice(&5u64.to_le_bytes());
+    accounts.shared.reload().unwrap();
+    assert_ne!(snapshot.shared.value, accounts.shared.value);
+    assert_eq!(
+        snapshot.config.map(|config| config.value),
+        accounts.config.as_ref().map(|config| config.value)
+    );
+}
+
+pub trait Plugin {
+    fn describe(&self) -> String;
+}
+
+#[account]
+pub struct FeePlugin {
+    pub fee: u64,
+}
+
+impl Plugin for FeePlugin {
+    fn describe(&self) -> String {
+        format!("fee {}", self.fee)
+    }
+}
+
+#[account]
+pub struct NamePlugin {
+    pub name: String,
+}
+
+impl Plugin for NamePlugin {
+    fn describe(&self) -> String {
+        format!("name {}", self.name)
+    }
+}
+
+#[test]
+fn test_dynamic_orphan() {
+    let mut deserializers: BTreeMap<[u8; 8], DynamicDeserializer<dyn Plugin>> = BTreeMap::new();
+    deserializers.insert(FeePlugin::discriminator(), |data| {
+        Ok(Box::new(FeePlugin::try_deserialize(&mut &data[..])?))
+    });
+    deserializers.insert(NamePlugin::discriminator(), |data| {
+        Ok(Box::new(NamePlugin::try_deserialize(&mut &data[..])?))
+    });
+
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    FeePlugin { fee: 3 }.try_serialize(&mut data).unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let plugin = DynamicOrphan::try_from(&info, &deserializers).unwrap();
+    assert_eq!(plugin.discriminator(), FeePlugin::discriminator());
+    assert_eq!(plugin.describe(), "fee 3");
+
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    NamePlugin {
+        name: "swap".to_string(),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let plugin = DynamicOrphan::try_from(&info, &deserializers).unwrap();
+    assert_eq!(plugin.describe(), "name swap");
+
+    // Unregistered types are rejected.
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        DynamicOrphan::try_from(&info, &deserializers).unwrap_err(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+}
+
+#[test]
+fn test_assert_freshly_created() {
+    let rent = Rent::default();
+    let key = Pubkey::new_unique();
+    let mut lamports = rent.minimum_balance(16);
+    let mut data = shared_data(0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    shared.assert_freshly_created(&rent).unwrap();
+
+    // Written to since creation.
+    let mut lamports = rent.minimum_balance(16);
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert_eq!(
+        shared.assert_freshly_created(&rent).unwrap_err(),
+        ErrorCode::AccountNotFreshlyCreated.into()
+    );
+
+    // Funded since creation.
+    let mut lamports = rent.minimum_balance(16) + 1;
+    let mut data = shared_data(0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert_eq!(
+        shared.assert_freshly_created(&rent).unwrap_err(),
+        ErrorCode::AccountNotFreshlyCreated.into()
+    );
+}
+
+#[cfg(feature = "anchor-debug")]
+#[test]
+fn test_debug_unmutated_writable_warning() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    LOGS.with(|logs| logs.borrow_mut().clear());
+
+    shared.exit(&ID).unwrap();
+    assert_eq!(
+        LOGS.with(|logs| logs.borrow().clone()),
+        vec![format!(
+            "Warning: OrphanAccount {} is writable but was not mutated",
+            key
+        )]
+    );
+
+    LOGS.with(|logs| logs.borrow_mut().clear());
+    shared.value = 2;
+    shared.exit(&ID).unwrap();
+    assert!(LOGS.with(|logs| logs.borrow().is_empty()));
+}
+
+#[account]
+pub struct Selected {
+    pub writer_ix: [u8; 8],
+    pub value: u64,
+}
+
+impl WriteSelector for Selected {
+    fn write_selector(&self) -> Option<[u8; 8]> {
+        Some(self.writer_ix).filter(|selector| *selector != [0; 8])
+    }
+}
+
+fn instructions_data(ix_data: &[u8]) -> Vec<u8> {
+    use solana_program::sysvar::instructions::{construct_instructions_data, BorrowedInstruction};
+    construct_instructions_data(&[BorrowedInstruction {
+        program_id: &ID,
+        accounts: vec![],
+        data: ix_data,
+    }])
+}
+
+#[test]
+fn test_selector_gated() {
+    let allowed = [7; 8];
+    let key = Pubkey::new_unique();
+    let sysvar_key = solana_program::sysvar::instructions::ID;
+    let sysvar_owner = solana_program::sysvar::ID;
+
+    for (ix_data, selector, ok) in [
+        ([7; 8], allowed, true),
+        ([8; 8], allowed, false),
+        ([8; 8], [0; 8], true),
+    ] {
+        let mut sysvar_lamports = 1;
+        let mut sysvar_data = instructions_data(&ix_data);
+        let sysvar = account_info(
+            &sysvar_key,
+            &sysvar_owner,
+            false,
+            false,
+            &mut sysvar_lamports,
+            &mut sysvar_data,
+        );
+        let mut lamports = 1;
+        let mut data = Vec::new();
+        Selected {
+            writer_ix: selector,
+            value: 1,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+        let mut selected =
+            OrphanAccount::<Selected>::try_from_selector_gated(&info, &sysvar).unwrap();
+
+        // Unmodified accounts can be exited by any instruction.
+        selected.exit(&ID).unwrap();
+        selected.value = 2;
+        match ok {
+            true => selected.exit(&ID).unwrap(),
+            false => assert_eq!(
+                selected.exit(&ID).unwrap_err(),
+                ErrorCode::AccountWrongInstruction.into()
+            ),
+        }
+    }
+}
+
+#[account(label = "Shared vault", category = "Treasury")]
+pub struct LabeledVault {
+    pub balance: u64,
+}
+
+#[account(category = "Treasury")]
+pub struct Categorized {
+    pub balance: u64,
+}
+
+#[test]
+fn test_metadata() {
+    assert_eq!(
+        OrphanAccount::<LabeledVault>::metadata(),
+        AccountMetadata {
+            label: Some("Shared vault"),
+            category: Some("Treasury"),
+        }
+    );
+    assert_eq!(
+        OrphanAccount::<Categorized>::metadata(),
+        AccountMetadata {
+            label: None,
+            category: Some("Treasury"),
+        }
+    );
+}
+
+#[account]
+pub struct Journal {
+    pub entries: Vec<u64>,
+}
+
+impl LogicalLen for Journal {
+    fn logical_len(&self) -> usize {
+        8 + 4 + 8 * self.entries.len()
+    }
+}
+
+impl Truncate for Journal {
+    fn truncate_to(&mut self, len: usize) {
+        self.entries.truncate(len.saturating_sub(8 + 4) / 8);
+    }
+}
+
+#[test]
+fn test_truncate() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Journal {
+        entries: vec![1, 2, 3, 4, 5],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data.resize(128, 0xAA);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut journal = OrphanAccount::<Journal>::try_from(&info).unwrap();
+
+    assert_eq!(
+        journal.truncate(128).unwrap_err(),
+        ErrorCode::AccountDidNotSerialize.into()
+    );
+    journal.truncate(8 + 4 + 2 * 8).unwrap();
+    assert_eq!(journal.entries, vec![1, 2]);
+    journal.exit(&ID).unwrap();
+
+    let data = info.try_borrow_data().unwrap();
+    assert_eq!(data.len(), 128);
+    assert!(data[8 + 4 + 2 * 8..].iter().all(|byte| *byte == 0));
+    drop(data);
+    assert_eq!(
+        OrphanAccount::<Journal>::try_from(&info).unwrap().entries,
+        vec![1, 2]
+    );
+}
+
+#[test]
+fn test_logical_len() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Journal {
+        entries: vec![1, 2, 3],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data.resize(128, 0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut journal = OrphanAccount::<Journal>::try_from(&info).unwrap();
+
+    assert_eq!(journal.logical_len().unwrap(), 8 + 4 + 3 * 8);
+    assert!(journal.logical_len().unwrap() < info.data_len());
+
+    journal.entries = vec![0; 15];
+    assert_eq!(
+        journal.logical_len().unwrap_err(),
+        ErrorCode::AccountDidNotSerialize.into()
+    );
+}
+
+#[account]
+pub struct Pool {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+}
+
+impl HasReferences for Pool {
+    fn references(&self) -> Vec<Pubkey> {
+        vec![self.vault, self.mint]
+    }
+}
+
+#[test]
+fn test_validate_refs() {
+    let (key, vault, mint) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let (mut lamports, mut vault_lamports, mut mint_lamports) = (1, 1, 1);
+    let mut data = Vec::new();
+    Pool { vault, mint }.try_serialize(&mut data).unwrap();
+    let (mut vault_data, mut mint_data) = (vec![], vec![]);
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    let vault_info = account_info(
+        &vault,
+        &ID,
+        false,
+        false,
+        &mut vault_lamports,
+        &mut vault_data,
+    );
+    let mint_info = account_info(&mint, &ID, false, false, &mut mint_lamports, &mut mint_data);
+    let pool = OrphanAccount::<Pool>::try_from(&info).unwrap();
+
+    pool.validate_refs(&[&mint_info, &info, &vault_info])
+        .unwrap();
+    let err = pool.validate_refs(&[&info, &vault_info]).unwrap_err();
+    assert_eq!(err, ErrorCode::AccountNotEnoughKeys.into());
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == mint.to_string()
+    ));
+}
+
+#[account]
+pub struct Nonced {
+    pub nonce: u64,
+    pub value: u64,
+}
+
+impl orphan_account::Versioned for Nonced {
+    fn version_mut(&mut self) -> &mut u64 {
+        &mut self.nonce
+    }
+}
+
+#[test]
+fn test_version_bump() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Nonced { nonce: 0, value: 1 }
+        .try_serialize(&mut data)
+        .unwrap();
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let stored = || OrphanAccount::<Nonced>::try_from(&info).unwrap();
+    let mut nonced = OrphanAccount::<Nonced>::try_from(&info)
+        .unwrap()
+        .with_version_bump();
+
+    // Unchanged accounts keep their version.
+    nonced.exit(&ID).unwrap();
+    assert_eq!(stored().nonce, 0);
+
+    nonced.value = 2;
+    nonced.exit(&ID).unwrap();
+    nonced.exit(&ID).unwrap();
+    assert_eq!((stored().nonce, stored().value), (1, 2));
+
+    nonced.value = 3;
+    nonced.exit(&ID).unwrap();
+    assert_eq!((stored().nonce, stored().value), (2, 3));
+}
+
+// Serializes `UpgradeableLoaderState::ProgramData` the way bincode does.
+fn program_data(authority: Option<Pubkey>) -> Vec<u8> {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&1u64.to_le_bytes());
+    match authority {
+        Some(authority) => {
+            data.push(1);
+            data.extend_from_slice(authority.as_ref());
+        }
+        None => data.push(0),
+    }
+    data
+}
+
+#[test]
+fn test_assert_owner_upgrade_authority() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let loader = solana_program::bpf_loader_upgradeable::ID;
+    let (program_data_key, _) = Pubkey::find_program_address(&[owner.as_ref()], &loader);
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    let check = |program_data_key: &Pubkey, stored: Option<Pubkey>| {
+        let mut lamports = 1;
+        let mut data = program_data(stored);
+        let program_data = account_info(
+            program_data_key,
+            &loader,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+        );
+        shared.assert_owner_upgrade_authority(&program_data, &authority)
+    };
+
+    check(&program_data_key, Some(authority)).unwrap();
+    assert_eq!(
+        check(&program_data_key, Some(Pubkey::new_unique())).unwrap_err(),
+        ErrorCode::AccountUpgradeAuthorityMismatch.into()
+    );
+    assert_eq!(
+        check(&program_data_key, None).unwrap_err(),
+        ErrorCode::AccountUpgradeAuthorityMismatch.into()
+    );
+    assert_eq!(
+        check(&Pubkey::new_unique(), Some(authority)).unwrap_err(),
+        ErrorCode::AccountNotProgramData.into()
+    );
+}
+
+impl Checksummed for Shared {
+    fn checksum(data: &[u8]) -> Vec<u8> {
+        let mut sum = [0u8; 4];
+        for (i, byte) in data.iter().enumerate() {
+            sum[i % 4] ^= byte.rotate_left(i as u32);
+        }
+        sum.to_vec()
+    }
+}
+
+fn checksummed_data(value: u64) -> Vec<u8> {
+    let mut data = shared_data(value);
+    let checksum = <Shared as Checksummed>::checksum(&data);
+    data.extend_from_slice(&checksum);
+    data
+}
+
+#[test]
+fn test_checksummed() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = checksummed_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from_checksummed(&info).unwrap();
+    assert_eq!(shared.value, 1);
+
+    // Exits keep the trailer valid.
+    shared.value = 2;
+    shared.exit(&ID).unwrap();
+    assert_eq!(
+        &info.try_borrow_data().unwrap()[..],
+        &checksummed_data(2)[..]
+    );
+
+    info.try_borrow_mut_data().unwrap()[8] ^= 0xFF;
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_checksummed(&info)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountChecksumMismatch.into()
+    );
+
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_checksummed(&info)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+}
+
+#[test]
+fn test_cpi_return_buffer() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut buffer = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    let ix = solana_program::instruction::Instruction {
+        program_id: returner::ID,
+        accounts: buffer.to_account_metas(None),
+        data: 42u64.to_le_bytes().to_vec(),
+    };
+    solana_program::program::invoke(&ix, &buffer.to_account_infos()).unwrap();
+    assert_eq!(buffer.value, 0);
+    buffer.reload().unwrap();
+    assert_eq!(buffer.value, 42);
+
+    // Exiting after the reload keeps the result.
+    buffer.exit(&ID).unwrap();
+    assert_eq!(&info.try_borrow_data().unwrap()[..], &shared_data(42)[..]);
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn test_fuzz_deserialize() {
+    let malformed: Vec<Vec<u8>> = vec![
+        vec![],
+        vec![0xFF; 3],
+        Shared::discriminator()[..5].to_vec(),
+        Shared::discriminator().to_vec(),
+        shared_data(1)[..12].to_vec(),
+        vec![0xFF; 12],
+    ];
+    for data in malformed {
+        assert!(OrphanAccount::<Shared>::fuzz_deserialize(&data).is_err());
+        assert!(OrphanAccount::<Journal>::fuzz_deserialize(&data).is_err());
+    }
+
+    let mut huge_vec = Journal::discriminator().to_vec();
+    huge_vec.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert!(OrphanAccount::<Journal>::fuzz_deserialize(&huge_vec).is_err());
+    assert_eq!(
+        OrphanAccount::<Shared>::fuzz_deserialize(&shared_data(3))
+            .ok()
+            .unwrap()
+            .value,
+        3
+    );
+}
+
+#[account]
+pub struct TaggedPool {
+    pub kind: u8,
+    pub value: u64,
+}
+
+impl Tagged for TaggedPool {
+    const TAG: u8 = 1;
+
+    fn tag_offset() -> usize {
+        8
+    }
+}
+
+#[test]
+fn test_try_from_tagged() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    for (kind, ok) in [(1, true), (2, false)] {
+        let mut lamports = 1;
+        let mut data = Vec::new();
+        TaggedPool { kind, value: 5 }
+            .try_serialize(&mut data)
+            .unwrap();
+        let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+        match ok {
+            true => assert_eq!(
+                OrphanAccount::<TaggedPool>::try_from_tagged(&info)
+                    .unwrap()
+                    .value,
+                5
+            ),
+            false => assert_eq!(
+                OrphanAccount::<TaggedPool>::try_from_tagged(&info)
+                    .err()
+                    .unwrap(),
+                ErrorCode::AccountDiscriminatorMismatch.into()
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_with_raw_data_mut() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(5);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    let len = shared
+        .with_raw_data_mut(|data| {
+            data[8] = 7;
+            data.len()
+        })
+        .unwrap();
+    assert_eq!(len, 16);
+
+    // The stale in-memory value is not written over the poked byte.
+    assert_eq!(shared.value, 5);
+    shared.exit(&ID).unwrap();
+    assert_eq!(info.try_borrow_data().unwrap()[8], 7);
+
+    // Reloading picks up the poked byte and restores the write back.
+    shared.reload().unwrap();
+    assert_eq!(shared.value, 7);
+    shared.value = 9;
+    shared.exit(&ID).unwrap();
+    assert_eq!(OrphanAccount::<Shared>::try_from(&info).unwrap().value, 9);
+}
+
+#[test]
+fn test_with_raw_data_mut_checksummed() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = checksummed_data(5);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from_checksummed(&info)
+        .unwrap()
+        .with_mutation_events();
+    shared
+        .with_raw_data_mut(|data| data[8..16].copy_from_slice(&7u64.to_le_bytes()))
+        .unwrap();
+    LOGGED_DATA.with(|logged| logged.borrow_mut().clear());
+
+    // The trailer is rewritten for the poked value and the write is reported.
+    shared.exit(&ID).unwrap();
+    assert_eq!(
+        &info.try_borrow_data().unwrap()[..],
+        &checksummed_data(7)[..]
+    );
+    let shared = OrphanAccount::<Shared>::try_from_checksummed(&info).unwrap();
+    assert_eq!(shared.value, 7);
+    let expected = OrphanMutated {
+        key,
+        discriminator: Shared::discriminator(),
+    };
+    assert_eq!(
+        LOGGED_DATA.with(|logged| logged.borrow().clone()),
+        vec![Event::data(&expected)]
+    );
+}
+
+#[account]
+pub struct Stamped {
+    pub last_modified: i64,
+    pub value: u64,
+}
+
+impl orphan_account::Timestamped for Stamped {
+    fn last_modified_mut(&mut self) -> &mut i64 {
+        &mut self.last_modified
+    }
+}
+
+#[test]
+fn test_timestamp() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Stamped {
+        last_modified: 0,
+        value: 1,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let stored = || OrphanAccount::<Stamped>::try_from(&info).unwrap();
+    let mut stamped = OrphanAccount::<Stamped>::try_from(&info)
+        .unwrap()
+        .with_timestamp();
+
+    // Unchanged accounts keep their timestamp.
+    stamped.exit(&ID).unwrap();
+    assert_eq!(stored().last_modified, 0);
+
+    stamped.value = 2;
+    stamped.exit(&ID).unwrap();
+    assert_eq!((stored().last_modified, stored().value), (NOW, 2));
+    assert_eq!(stamped.last_modified, 0);
+}
+
+#[test]
+fn test_try_from_allow_zero_lamports() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = shared_data(5);
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_allow_zero_lamports(&info)
+            .unwrap()
+            .value,
+        5
+    );
+
+    // The lamports no longer reject a drained system account, but its
+    // discriminator is still checked.
+    let mut lamports = 0;
+    let mut data = shared_data(5);
+    let system = System::id();
+    let info = account_info(&key, &system, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from(&info).err().unwrap(),
+        ErrorCode::AccountClosed.into()
+    );
+    assert!(OrphanAccount::<Shared>::try_from_allow_zero_lamports(&info).is_ok());
+    #[cfg(not(feature = "legacy-fallback"))]
+    assert_eq!(
+        OrphanAccount::<Versioned>::try_from_allow_zero_lamports(&info)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountDiscriminatorMismatch.into()
+    );
+
+    let mut lamports = 0;
+    let mut data = anchor_lang::__private::CLOSED_ACCOUNT_DISCRIMINATOR.to_vec();
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Shared>::try_from_allow_zero_lamports(&info)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountClosed.into()
+    );
+}
+
+#[test]
+fn test_lamport_delta() {
+    let treasury_key = Pubkey::new_unique();
+    let payer_key = Pubkey::new_unique();
+    let mut treasury_lamports = 100;
+    let mut payer_lamports = 50;
+    let mut treasury_data = shared_data(0);
+    let mut payer_data = shared_data(0);
+    let treasury_info = account_info(
+        &treasury_key,
+        &ID,
+        true,
+        false,
+        &mut treasury_lamports,
+        &mut treasury_data,
+    );
+    let payer_info = account_info(
+        &payer_key,
+        &ID,
+        true,
+        false,
+        &mut payer_lamports,
+        &mut payer_data,
+    );
+    let treasury = OrphanAccount::<Shared>::try_from(&treasury_info).unwrap();
+    let payer = OrphanAccount::<Shared>::try_from(&payer_info).unwrap();
+    assert_eq!(treasury.lamport_delta(), 0);
+
+    **payer_info.lamports.borrow_mut() -= 30;
+    **treasury_info.lamports.borrow_mut() += 30;
+    assert_eq!(treasury.lamport_delta(), 30);
+    assert_eq!(payer.lamport_delta(), -30);
+    assert_eq!(treasury.lamport_delta() + payer.lamport_delta(), 0);
+}
+
+// The derived borsh impls bound every field type, which rustc can't resolve
+// for a directly recursive type, so `Node` is serialized by hand.
+#[derive(Clone)]
+pub struct Node {
+    pub value: u8,
+    pub child: Option<orphan_account::Nested<Node>>,
+}
+
+impl AnchorSerialize for Node {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.value.serialize(writer)?;
+        self.child.serialize(writer)
+    }
+}
+
+impl AnchorDeserialize for Node {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        Ok(Node {
+            value: u8::deserialize(buf)?,
+            child: Option::deserialize(buf)?,
+        })
+    }
+}
+
+#[account]
+pub struct Tree {
+    pub root: orphan_account::Nested<Node>,
+}
+
+fn node_data(depth: usize) -> Vec<u8> {
+    let mut node = Node {
+        value: 0,
+        child: None,
+    };
+    for value in 1..depth as u8 {
+        node = Node {
+            value,
+            child: Some(orphan_account::Nested(Box::new(node))),
+        };
+    }
+    let mut data = Vec::new();
+    Tree {
+        root: orphan_account::Nested(Box::new(node)),
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data
+}
+
+#[test]
+fn test_try_from_max_depth() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = node_data(16);
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Tree>::try_from_max_depth(&info, 8)
+            .err()
+            .unwrap(),
+        ErrorCode::AccountNestingTooDeep.into()
+    );
+    let tree = OrphanAccount::<Tree>::try_from_max_depth(&info, 16).unwrap();
+    assert_eq!(tree.root.value, 15);
+    assert_eq!(tree.root.child.as_ref().unwrap().value, 14);
+
+    // The default maximum applies again afterwards.
+    assert!(OrphanAccount::<Tree>::try_from(&info).is_ok());
+    let mut lamports = 1;
+    let mut data = node_data(DEFAULT_MAX_DEPTH + 1);
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    assert_eq!(
+        OrphanAccount::<Tree>::try_from(&info).err().unwrap(),
+        ErrorCode::AccountDidNotDeserialize.into()
+    );
+}
+
+#[test]
+fn test_reload_and_diff() {
+    let owner = Pubkey::new_unique();
+    let (shared_key, trusted_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut shared_lamports, mut trusted_lamports) = (1, 1);
+    let mut shared_data = shared_data(1);
+    let mut trusted_data = Vec::new();
+    Trusted { value: 2 }
+        .try_serialize(&mut trusted_data)
+        .unwrap();
+    let shared_info = account_info(
+        &shared_key,
+        &owner,
+        true,
+        false,
+        &mut shared_lamports,
+        &mut shared_data,
+    );
+    let trusted_info = account_info(
+        &trusted_key,
+        &owner,
+        true,
+        false,
+        &mut trusted_lamports,
+        &mut trusted_data,
+    );
+    let mut shared = OrphanAccount::<Shared>::try_from(&shared_info).unwrap();
+    let mut trusted = OrphanAccount::<Trusted>::try_from(&trusted_info).unwrap();
+
+    // Simulate a CPI changing only the trusted account.
+    trusted_info.try_borrow_mut_data().unwrap()[8..].copy_from_slice(&20u64.to_le_bytes());
+    let changed = reload_and_diff(&mut [&mut shared, &mut trusted]).unwrap();
+    assert_eq!(changed, vec![trusted_key]);
+    assert_eq!((shared.value, trusted.value), (1, 20));
+    assert!(reload_and_diff(&mut [&mut shared, &mut trusted])
+        .unwrap()
+        .is_empty());
+}
+
+#[account]
+pub struct Sparse {
+    pub values: Vec<u8>,
+}
+
+// Run length encodes bytes as pairs of a count and the repeated byte.
+impl Compressed for Sparse {
+    fn compress(body: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        for byte in body {
+            match compressed.len() {
+                len if len >= 2
+                    && compressed[len - 1] == *byte
+                    && compressed[len - 2] < u8::MAX =>
+                {
+                    compressed[len - 2] += 1
+                }
+                _ => compressed.extend_from_slice(&[1, *byte]),
+            }
+        }
+        compressed
+    }
+
+    fn decompress(compressed: &[u8]) -> Result<Vec<u8>> {
+        if compressed.len() % 2 != 0 {
+            return Err(ErrorCode::AccountDidNotDeserialize.into());
+        }
+        Ok(compressed
+            .chunks(2)
+            .flat_map(|run| std::iter::repeat(run[1]).take(run[0] as usize))
+            .collect())
+    }
+}
+
+#[test]
+fn test_compression_codec() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut values = vec![0; 200];
+    values[0] = 9;
+    let mut data = Vec::new();
+    <Compression as Codec<Sparse>>::serialize(&Sparse { values }, &mut data).unwrap();
+    // The discriminator, the length and two runs each for the length prefix
+    // and the values.
+    assert_eq!(data.len(), 8 + 4 + 4 * 2);
+    data.resize(64, 0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+
+    let mut sparse = OrphanAccount::<Sparse, Compression>::try_from(&info).unwrap();
+    assert_eq!(sparse.values.len(), 200);
+    assert_eq!((sparse.values[0], sparse.values[1]), (9, 0));
+    assert!(!sparse.is_dirty().unwrap());
+
+    sparse.values[199] = 7;
+    sparse.exit(&ID).unwrap();
+    let sparse = OrphanAccount::<Sparse, Compression>::try_from(&info).unwrap();
+    assert_eq!(
+        (sparse.values[0], sparse.values[198], sparse.values[199]),
+        (9, 0, 7)
+    );
+    assert_eq!(info.try_borrow_data().unwrap()[8..12], 10u32.to_le_bytes());
+}
+
+#[account]
+pub struct Gate {
+    pub open: bool,
+}
+
+#[derive(Accounts)]
+pub struct Enter<'info> {
+    pub gate: OrphanAccount<'info, Gate>,
+}
+
+fn check_gate(ctx: &Context<Enter>, owner: &Pubkey) -> Result<()> {
+    require!(ctx.accounts.gate.open, ErrorCode::ConstraintRaw);
+    require_keys_eq!(*ctx.accounts.gate.info().owner, *owner);
+    Ok(())
+}
+
+#[access_control(check_gate(&ctx, owner))]
+fn enter(ctx: Context<Enter>, owner: &Pubkey) -> Result<()> {
+    Ok(())
+}
+
+#[test]
+fn test_access_control() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    for (open, gate_owner, ok) in [
+        (true, owner, true),
+        (false, owner, false),
+        (true, ID, false),
+    ] {
+        let mut lamports = 1;
+        let mut data = Vec::new();
+        Gate { open }.try_serialize(&mut data).unwrap();
+        let infos = [account_info(
+            &key,
+            &gate_owner,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+        )];
+        let mut accounts = try_accounts::<Enter>(&infos).unwrap();
+        let ctx = Context::new(&ID, &mut accounts, &[], BTreeMap::new());
+        assert_eq!(enter(ctx, &owner).is_ok(), ok);
+    }
+}
+
+#[test]
+fn test_return_value() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(5);
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    shared.return_value().unwrap();
+    assert_eq!(
+        RETURN_DATA.with(|returned| returned.borrow().clone()),
+        5u64.to_le_bytes()
+    );
+
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Sparse {
+        values: vec![0; 1024],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    let sparse = OrphanAccount::<Sparse>::try_from(&info).unwrap();
+    assert_eq!(
+        sparse.return_value().unwrap_err(),
+        ErrorCode::AccountReturnDataTooLarge.into()
+    );
+}
+
+#[derive(Accounts)]
+pub struct Aliased<'info> {
+    #[account(mut)]
+    pub orphan: OrphanAccount<'info, Shared>,
+    #[account(mut)]
+    pub account: Account<'info, Shared>,
+}
+
+#[cfg(feature = "anchor-debug")]
+#[test]
+fn test_debug_alias_warning() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    let warning = |key: &Pubkey| {
+        format!(
+            "Warning: writable fields orphan and account alias account {}",
+            key
+        )
+    };
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    LOGS.with(|logs| logs.borrow_mut().clear());
+    try_accounts::<Aliased>(&[info.clone(), info]).unwrap();
+    assert!(LOGS.with(|logs| logs.borrow().contains(&warning(&key))));
+
+    let other_key = Pubkey::new_unique();
+    let mut other_lamports = 1;
+    let mut other_data = shared_data(1);
+    let infos = [
+        account_info(&key, &ID, true, false, &mut lamports, &mut data),
+        account_info(
+            &other_key,
+            &ID,
+            true,
+            false,
+            &mut other_lamports,
+            &mut other_data,
+        ),
+    ];
+    LOGS.with(|logs| logs.borrow_mut().clear());
+    try_accounts::<Aliased>(&infos).unwrap();
+    assert!(!LOGS.with(|logs| logs
+        .borrow()
+        .iter()
+        .any(|log| log.starts_with("Warning: writable fields"))));
+}
+
+#[test]
+fn test_assert_order() {
+    let owner = Pubkey::new_unique();
+    let route: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = [1; 3];
+    let mut datas = vec![shared_data(1); 3];
+    let infos: Vec<AccountInfo> = route
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(datas.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &owner, false, false, lamports, data))
+        .collect();
+    let pools: Vec<OrphanAccount<Shared>> = infos
+        .iter()
+        .map(|info| OrphanAccount::try_from(info).unwrap())
+        .collect();
+    assert_order(&pools, &route).unwrap();
+
+    let name_of = |err: Error| match err {
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) => name,
+        _ => panic!("unnamed error"),
+    };
+    let reordered = [route[0], route[2], route[1]];
+    let err = assert_order(&pools, &reordered).unwrap_err();
+    assert_eq!(
+        err,
+        Error::from(ErrorCode::ConstraintAddress).with_pubkeys((route[1], route[2]))
+    );
+    assert_eq!(name_of(err), "accounts[1]");
+
+    let err = assert_order(&pools[..2], &route).unwrap_err();
+    assert_eq!(err, ErrorCode::AccountNotEnoughKeys.into());
+    assert_eq!(name_of(err), "accounts[2]");
+    assert_eq!(
+        assert_order(&pools, &route[..2]).unwrap_err(),
+        ErrorCode::AccountTooManyKeys.into()
+    );
+}
+
+#[test]
+fn test_init_or_load() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    let (owner, system) = (System::id(), System::id());
+    let (key, payer_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut lamports, mut payer_lamports) = (0, 10_000_000);
+    let (mut data, mut payer_data) = (Vec::new(), Vec::new());
+    let mut info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut payer = account_info(
+        &payer_key,
+        &system,
+        true,
+        false,
+        &mut payer_lamports,
+        &mut payer_data,
+    );
+
+    info.is_signer = true;
+    payer.is_signer = true;
+
+    // The first call creates the account.
+    let created =
+        OrphanAccount::<Shared>::init_or_load(&info, &payer, 16, &ID, || Shared { value: 3 })
+            .unwrap();
+    let rent = Rent::default().minimum_balance(16);
+    assert_eq!(created.value, 3);
+    assert_eq!(info.owner, &ID);
+    assert_eq!(
+        (info.lamports(), payer.lamports()),
+        (rent, 10_000_000 - rent)
+    );
+    assert_eq!(&info.try_borrow_data().unwrap()[..], &shared_data(3)[..]);
+
+    // Later calls load it.
+    info.try_borrow_mut_data().unwrap()[8..].copy_from_slice(&4u64.to_le_bytes());
+    let loaded = OrphanAccount::<Shared>::init_or_load(&info, &payer, 16, &ID, || {
+        panic!("the account exists")
+    })
+    .unwrap();
+    assert_eq!(loaded.value, 4);
+    assert_eq!(payer.lamports(), 10_000_000 - rent);
+}
+
+#[test]
+fn test_init_or_load_prefunded() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    let (owner, system) = (System::id(), System::id());
+    let (key, payer_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    // Someone sent lamports to the address before it was created.
+    let (mut lamports, mut payer_lamports) = (1_000, 10_000_000);
+    let (mut data, mut payer_data) = (Vec::new(), Vec::new());
+    let mut info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+    let mut payer = account_info(
+        &payer_key,
+        &system,
+        true,
+        false,
+        &mut payer_lamports,
+        &mut payer_data,
+    );
+
+    info.is_signer = true;
+    payer.is_signer = true;
+
+    let created =
+        OrphanAccount::<Shared>::init_or_load(&info, &payer, 16, &ID, || Shared { value: 3 })
+            .unwrap();
+    let rent = Rent::default().minimum_balance(16);
+    assert_eq!(created.value, 3);
+    assert_eq!(info.owner, &ID);
+    // Only the missing rent is paid.
+    assert_eq!(
+        (info.lamports(), payer.lamports()),
+        (rent, 10_000_000 - (rent - 1_000))
+    );
+    assert_eq!(&info.try_borrow_data().unwrap()[..], &shared_data(3)[..]);
+}
+
+#[account]
+pub struct Ledger {
+    pub owner: Pubkey,
+    pub entries: Vec<u64>,
+}
+
+impl StreamSerialize for Ledger {
+    fn stream_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let write = |writer: &mut W, bytes: &[u8]| {
+            writer
+                .write_all(bytes)
+                .map_err(|_| Error::from(ErrorCode::AccountDidNotSerialize))
+        };
+        write(writer, &Ledger::discriminator())?;
+        write(writer, self.owner.as_ref())?;
+        write(writer, &(self.entries.len() as u32).to_le_bytes())?;
+        for entry in &self.entries {
+            write(writer, &entry.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_streamed() {
+    let ledger = Ledger {
+        owner: Pubkey::new_unique(),
+        entries: (0..100).collect(),
+    };
+    let mut expected = Vec::new();
+    ledger.try_serialize(&mut expected).unwrap();
+    let mut streamed = Vec::new();
+    <Streamed as Codec<Ledger>>::serialize(&ledger, &mut streamed).unwrap();
+    assert_eq!(streamed, expected);
+
+    // Exiting streams the changed ledger into the account.
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = expected.clone();
+    data.resize(expected.len() + 8, 0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut account = OrphanAccount::<Ledger, Streamed>::try_from(&info).unwrap();
+    account.entries.push(100);
+    account.exit(&ID).unwrap();
+    let mut expected = Vec::new();
+    account.try_serialize(&mut expected).unwrap();
+    assert_eq!(&info.try_borrow_data().unwrap()[..], &expected[..]);
+}
+
+#[test]
+fn test_reload_checked() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    info.try_borrow_mut_data().unwrap()[8..].copy_from_slice(&2u64.to_le_bytes());
+    shared.reload_checked().unwrap();
+    assert_eq!(shared.value, 2);
+
+    // Simulate the account being closed and re-created as another type.
+    let mut retyped = Vec::new();
+    Trusted { value: 3 }.try_serialize(&mut retyped).unwrap();
+    info.try_borrow_mut_data()
+        .unwrap()
+        .copy_from_slice(&retyped);
+    assert_eq!(
+        shared.reload_checked().unwrap_err(),
+        ErrorCode::AccountTypeChanged.into()
+    );
+    assert_eq!(shared.value, 2);
+}
+
+#[test]
+fn test_growth_needed_for() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Journal {
+        entries: vec![1, 2],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    data.resize(8 + 4 + 3 * 8, 0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let journal = OrphanAccount::<Journal>::try_from(&info).unwrap();
+
+    // Room is left for a third entry, so only the fourth and fifth grow it.
+    let fits = Journal {
+        entries: vec![1, 2, 3],
+    };
+    assert_eq!(journal.growth_needed_for(&fits).unwrap(), 0);
+    let grows = Journal {
+        entries: vec![1, 2, 3, 4, 5],
+    };
+    assert_eq!(journal.growth_needed_for(&grows).unwrap(), 2 * 8);
+}
+
+#[test]
+fn test_lamport_snapshot() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 100;
+    let mut data = shared_data(0);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let account = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    assert_eq!(
+        account.lamport_snapshot(),
+        LamportSnapshot { key, lamports: 100 }
+    );
+
+    **info.lamports.borrow_mut() -= 40;
+    assert_eq!(account.snapshot_lamports(), 100);
+    assert_eq!(account.current_lamports(), 60);
+    assert_eq!(LamportSnapshot::capture(&info).lamports, 60);
+}
+
+#[test]
+fn test_assert_not_in() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(0);
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    let account = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    let mut reserved = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    account.assert_not_in(&reserved).unwrap();
+    account.assert_not_in(&[]).unwrap();
+
+    reserved.push(key);
+    assert_eq!(
+        account.assert_not_in(&reserved).unwrap_err(),
+        ErrorCode::ConstraintAddress.into()
+    );
+}
+
+#[derive(Accounts)]
+pub struct SeededVault<'info> {
+    /// CHECK: Only used as a seed.
+    pub authority: UncheckedAccount<'info>,
+    #[account(seeds = [b"vault", authority.key().as_ref()], bump)]
+    pub vault: OrphanAccount<'info, Shared>,
+}
+
+#[test]
+fn test_client_orphan_seeds() {
+    let authority = Pubkey::new_unique();
+    let client = __client_accounts_seeded_vault::SeededVault {
+        authority,
+        vault: Pubkey::default(),
+    };
+    let seeds = client.vault_seeds();
+    assert_eq!(
+        seeds,
+        vec![b"vault".to_vec(), authority.to_bytes().to_vec()]
+    );
+
+    // The address derived off-chain passes the on-chain seeds constraint.
+    let seeds: Vec<&[u8]> = seeds.iter().map(|s| &s[..]).collect();
+    let (vault, _) = Pubkey::find_program_address(&seeds, &ID);
+    let owner = Pubkey::new_unique();
+    let (mut authority_lamports, mut vault_lamports) = (1, 1);
+    let mut authority_data = Vec::new();
+    let mut vault_data = shared_data(3);
+    let infos = [
+        account_info(
+            &authority,
+            &owner,
+            false,
+            false,
+            &mut authority_lamports,
+            &mut authority_data,
+        ),
+        account_info(
+            &vault,
+            &owner,
+            false,
+            false,
+            &mut vault_lamports,
+            &mut vault_data,
+        ),
+    ];
+    let accounts = try_accounts::<SeededVault>(&infos).ok().unwrap();
+    assert_eq!(accounts.vault.value, 3);
+}
+
+#[test]
+fn test_assert_size_in() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+
+    shared.assert_size_in(&[16, 32, 64]).unwrap();
+    assert_eq!(
+        shared.assert_size_in(&[24, 32]).unwrap_err(),
+        Error::from(ErrorCode::AccountDidNotDeserialize).with_values((16, "[24, 32]"))
+    );
+}
+
+#[cfg(feature = "client")]
+pub struct GeyserUpdate {
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub slot: u64,
+}
+
+#[cfg(feature = "client")]
+impl orphan_account::AccountUpdate for GeyserUpdate {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn owner(&self) -> Pubkey {
+        self.owner
+    }
+
+    fn lamports(&self) -> u64 {
+        self.lamports
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_try_from_geyser_update() {
+    let mut update = GeyserUpdate {
+        pubkey: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        lamports: 1,
+        data: shared_data(9),
+        slot: 42,
+    };
+    let shared = OrphanAccount::<Shared>::try_from_geyser_update(&update).unwrap();
+    assert_eq!(shared.value, 9);
+
+    // A closed account shows up as a zero lamport update owned by the system
+    // program.
+    update.owner = System::id();
+    update.lamports = 0;
+    update.data = Vec::new();
+    let err = OrphanAccount::<Shared>::try_from_geyser_update(&update)
+        .err()
+        .unwrap();
+    assert_eq!(err, ErrorCode::AccountNotInitialized.into());
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == update.pubkey.to_string()
+    ));
+}
+
+#[test]
+fn test_require_authority_signer() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let mut lamports = [1; 3];
+    let mut data = Vec::new();
+    Authorized { authority }.try_serialize(&mut data).unwrap();
+    let (mut authority_data, mut other_data) = (Vec::new(), Vec::new());
+    let [lamports, authority_lamports, other_lamports] = &mut lamports;
+    let info = account_info(&key, &owner, false, false, lamports, &mut data);
+    let account = OrphanAccount::<Authorized>::try_from(&info).unwrap();
+    let mut authority_info = account_info(
+        &authority,
+        &owner,
+        false,
+        false,
+        authority_lamports,
+        &mut authority_data,
+    );
+    let other_info = account_info(
+        &other,
+        &owner,
+        false,
+        false,
+        other_lamports,
+        &mut other_data,
+    );
+
+    assert_eq!(
+        account
+            .require_authority_signer(std::slice::from_ref(&other_info))
+            .unwrap_err(),
+        ErrorCode::AccountNotEnoughKeys.into()
+    );
+    // The authority is passed but didn't sign.
+    assert_eq!(
+        account
+            .require_authority_signer(&[other_info.clone(), authority_info.clone()])
+            .unwrap_err(),
+        ErrorCode::ConstraintSigner.into()
+    );
+    authority_info.is_signer = true;
+    account
+        .require_authority_signer(&[other_info, authority_info])
+        .unwrap();
+}
+
+static EXTRACTED: AtomicUsize = AtomicUsize::new(0);
+
+fn extract_value(data: &[u8]) -> Result<u64> {
+    EXTRACTED.fetch_add(1, Ordering::SeqCst);
+    u64::deserialize(&mut &data[8..]).map_err(Into::into)
+}
+
+#[test]
+fn test_cached_orphan() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(7);
+    let info = account_info(&key, &owner, true, false, &mut lamports, &mut data);
+
+    let mut cached = CachedOrphan::new(&info, extract_value);
+    assert!(!cached.is_cached());
+    assert_eq!(EXTRACTED.load(Ordering::SeqCst), 0);
+    for _ in 0..3 {
+        assert_eq!(*cached.get().unwrap(), 7);
+    }
+    assert_eq!(EXTRACTED.load(Ordering::SeqCst), 1);
+
+    // The cache is stale until invalidated.
+    info.try_borrow_mut_data().unwrap()[8..].copy_from_slice(&9u64.to_le_bytes());
+    assert_eq!(*cached.get().unwrap(), 7);
+    cached.invalidate();
+    assert_eq!(*cached.get().unwrap(), 9);
+    assert_eq!(EXTRACTED.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_swap_contents() {
+    let (first_key, second_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut first_lamports, mut second_lamports) = (1, 1);
+    let (mut first_data, mut second_data) = (shared_data(1), shared_data(2));
+    let first_info = account_info(
+        &first_key,
+        &ID,
+        true,
+        false,
+        &mut first_lamports,
+        &mut first_data,
+    );
+    let second_info = account_info(
+        &second_key,
+        &ID,
+        true,
+        false,
+        &mut second_lamports,
+        &mut second_data,
+    );
+    let mut first = OrphanAccount::<Shared>::try_from(&first_info).unwrap();
+    let mut second = OrphanAccount::<Shared>::try_from(&second_info).unwrap();
+    swap_contents(&mut first, &mut second).unwrap();
+    exit_all(&[&first, &second], &ID).unwrap();
+    drop((first, second));
+
+    assert_eq!(first_data, shared_data(2));
+    assert_eq!(second_data, shared_data(1));
+}
+
+#[test]
+fn test_swap_contents_too_large() {
+    let (short_key, long_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut short_lamports, mut long_lamports) = (1, 1);
+    let (mut short_data, mut long_data) = (Vec::new(), Vec::new());
+    Journal { entries: vec![1] }
+        .try_serialize(&mut short_data)
+        .unwrap();
+    Journal {
+        entries: vec![1, 2, 3],
+    }
+    .try_serialize(&mut long_data)
+    .unwrap();
+    let short_info = account_info(
+        &short_key,
+        &ID,
+        true,
+        false,
+        &mut short_lamports,
+        &mut short_data,
+    );
+    let long_info = account_info(
+        &long_key,
+        &ID,
+        true,
+        false,
+        &mut long_lamports,
+        &mut long_data,
+    );
+    let mut short = OrphanAccount::<Journal>::try_from(&short_info).unwrap();
+    let mut long = OrphanAccount::<Journal>::try_from(&long_info).unwrap();
+
+    assert_eq!(
+        swap_contents(&mut short, &mut long).unwrap_err(),
+        ErrorCode::AccountDidNotSerialize.into()
+    );
+    assert_eq!(short.entries, vec![1]);
+    assert_eq!(long.entries, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_assert_in_filter() {
+    let allowed: Vec<Pubkey> = (0..64).map(|i| Pubkey::new_from_array([i; 32])).collect();
+    let mut filter = BloomFilter::new(4096, 4);
+    for key in &allowed {
+        filter.insert(key);
+    }
+    // The filter survives a round trip through a config account.
+    let filter = BloomFilter::try_from_slice(&filter.try_to_vec().unwrap()).unwrap();
+
+    let owner = Pubkey::new_unique();
+    for key in &allowed {
+        let mut lamports = 1;
+        let mut data = shared_data(0);
+        let info = account_info(key, &owner, false, false, &mut lamports, &mut data);
+        let account = OrphanAccount::<Shared>::try_from(&info).unwrap();
+        account.assert_in_filter(&filter).unwrap();
+    }
+
+    // With 4096 bits for 64 keys, false positives are rare enough that none
+    // of these hit.
+    for i in 64..80 {
+        let key = Pubkey::new_from_array([i; 32]);
+        let mut lamports = 1;
+        let mut data = shared_data(0);
+        let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+        let account = OrphanAccount::<Shared>::try_from(&info).unwrap();
+        assert_eq!(
+            account.assert_in_filter(&filter).unwrap_err(),
+            ErrorCode::ConstraintAddress.into()
+        );
+    }
+}
+
+#[account]
+pub struct Reserved {
+    pub version: u8,
+    pub gap: [u8; 7],
+    pub value: u64,
+    pub reserved: [u8; 16],
+}
+
+impl Padded for Reserved {
+    fn padding_ranges() -> Vec<Range<usize>> {
+        vec![9..16, 24..40]
+    }
+}
+
+#[test]
+fn test_try_from_padded() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Reserved {
+        version: 1,
+        gap: [0; 7],
+        value: 4,
+        reserved: [0; 16],
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+    let account = OrphanAccount::<Reserved>::try_from_padded(&info, true).unwrap();
+    assert_eq!(account.value, 4);
+
+    info.try_borrow_mut_data().unwrap()[28] = 1;
+    assert_eq!(
+        OrphanAccount::<Reserved>::try_from_padded(&info, true)
+            .err()
+            .unwrap(),
+        Error::from(ErrorCode::AccountPaddingNotZero).with_values((28, "24..40"))
+    );
+    // Padding is ignored unless asked otherwise.
+    let account = OrphanAccount::<Reserved>::try_from_padded(&info, false).unwrap();
+    assert_eq!(account.reserved[4], 1);
+}
+
+#[event]
+pub struct Deposited {
+    pub amount: u64,
+}
+
+#[test]
+fn test_emit_for() {
+    solana_program::program_stubs::set_syscall_stubs(Box::new(TestStubs));
+
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(1);
+    let info = account_info(&key, &ID, false, false, &mut lamports, &mut data);
+    let shared = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    LOGGED_DATA.with(|logged| logged.borrow_mut().clear());
+
+    shared.emit_for(Deposited { amount: 5 });
+    let logged = LOGGED_DATA.with(|logged| logged.borrow().clone());
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0][..8], OrphanEvent::discriminator());
+    let emitted = OrphanEvent::try_from_slice(&logged[0][8..]).unwrap();
+    assert_eq!(emitted.key, key);
+    assert_eq!(emitted.event, Event::data(&Deposited { amount: 5 }));
+}
+
+#[test]
+fn test_try_from_min_version() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Vec::new();
+    Versioned {
+        version: 1,
+        value: 5,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+
+    assert_eq!(
+        OrphanAccount::<Versioned>::try_from_min_version(&info, 2)
+            .err()
+            .unwrap(),
+        Error::from(ErrorCode::AccountVersionUnsupported).with_values((1, "2.."))
+    );
+
+    // An account at the floor is accepted, read at the current version and
+    // upgraded on exit.
+    let versioned = OrphanAccount::<Versioned>::try_from_min_version(&info, 1).unwrap();
+    assert_eq!(versioned.version, 2);
+    assert!(versioned.is_dirty().unwrap());
+    versioned.exit(&ID).unwrap();
+    assert!(!versioned.is_dirty().unwrap());
+    assert_eq!(info.try_borrow_data().unwrap()[8], 2);
+    let versioned = OrphanAccount::<Versioned>::try_from_min_version(&info, 2).unwrap();
+    assert_eq!((versioned.version, versioned.value), (2, 5));
+
+    // Data written directly is upgraded too.
+    info.try_borrow_mut_data().unwrap()[8] = 1;
+    let mut versioned = OrphanAccount::<Versioned>::try_from_min_version(&info, 1).unwrap();
+    versioned
+        .with_raw_data_mut(|data| data[9..17].copy_from_slice(&7u64.to_le_bytes()))
+        .unwrap();
+    versioned.exit(&ID).unwrap();
+    let versioned = OrphanAccount::<Versioned>::try_from_min_version(&info, 2).unwrap();
+    assert_eq!((versioned.version, versioned.value), (2, 7));
+}
+
+#[test]
+fn test_orphan_transaction() {
+    let (shared_key, journal_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut shared_lamports, mut journal_lamports) = (1, 1);
+    let mut data = shared_data(1);
+    let mut journal_data = Vec::new();
+    Journal { entries: vec![1] }
+        .try_serialize(&mut journal_data)
+        .unwrap();
+    let shared_info = account_info(
+        &shared_key,
+        &ID,
+        true,
+        false,
+        &mut shared_lamports,
+        &mut data,
+    );
+    let journal_info = account_info(
+        &journal_key,
+        &ID,
+        true,
+        false,
+        &mut journal_lamports,
+        &mut journal_data,
+    );
+    let mut shared = OrphanAccount::<Shared>::try_from(&shared_info).unwrap();
+    let mut journal = OrphanAccount::<Journal>::try_from(&journal_info).unwrap();
+    shared.value = 2;
+    journal.entries.push(2);
+
+    // The journal no longer fits, so neither account is written.
+    let mut transaction = OrphanTransaction::new();
+    transaction.stage(&shared).stage(&journal);
+    let err = transaction.commit(&ID).unwrap_err();
+    assert_eq!(err, ErrorCode::AccountDidNotSerialize.into());
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == journal_key.to_string()
+    ));
+    assert_eq!(
+        shared_info.try_borrow_data().unwrap()[..],
+        shared_data(1)[..]
+    );
+
+    journal.entries.pop();
+    let mut transaction = OrphanTransaction::new();
+    transaction.stage(&shared).stage(&journal);
+    transaction.commit(&ID).unwrap();
+    assert_eq!(
+        shared_info.try_borrow_data().unwrap()[..],
+        shared_data(2)[..]
+    );
+
+    // A version that can't be bumped fails before anything is written too.
+    let nonced_key = Pubkey::new_unique();
+    let mut nonced_lamports = 1;
+    let mut nonced_data = Vec::new();
+    Nonced {
+        nonce: u64::MAX,
+        value: 1,
+    }
+    .try_serialize(&mut nonced_data)
+    .unwrap();
+    let nonced_info = account_info(
+        &nonced_key,
+        &ID,
+        true,
+        false,
+        &mut nonced_lamports,
+        &mut nonced_data,
+    );
+    let mut nonced = OrphanAccount::<Nonced>::try_from(&nonced_info)
+        .unwrap()
+        .with_version_bump();
+    nonced.value = 2;
+    shared.value = 3;
+    let mut transaction = OrphanTransaction::new();
+    transaction.stage(&shared).stage(&nonced);
+    let err = transaction.commit(&ID).unwrap_err();
+    assert_eq!(err, ErrorCode::AccountDidNotSerialize.into());
+    assert!(matches!(
+        err,
+        Error::AnchorError(AnchorError {
+            error_origin: Some(ErrorOrigin::AccountName(name)),
+            ..
+        }) if name == nonced_key.to_string()
+    ));
+    assert_eq!(
+        shared_info.try_borrow_data().unwrap()[..],
+        shared_data(2)[..]
+    );
+}
+
+#[test]
+fn test_orphan_transaction_wrong_owner() {
+    let (key, foreign_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut lamports, mut foreign_lamports) = (1, 1);
+    let (mut data, mut foreign_data) = (shared_data(1), shared_data(1));
+    let foreign_owner = Pubkey::new_unique();
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let foreign_info = account_info(
+        &foreign_key,
+        &foreign_owner,
+        true,
+        false,
+        &mut foreign_lamports,
+        &mut foreign_data,
+    );
+    let mut account = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    let mut foreign = OrphanAccount::<Shared>::try_from(&foreign_info).unwrap();
+    account.value = 2;
+    foreign.value = 2;
+
+    let mut transaction = OrphanTransaction::new();
+    transaction.stage(&account).stage(&foreign);
+    assert_eq!(
+        transaction.commit(&ID).unwrap_err(),
+        ErrorCode::AccountOwnedByWrongProgram.into()
+    );
+    assert_eq!(info.try_borrow_data().unwrap()[..], shared_data(1)[..]);
+}
+
+#[test]
+fn test_exit_with_invariant() {
+    let (first_key, second_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut first_lamports, mut second_lamports) = (1, 1);
+    let (mut first_data, mut second_data) = (shared_data(60), shared_data(40));
+    let first_info = account_info(
+        &first_key,
+        &ID,
+        true,
+        false,
+        &mut first_lamports,
+        &mut first_data,
+    );
+    let second_info = account_info(
+        &second_key,
+        &ID,
+        true,
+        false,
+        &mut second_lamports,
+        &mut second_data,
+    );
+    let mut first = OrphanAccount::<Shared>::try_from(&first_info).unwrap();
+    let mut second = OrphanAccount::<Shared>::try_from(&second_info).unwrap();
+    let conserved = |a: &Shared, b: &Shared| a.value + b.value == 100;
+
+    // Only one side of the transfer was applied.
+    first.value -= 10;
+    assert_eq!(
+        first
+            .exit_with_invariant(&second, &ID, conserved)
+            .unwrap_err(),
+        ErrorCode::AccountInvariantViolated.into()
+    );
+    assert_eq!(
+        first_info.try_borrow_data().unwrap()[..],
+        shared_data(60)[..]
+    );
+
+    second.value += 10;
+    first.exit_with_invariant(&second, &ID, conserved).unwrap();
+    assert_eq!(
+        first_info.try_borrow_data().unwrap()[..],
+        shared_data(50)[..]
+    );
+    assert_eq!(
+        second_info.try_borrow_data().unwrap()[..],
+        shared_data(50)[..]
+    );
+}
+
+#[test]
+fn test_exit_with_invariant_same_account() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = shared_data(60);
+    let info = account_info(&key, &ID, true, false, &mut lamports, &mut data);
+    let mut first = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    let mut second = OrphanAccount::<Shared>::try_from(&info).unwrap();
+    first.value -= 10;
+    second.value += 10;
+
+    // Both values pass the invariant, but only one could be written.
+    let err = first
+        .exit_with_invariant(&second, &ID, |a: &Shared, b: &Shared| {
+            a.value + b.value == 120
+        })
+        .unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintDuplicateMutableAccount.into());
+    assert_eq!(info.try_borrow_data().unwrap()[..], shared_data(60)[..]);
+}
+
+// The key a `Sealed` body is xored with.
+pub struct SealKey(pub u8);
+
+#[account]
+pub struct Sealed {
+    pub value: u64,
+}
+
+impl ContextualDeserialize<SealKey> for Sealed {
+    fn deserialize_with_ctx(data: &[u8], ctx: &SealKey) -> Result<Self> {
+        if data.get(..8) != Some(&Sealed::discriminator()[..]) {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        let body: Vec<u8> = data[8..].iter().map(|byte| byte ^ ctx.0).collect();
+        Sealed::deserialize(&mut &body[..]).map_err(Into::into)
+    }
+}
+
+#[test]
+fn test_try_from_with_ctx() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = Sealed::discriminator().to_vec();
+    data.extend(7u64.to_le_bytes().iter().map(|byte| byte ^ 0x5A));
+    let info = account_info(&key, &owner, false, false, &mut lamports, &mut data);
+
+    let sealed = OrphanAccount::<Sealed>::try_from_with_ctx(&info, &SealKey(0x5A)).unwrap();
+    assert_eq!(sealed.value, 7);
+    // Without the context, the body reads as garbage.
+    let unsealed = OrphanAccount::<Sealed>::try_from(&info).unwrap();
+    assert_ne!(unsealed.value, 7);
+}
+
+#[cfg(feature = "orphan-profiler")]
+thread_local! {
+    static REMAINING_UNITS: std::cell::Cell<u64> = std::cell::Cell::new(200_000);
+    static PROFILED: RefCell<Vec<(Pubkey, u64)>> = RefCell::new(Vec::new());
+}
+
+// Charges 150 units per reading, so every deserialization costs 150.
+#[cfg(feature = "orphan-profiler")]
+fn mock_remaining_units() -> u64 {
+    REMAINING_UNITS.with(|units| {
+        units.set(units.get() - 150);
+        units.get()
+    })
+}
+
+#[cfg(feature = "orphan-profiler")]
+struct MockSink;
+
+#[cfg(feature = "orphan-profiler")]
+impl orphan_account::ProfilerSink for MockSink {
+    fn record(&self, key: &Pubkey, compute_units: u64) {
+        PROFILED.with(|profiled| profiled.borrow_mut().push((*key, compute_units)));
+    }
+}
+
+#[cfg(feature = "orphan-profiler")]
+#[test]
+fn test_profiler_sink() {
+    let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let owner = Pubkey::new_unique();
+    let mut lamports = [1; 3];
+    let mut data: Vec<Vec<u8>> = (0..3).map(shared_data).collect();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| account_info(key, &owner, false, false, lamports, data))
+        .collect();
+
+    // Nothing is recorded until a sink is installed.
+    OrphanAccount::<Shared>::try_from(&infos[0]).unwrap();
+    assert!(orphan_account::set_profiler_sink(Box::new(MockSink), mock_remaining_units).is_none());
+    for info in &infos {
+        OrphanAccount::<Shared>::try_from(info).unwrap();
+    }
+    assert!(orphan_account::take_profiler_sink().is_some());
+    OrphanAccount::<Shared>::try_from(&infos[0]).unwrap();
+
+    assert_eq!(
+        PROFILED.with(|profiled| profiled.borrow().clone()),
+        keys.iter().map(|key| (*key, 150)).collect::<Vec<_>>()
+    );
+}
@@ -1,9 +1,11 @@
 use anchor_lang::solana_program::account_info::AccountInfo;
 
+use anchor_lang::accounts::orphan_account::OrphanAccount;
+use anchor_lang::error::{Error, ErrorCode};
 use anchor_lang::solana_program::program_pack::Pack;
 use anchor_lang::solana_program::pubkey::Pubkey;
 use anchor_lang::{context::CpiContext, Accounts};
-use anchor_lang::{solana_program, Result};
+use anchor_lang::{solana_program, Key, Result, ToAccountInfos};
 use std::ops::Deref;
 
 pub use spl_token;
@@ -377,6 +379,78 @@ impl Deref for TokenAccount {
     }
 }
 
+/// A [`TokenAccount`] read through an [`OrphanAccount`], for token accounts
+/// passed to a program that doesn't know them up front, e.g. in
+/// `remaining_accounts`.
+///
+/// Unlike a bare `OrphanAccount<TokenAccount>`, the account is checked to be
+/// owned by the token program on construction.
+#[derive(Clone, Debug)]
+pub struct OrphanTokenAccount<'info>(OrphanAccount<'info, TokenAccount>);
+
+impl<'info> OrphanTokenAccount<'info> {
+    /// Deserializes the SPL token account layout from `info`, failing with
+    /// [`ErrorCode::AccountOwnedByWrongProgram`] if it isn't owned by the
+    /// token program.
+    pub fn try_from(info: &AccountInfo<'info>) -> Result<OrphanTokenAccount<'info>> {
+        if info.owner != &ID {
+            return Err(
+                Error::from(ErrorCode::AccountOwnedByWrongProgram).with_pubkeys((*info.owner, ID))
+            );
+        }
+        OrphanAccount::try_from(info).map(OrphanTokenAccount)
+    }
+
+    /// Returns the number of tokens held by the account.
+    pub fn amount(&self) -> u64 {
+        self.0.amount
+    }
+
+    /// Returns the mint of the tokens held by the account.
+    pub fn mint(&self) -> Pubkey {
+        self.0.mint
+    }
+
+    /// Returns the owner of the tokens, i.e. the authority of the token
+    /// account rather than the program owning it.
+    pub fn owner(&self) -> Pubkey {
+        self.0.owner
+    }
+
+    /// Checks the account holds tokens of the `expected` mint, failing with
+    /// [`ErrorCode::ConstraintTokenMint`] otherwise.
+    pub fn require_mint(&self, expected: &Pubkey) -> Result<()> {
+        if self.0.mint != *expected {
+            return Err(
+                Error::from(ErrorCode::ConstraintTokenMint).with_pubkeys((self.0.mint, *expected))
+            );
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> TokenAccount {
+        self.0.into_inner()
+    }
+}
+
+impl<'info> ToAccountInfos<'info> for OrphanTokenAccount<'info> {
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        self.0.to_account_infos()
+    }
+}
+
+impl<'info> AsRef<AccountInfo<'info>> for OrphanTokenAccount<'info> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        self.0.info()
+    }
+}
+
+impl<'info> Key for OrphanTokenAccount<'info> {
+    fn key(&self) -> Pubkey {
+        self.0.key()
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Mint(spl_token::state::Mint);
 
@@ -0,0 +1,86 @@
+#![cfg(feature = "token")]
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::Epoch;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::{spl_token, OrphanTokenAccount, ID};
+
+fn token_account_data(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+    let account = spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(account, &mut data).unwrap();
+    data
+}
+
+fn account_info<'a>(
+    key: &'a Pubkey,
+    owner: &'a Pubkey,
+    lamports: &'a mut u64,
+    data: &'a mut [u8],
+) -> AccountInfo<'a> {
+    AccountInfo::new(
+        key,
+        false,
+        false,
+        lamports,
+        data,
+        owner,
+        false,
+        Epoch::default(),
+    )
+}
+
+#[test]
+fn test_orphan_token_account() {
+    let (key, mint, authority) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut lamports = 1;
+    let mut data = token_account_data(mint, authority, 42);
+    let info = account_info(&key, &ID, &mut lamports, &mut data);
+
+    let token = OrphanTokenAccount::try_from(&info).unwrap();
+    assert_eq!(token.key(), key);
+    assert_eq!(token.amount(), 42);
+    assert_eq!(token.mint(), mint);
+    assert_eq!(token.owner(), authority);
+}
+
+#[test]
+fn test_orphan_token_account_wrong_owner() {
+    let key = Pubkey::new_unique();
+    let other_program = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = token_account_data(Pubkey::new_unique(), Pubkey::new_unique(), 42);
+    let info = account_info(&key, &other_program, &mut lamports, &mut data);
+
+    assert_eq!(
+        OrphanTokenAccount::try_from(&info).unwrap_err(),
+        Error::from(ErrorCode::AccountOwnedByWrongProgram).with_pubkeys((other_program, ID))
+    );
+}
+
+#[test]
+fn test_require_mint() {
+    let (key, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut lamports = 1;
+    let mut data = token_account_data(mint, Pubkey::new_unique(), 1);
+    let info = account_info(&key, &ID, &mut lamports, &mut data);
+    let token = OrphanTokenAccount::try_from(&info).unwrap();
+
+    token.require_mint(&mint).unwrap();
+    let other_mint = Pubkey::new_unique();
+    assert_eq!(
+        token.require_mint(&other_mint).unwrap_err(),
+        Error::from(ErrorCode::ConstraintTokenMint).with_pubkeys((mint, other_mint))
+    );
+}
@@ -337,6 +337,11 @@ export const LangErrorCode = {
   ConstraintMintFreezeAuthority: 2017,
   ConstraintMintDecimals: 2018,
   ConstraintSpace: 2019,
+  ConstraintNotExecutable: 2020,
+  ConstraintDenySelfOwner: 2021,
+  ConstraintRequiredIf: 2022,
+  ConstraintFlags: 2023,
+  ConstraintDuplicateMutableAccount: 2024,
 
   // Require.
   RequireViolated: 2500,
@@ -366,6 +371,25 @@ export const LangErrorCode = {
   AccountSysvarMismatch: 3015,
   AccountReallocExceedsLimit: 3016,
   AccountDuplicateReallocs: 3017,
+  AccountTooManyKeys: 3018,
+  AccountInsufficientLamports: 3019,
+  AccountIndexOutOfRange: 3020,
+  AccountDiscriminatorCollision: 3021,
+  AccountClosed: 3022,
+  AccountNotEmpty: 3023,
+  AccountVersionUnsupported: 3024,
+  AccountLamportsNotConserved: 3025,
+  AccountWriteSealed: 3026,
+  AccountNotFreshlyCreated: 3027,
+  AccountWrongInstruction: 3028,
+  AccountUpgradeAuthorityMismatch: 3029,
+  AccountChecksumMismatch: 3030,
+  AccountNestingTooDeep: 3031,
+  AccountReturnDataTooLarge: 3032,
+  AccountTypeChanged: 3033,
+  AccountPaddingNotZero: 3034,
+  AccountFieldNotFound: 3035,
+  AccountInvariantViolated: 3036,
 
   // State.
   StateInvalidAddress: 4000,
@@ -442,6 +466,20 @@ export const LangErrorMessage = new Map([
     "A mint decimals constraint was violated",
   ],
   [LangErrorCode.ConstraintSpace, "A space constraint was violated"],
+  [
+    LangErrorCode.ConstraintNotExecutable,
+    "A non-executable constraint was violated",
+  ],
+  [
+    LangErrorCode.ConstraintDenySelfOwner,
+    "A deny self owner constraint was violated",
+  ],
+  [LangErrorCode.ConstraintRequiredIf, "A required_if constraint was violated"],
+  [LangErrorCode.ConstraintFlags, "A flags constraint was violated"],
+  [
+    LangErrorCode.ConstraintDuplicateMutableAccount,
+    "A duplicate mutable account constraint was violated",
+  ],
 
   // Require.
   [LangErrorCode.RequireViolated, "A require expression was violated"],
@@ -513,6 +551,82 @@ export const LangErrorMessage = new Map([
     LangErrorCode.AccountDuplicateReallocs,
     "The account was duplicated for more than one reallocation",
   ],
+  [
+    LangErrorCode.AccountTooManyKeys,
+    "Too many account keys given to the instruction",
+  ],
+  [
+    LangErrorCode.AccountInsufficientLamports,
+    "The account has fewer lamports than required",
+  ],
+  [
+    LangErrorCode.AccountIndexOutOfRange,
+    "The index is out of range for the account",
+  ],
+  [
+    LangErrorCode.AccountDiscriminatorCollision,
+    "The account discriminator is already registered to another type",
+  ],
+  [
+    LangErrorCode.AccountClosed,
+    "The account appears to have been closed or drained of its lamports",
+  ],
+  [
+    LangErrorCode.AccountNotEmpty,
+    "The account is not empty and cannot be closed",
+  ],
+  [
+    LangErrorCode.AccountVersionUnsupported,
+    "The account version is not supported",
+  ],
+  [
+    LangErrorCode.AccountLamportsNotConserved,
+    "The total lamports of the accounts changed",
+  ],
+  [
+    LangErrorCode.AccountWriteSealed,
+    "The account can only be written by its designated writer program",
+  ],
+  [
+    LangErrorCode.AccountNotFreshlyCreated,
+    "The account was not freshly created",
+  ],
+  [
+    LangErrorCode.AccountWrongInstruction,
+    "The account can not be written by this instruction",
+  ],
+  [
+    LangErrorCode.AccountUpgradeAuthorityMismatch,
+    "The program owning the account has an unexpected upgrade authority",
+  ],
+  [
+    LangErrorCode.AccountChecksumMismatch,
+    "The account checksum does not match its content",
+  ],
+  [
+    LangErrorCode.AccountNestingTooDeep,
+    "The account data is nested deeper than allowed",
+  ],
+  [
+    LangErrorCode.AccountReturnDataTooLarge,
+    "The account is too large for the return data",
+  ],
+  [
+    LangErrorCode.AccountTypeChanged,
+    "The account was re-initialized with a different type",
+  ],
+  [
+    LangErrorCode.AccountPaddingNotZero,
+    "The reserved padding of the account is not zeroed",
+  ],
+  [
+    LangErrorCode.AccountFieldNotFound,
+    "The account has no field at the given path",
+  ],
+  [
+    LangErrorCode.AccountInvariantViolated,
+    "The accounts violate an invariant they must uphold jointly",
+  ],
 
   // State.
   [
@@ -56,6 +56,7 @@ export type IdlAccount = {
   name: string;
   isMut: boolean;
   isSigner: boolean;
+  isOptional?: boolean;
   docs?: string[];
   pda?: IdlPda;
 };
@@ -90,6 +91,12 @@ export type IdlAccountDef = {
   name: string;
   docs?: string[];
   type: IdlTypeDefTyStruct;
+  metadata?: IdlAccountMetadata;
+};
+
+export type IdlAccountMetadata = {
+  label?: string;
+  category?: string;
 };
 
 export type IdlTypeDefTyStruct = {
@@ -85,6 +85,16 @@ export class AccountsResolver<IDL extends Idl, I extends AllInstructions<IDL>> {
         continue;
       }
     }
+
+    for (let k = 0; k < this._idlIx.accounts.length; k += 1) {
+      const accountDesc = this._idlIx.accounts[k] as IdlAccount;
+      const accountDescName = camelCase(accountDesc.name);
+
+      // Absent optional accounts are passed as the program id.
+      if (accountDesc.isOptional && !this._accounts[accountDescName]) {
+        this._accounts[accountDescName] = this._programId;
+      }
+    }
   }
 
   private async autoPopulatePda(accountDesc: IdlAccount) {